@@ -0,0 +1,284 @@
+mod progress;
+mod summary;
+#[cfg(feature = "tui")]
+mod tui;
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use clap::{Args, Parser, Subcommand};
+use serde_json::Value;
+
+use atomic_bomb_engine::core::execute;
+use atomic_bomb_engine::core::report::save_json_report;
+use atomic_bomb_engine::core::validate::dry_run;
+use atomic_bomb_engine::core::test_plan::{load_test_plan, run_test_plan};
+use atomic_bomb_engine::models::assert_option::AssertOption;
+use atomic_bomb_engine::models::batch_option::BatchOption;
+use atomic_bomb_engine::models::connection_option::ConnectionOption;
+use atomic_bomb_engine::models::dns_option::DnsOption;
+use atomic_bomb_engine::models::engine_error::{localized, set_language, Language};
+use atomic_bomb_engine::models::result::BatchResult;
+use atomic_bomb_engine::models::test_plan::TestPlan;
+use atomic_bomb_engine::models::threshold::ThresholdFailure;
+use atomic_bomb_engine::models::tls_option::TlsOption;
+use atomic_bomb_engine::models::proxy_option::ProxyOption;
+#[cfg(feature = "tui")]
+use std::sync::Arc;
+#[cfg(feature = "tui")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "tui")]
+use atomic_bomb_engine::core::batch_control::BatchControl;
+
+// 阈值未通过时的退出码
+const THRESHOLD_FAILED_EXIT_CODE: u8 = 99;
+
+#[derive(Parser)]
+#[command(name = "atomic-bomb-engine", version, about = "高性能压测工具")]
+struct Cli {
+    #[arg(long, global = true, default_value = "zh", help = "错误信息的语言(zh/en)")]
+    lang: Language,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    // 对单个url压测，参数与execute::run一致
+    #[command(about = "对单个url进行压测")]
+    Run(Box<RunArgs>),
+    // 按测试计划压测，参数与batch::batch一致
+    #[command(about = "按yaml/toml测试计划进行压测")]
+    Plan(PlanArgs),
+}
+
+#[derive(Args)]
+struct RunArgs {
+    #[arg(help = "压测的url")]
+    url: String,
+    #[arg(short = 'd', long = "duration", default_value_t = 10, help = "压测时长(秒)")]
+    test_duration_secs: u64,
+    #[arg(short = 'c', long = "concurrency", default_value_t = 10, help = "并发数")]
+    concurrent_requests: i32,
+    #[arg(short = 't', long = "timeout", default_value_t = 0, help = "请求超时时间(秒)，0表示不超时")]
+    timeout_secs: u64,
+    #[arg(short = 'm', long, default_value = "GET", help = "请求方法")]
+    method: String,
+    #[arg(long, help = "json请求体")]
+    json: Option<String>,
+    #[arg(long, help = "form请求体，例如: a=1&b=2")]
+    form: Option<String>,
+    #[arg(short = 'H', long = "header", help = "请求头，例如: \"Content-Type: text/plain\"，可以重复使用")]
+    headers: Vec<String>,
+    #[arg(short = 'b', long, help = "cookie")]
+    cookie: Option<String>,
+    #[arg(long = "assert", num_args = 2, value_names = ["JSONPATH", "VALUE"], help = "断言，例如: --assert '$.code' 200，可以重复使用")]
+    asserts: Vec<String>,
+    #[arg(long, help = "压测期间阻止电脑休眠")]
+    prevent_sleep: bool,
+    #[arg(long, help = "总请求数达到后提前结束")]
+    max_requests: Option<u64>,
+    #[arg(long = "iterations", help = "每个并发迭代次数达到后结束")]
+    iterations_per_vu: Option<u64>,
+    #[arg(long, help = "额外信任的根证书(PEM)")]
+    cacert: Option<String>,
+    #[arg(long, requires = "key", help = "客户端证书(PEM)")]
+    cert: Option<String>,
+    #[arg(long, requires = "cert", help = "客户端私钥(PKCS#8 PEM)")]
+    key: Option<String>,
+    #[arg(short = 'k', long, help = "不校验服务端证书，只用于测试环境")]
+    insecure: bool,
+    #[arg(short = 'x', long, help = "代理地址，例如: http://127.0.0.1:8888、socks5://127.0.0.1:1080")]
+    proxy: Option<String>,
+    #[arg(long, requires = "proxy", value_name = "USER:PASSWORD", help = "代理认证")]
+    proxy_user: Option<String>,
+    #[arg(long, requires = "proxy", value_delimiter = ',', help = "不使用代理的主机，逗号分隔")]
+    noproxy: Vec<String>,
+    #[arg(long, value_name = "HOST:PORT:ADDR[,ADDR]", help = "固定解析，和curl的--resolve一样，可以重复使用")]
+    resolve: Vec<String>,
+    #[arg(long = "local-address", help = "建立连接时绑定的本地地址，可以重复使用")]
+    local_addresses: Vec<IpAddr>,
+    #[arg(short = 'v', long, help = "打印详细信息")]
+    verbose: bool,
+    #[arg(short = 'o', long = "report", help = "将结果写入json报告")]
+    report: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct PlanArgs {
+    #[arg(help = "测试计划文件(yaml/yml/toml)")]
+    plan: PathBuf,
+    #[arg(short = 'v', long, help = "打印详细信息")]
+    verbose: bool,
+    #[arg(short = 'o', long = "report", help = "将结果写入json报告")]
+    report: Option<PathBuf>,
+    #[arg(long, help = "只校验测试计划，不发送请求")]
+    dry_run: bool,
+    #[cfg(feature = "tui")]
+    #[arg(long, help = "使用终端仪表盘显示实时结果，可以暂停、停止和调整并发数")]
+    tui: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    set_language(cli.lang);
+    let result = match cli.command {
+        Command::Run(args) => run_url(*args).await,
+        Command::Plan(args) => run_plan(args).await,
+    };
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{}: {:?}", localized("错误", "error"), e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run_url(args: RunArgs) -> anyhow::Result<ExitCode> {
+    let assert_options = parse_asserts(&args.asserts);
+    let tls_option = TlsOption {
+        ca_cert: args.cacert,
+        client_cert: args.cert,
+        client_key: args.key,
+        danger_accept_invalid_certs: args.insecure,
+        ..Default::default()
+    };
+    let proxy_option = args.proxy.map(|url| {
+        let (username, password) = match args.proxy_user.as_deref().map(|user| user.split_once(':').unwrap_or((user, ""))) {
+            Some((username, password)) => (Some(username.to_string()), Some(password.to_string())),
+            None => (None, None),
+        };
+        ProxyOption { url, username, password, no_proxy: args.noproxy, ..Default::default() }
+    });
+    let batch_option = BatchOption {
+        max_requests: args.max_requests,
+        iterations_per_vu: args.iterations_per_vu,
+        tls: if tls_option == TlsOption::default() { None } else { Some(tls_option) },
+        proxies: proxy_option.into_iter().collect(),
+        dns: DnsOption { resolve: parse_resolve(&args.resolve)?, ..Default::default() },
+        connection: ConnectionOption { local_addresses: args.local_addresses, ..Default::default() },
+        ..Default::default()
+    };
+    let progress = progress::spawn_single_progress();
+    let result = execute::run(
+        &args.url,
+        args.test_duration_secs,
+        args.concurrent_requests,
+        args.timeout_secs,
+        args.verbose,
+        &args.method,
+        args.json,
+        args.form,
+        if args.headers.is_empty() { None } else { Some(args.headers) },
+        args.cookie,
+        args.prevent_sleep,
+        assert_options,
+        Some(batch_option),
+    ).await;
+    progress.abort();
+    eprintln!();
+    let result = result?;
+    summary::print_test_result(&result);
+    if let Some(report) = args.report {
+        std::fs::write(&report, serde_json::to_vec_pretty(&result)?)?;
+        eprintln!("{}: {}", localized("报告已写入", "report written to"), report.display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn run_plan(args: PlanArgs) -> anyhow::Result<ExitCode> {
+    let mut plan = load_test_plan(&args.plan)?;
+    plan.verbose = plan.verbose || args.verbose;
+    if args.dry_run {
+        dry_run(plan.test_duration_secs, plan.concurrent_requests, &plan.api_endpoints, plan.step_option.as_ref(), plan.batch_option.as_ref())?;
+        eprintln!("{}", localized(
+            format!("测试计划校验通过: {}个接口", plan.api_endpoints.len()),
+            format!("test plan is valid: {} endpoints", plan.api_endpoints.len()),
+        ));
+        return Ok(ExitCode::SUCCESS);
+    }
+    #[cfg(feature = "tui")]
+    let result = if args.tui {
+        run_plan_with_dashboard(plan).await
+    } else {
+        run_plan_with_progress(plan).await
+    };
+    #[cfg(not(feature = "tui"))]
+    let result = run_plan_with_progress(plan).await;
+    let (result, failures) = result?;
+    summary::print_batch_result(&result);
+    if let Some(report) = args.report {
+        save_json_report(&result, &report)?;
+        eprintln!("{}: {}", localized("报告已写入", "report written to"), report.display());
+    }
+    if !failures.is_empty() {
+        eprintln!("\n{}:", localized("阈值未通过", "thresholds failed"));
+        for failure in &failures {
+            eprintln!("  ✗ {}", failure.message);
+        }
+        return Ok(ExitCode::from(THRESHOLD_FAILED_EXIT_CODE));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn run_plan_with_progress(plan: TestPlan) -> anyhow::Result<(BatchResult, Vec<ThresholdFailure>)> {
+    let progress = progress::spawn_batch_progress();
+    let result = run_test_plan(plan).await;
+    progress.abort();
+    eprintln!();
+    Ok(result?)
+}
+
+// 仪表盘模式下不打印详细信息，避免破坏界面
+#[cfg(feature = "tui")]
+async fn run_plan_with_dashboard(mut plan: TestPlan) -> anyhow::Result<(BatchResult, Vec<ThresholdFailure>)> {
+    let control = Arc::new(BatchControl::new());
+    plan.verbose = false;
+    plan.batch_option.get_or_insert_with(Default::default).control = Some(control.clone());
+    let finished = Arc::new(AtomicBool::new(false));
+    let dashboard = tui::spawn_dashboard(control, plan.concurrent_requests, finished.clone());
+    let result = run_test_plan(plan).await;
+    finished.store(true, Ordering::Relaxed);
+    dashboard.await??;
+    Ok(result?)
+}
+
+// 断言参数两两一组(jsonpath, 预期值)，预期值优先按json解析
+fn parse_asserts(asserts: &[String]) -> Option<Vec<AssertOption>> {
+    if asserts.is_empty() {
+        return None;
+    }
+    Some(
+        asserts
+            .chunks(2)
+            .map(|pair| AssertOption {
+                jsonpath: pair[0].clone(),
+                reference_object: serde_json::from_str(&pair[1]).unwrap_or_else(|_| Value::String(pair[1].clone())),
+            })
+            .collect(),
+    )
+}
+
+// 解析curl风格的"host:port:addr[,addr]"，同一个host:port重复时合并地址
+fn parse_resolve(entries: &[String]) -> anyhow::Result<HashMap<String, Vec<IpAddr>>> {
+    let mut resolve: HashMap<String, Vec<IpAddr>> = HashMap::new();
+    for entry in entries {
+        let parsed = entry.split_once(':').and_then(|(host, rest)| {
+            let (port, addrs) = rest.split_once(':')?;
+            let addrs = addrs
+                .split(',')
+                .map(|addr| addr.trim_start_matches('[').trim_end_matches(']').parse())
+                .collect::<Result<Vec<IpAddr>, _>>()
+                .ok()?;
+            Some((format!("{}:{}", host, port.parse::<u16>().ok()?), addrs))
+        });
+        let Some((key, addrs)) = parsed else {
+            anyhow::bail!("{}: {}", localized("无效的--resolve", "invalid --resolve"), entry);
+        };
+        resolve.entry(key).or_default().extend(addrs);
+    }
+    Ok(resolve)
+}
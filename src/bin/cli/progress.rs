@@ -0,0 +1,70 @@
+use std::io::Write;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use atomic_bomb_engine::core::status_share::{RESULTS_QUEUE, SINGLE_RESULT_QUEUE};
+use atomic_bomb_engine::models::engine_error::localized;
+
+// 在一行中刷新batch的周期统计
+pub(crate) fn spawn_batch_progress() -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            let latest = RESULTS_QUEUE.lock().await.back().cloned();
+            if let Some(result) = latest {
+                print_line(format!(
+                    "[{}{:>5.0}s] {} {} | rps {:.1} | p95 {}ms | {} {:.2}% | {} {}",
+                    warm_up_label(result.warm_up),
+                    result.total_duration,
+                    localized("请求", "requests"),
+                    result.total_requests,
+                    result.rps,
+                    result.response_time_95,
+                    localized("错误率", "errors"),
+                    finite_or_zero(result.error_rate),
+                    localized("并发", "concurrency"),
+                    result.total_concurrent_number,
+                ));
+            }
+        }
+    })
+}
+
+// 在一行中刷新单接口压测的周期统计
+pub(crate) fn spawn_single_progress() -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            let latest = SINGLE_RESULT_QUEUE.lock().await.back().cloned();
+            if let Some(result) = latest {
+                print_line(format!(
+                    "[{}{:>5.0}s] {} {} | rps {:.1} | p95 {}ms | {} {:.2}%",
+                    warm_up_label(result.warm_up),
+                    result.total_duration,
+                    localized("请求", "requests"),
+                    result.total_requests,
+                    result.rps,
+                    result.response_time_95,
+                    localized("成功率", "success"),
+                    finite_or_zero(result.success_rate),
+                ));
+            }
+        }
+    })
+}
+
+fn print_line(line: String) {
+    let mut stderr = std::io::stderr();
+    let _ = write!(stderr, "\r\x1b[2K{}", line);
+    let _ = stderr.flush();
+}
+
+fn warm_up_label(warm_up: bool) -> &'static str {
+    if warm_up { localized("预热 ", "warm-up ") } else { "" }
+}
+
+fn finite_or_zero(value: f64) -> f64 {
+    if value.is_finite() { value } else { 0.0 }
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use atomic_bomb_engine::models::engine_error::localized;
+use atomic_bomb_engine::models::result::{BatchResult, TestResult};
+
+// 打印batch结果汇总表
+pub(crate) fn print_batch_result(result: &BatchResult) {
+    println!();
+    println!(
+        "{:<24} {:<7} {:>10} {:>10} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        localized("接口", "endpoint"),
+        localized("方法", "method"),
+        localized("请求数", "requests"),
+        "rps",
+        "p50",
+        "p95",
+        "p99",
+        "max",
+        localized("错误率", "errors"),
+        localized("新建连接", "opened"),
+        localized("复用连接", "reused"),
+    );
+    for api in &result.api_results {
+        println!(
+            "{:<24} {:<7} {:>10} {:>10.1} {:>8} {:>8} {:>8} {:>8} {:>7.2}% {:>8} {:>8}",
+            truncate(&api.name, 24),
+            api.method,
+            api.total_requests,
+            api.rps,
+            api.median_response_time,
+            api.response_time_95,
+            api.response_time_99,
+            api.max_response_time,
+            finite_or_zero(api.error_rate),
+            api.connections_opened,
+            api.connections_reused,
+        );
+    }
+    println!(
+        "{:<24} {:<7} {:>10} {:>10.1} {:>8} {:>8} {:>8} {:>8} {:>7.2}% {:>8} {:>8}",
+        localized("总计", "total"),
+        "",
+        result.total_requests,
+        result.rps,
+        result.median_response_time,
+        result.response_time_95,
+        result.response_time_99,
+        result.max_response_time,
+        finite_or_zero(result.error_rate),
+        result.api_results.iter().map(|api| api.connections_opened).sum::<u64>(),
+        result.api_results.iter().map(|api| api.connections_reused).sum::<u64>(),
+    );
+    println!(
+        "{} {:.1}s | {} {} | {} {} | {} {:.1}KB | {} {:.1}KB/s | {} {}",
+        localized("耗时", "duration"),
+        result.total_duration,
+        localized("并发", "concurrency"),
+        result.total_concurrent_number,
+        localized("中断", "interrupted"),
+        result.interrupted_requests,
+        localized("数据", "data"),
+        result.total_data_kb,
+        localized("吞吐", "throughput"),
+        result.throughput_per_second_kb,
+        localized("结束原因", "end reason"),
+        result.end_reason.as_str()
+    );
+    print_http_versions(result);
+    print_errors(
+        result.http_errors.iter().map(|((code, msg, url), count)| (format!("{} {} {}", code, msg, url), *count)),
+        result.assert_errors.iter().map(|((url, msg), count)| (format!("{} {}", url, msg), *count)),
+    );
+}
+
+// 汇总所有接口的协议版本
+fn print_http_versions(result: &BatchResult) {
+    let mut versions: BTreeMap<&str, u64> = BTreeMap::new();
+    for api in &result.api_results {
+        for (version, count) in &api.http_versions {
+            *versions.entry(version).or_insert(0) += count;
+        }
+    }
+    if !versions.is_empty() {
+        let versions: Vec<String> = versions.iter().map(|(version, count)| format!("{} {}", version, count)).collect();
+        println!("{} {}", localized("协议", "protocols"), versions.join(" | "));
+    }
+}
+
+// 打印单接口结果汇总
+pub(crate) fn print_test_result(result: &TestResult) {
+    println!();
+    let label = |chinese: &str, english: &str| format!("{}:", localized(chinese, english));
+    println!("{:<12}{:.1}s", label("耗时", "duration"), result.total_duration);
+    println!("{:<12}{} ({} {})", label("请求数", "requests"), result.total_requests, localized("中断", "interrupted"), result.interrupted_requests);
+    println!("{:<12}{:.2}%", label("成功率", "success"), finite_or_zero(result.success_rate));
+    println!("{:<12}{:.1}", "rps:", result.rps);
+    println!("{:<12}{}/{}/{}ms", "p50/p95/p99:", result.median_response_time, result.response_time_95, result.response_time_99);
+    println!("{:<12}{}/{}ms", label("最大/最小", "max/min"), result.max_response_time, if result.min_response_time == u64::MAX { 0 } else { result.min_response_time });
+    println!("{:<12}{:.1}KB ({:.1}KB/s)", label("数据", "data"), result.total_data_kb, result.throughput_per_second_kb);
+    println!("{:<12}{}", label("结束原因", "end reason"), result.end_reason.as_str());
+    print_errors(
+        result.http_errors.iter().map(|((code, msg, url), count)| (format!("{} {} {}", code, msg, url), *count)),
+        result.assert_errors.iter().map(|((url, msg), count)| (format!("{} {}", url, msg), *count)),
+    );
+}
+
+fn print_errors(http_errors: impl Iterator<Item = (String, u32)>, assert_errors: impl Iterator<Item = (String, u32)>) {
+    let mut http_errors: Vec<(String, u32)> = http_errors.collect();
+    let mut assert_errors: Vec<(String, u32)> = assert_errors.collect();
+    if !http_errors.is_empty() {
+        http_errors.sort_by_key(|e| std::cmp::Reverse(e.1));
+        println!("\n{}:", localized("http错误", "http errors"));
+        for (error, count) in http_errors {
+            println!("  {:>8}  {}", count, error);
+        }
+    }
+    if !assert_errors.is_empty() {
+        assert_errors.sort_by_key(|e| std::cmp::Reverse(e.1));
+        println!("\n{}:", localized("断言错误", "assertion errors"));
+        for (error, count) in assert_errors {
+            println!("  {:>8}  {}", count, error);
+        }
+    }
+}
+
+fn truncate(value: &str, max_chars: usize) -> String {
+    if value.chars().count() <= max_chars {
+        value.to_string()
+    } else {
+        value.chars().take(max_chars - 1).chain(std::iter::once('…')).collect()
+    }
+}
+
+fn finite_or_zero(value: f64) -> f64 {
+    if value.is_finite() { value } else { 0.0 }
+}
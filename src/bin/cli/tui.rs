@@ -0,0 +1,279 @@
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Sparkline, Table};
+use ratatui::{DefaultTerminal, Frame};
+use tokio::task::JoinHandle;
+
+use atomic_bomb_engine::core::batch_control::BatchControl;
+use atomic_bomb_engine::core::status_share::RESULTS_QUEUE;
+use atomic_bomb_engine::models::engine_error::localized;
+use atomic_bomb_engine::models::result::BatchResult;
+
+// 折线图保留的采样点数
+const HISTORY_SIZE: usize = 240;
+
+// 仪表盘状态
+struct Dashboard {
+    control: Arc<BatchControl>,
+    concurrency: usize,
+    latest: Option<BatchResult>,
+    rps_history: VecDeque<u64>,
+    latency_history: VecDeque<u64>,
+}
+
+impl Dashboard {
+    // 拉取最新的周期统计，按两次统计的差值计算实时rps
+    fn refresh(&mut self) {
+        let latest = match RESULTS_QUEUE.blocking_lock().back().cloned() {
+            None => return,
+            Some(latest) => latest,
+        };
+        if self.latest.as_ref().map(|r| r.timestamp) == Some(latest.timestamp) {
+            return;
+        }
+        // 预热结束后统计重新开始
+        let (previous_requests, previous_duration) = self
+            .latest
+            .as_ref()
+            .filter(|r| r.warm_up == latest.warm_up)
+            .map(|r| (r.total_requests, r.total_duration))
+            .unwrap_or((0, 0.0));
+        let elapsed = latest.total_duration - previous_duration;
+        let rps = if elapsed > 0.0 {
+            latest.total_requests.saturating_sub(previous_requests) as f64 / elapsed
+        } else {
+            0.0
+        };
+        push_sample(&mut self.rps_history, rps.round() as u64);
+        push_sample(&mut self.latency_history, latest.response_time_95);
+        self.latest = Some(latest);
+    }
+
+    // 处理按键，返回false表示用户要求停止
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        match code {
+            KeyCode::Char('q') | KeyCode::Char('s') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char('p') | KeyCode::Char(' ') => {
+                if self.control.state().paused {
+                    self.control.resume();
+                } else {
+                    self.control.pause();
+                }
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Up => self.set_concurrency(self.concurrency + 1),
+            KeyCode::Char('-') | KeyCode::Down => self.set_concurrency(self.concurrency.saturating_sub(1)),
+            KeyCode::PageUp => self.set_concurrency(self.concurrency + 10),
+            KeyCode::PageDown => self.set_concurrency(self.concurrency.saturating_sub(10)),
+            _ => {}
+        }
+        true
+    }
+
+    fn set_concurrency(&mut self, concurrency: usize) {
+        self.concurrency = concurrency.max(1);
+        self.control.set_concurrency(self.concurrency);
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(8),
+                Constraint::Min(6),
+                Constraint::Length(8),
+                Constraint::Length(1),
+            ])
+            .split(frame.area());
+        self.draw_header(frame, areas[0]);
+        self.draw_charts(frame, areas[1]);
+        self.draw_endpoints(frame, areas[2]);
+        self.draw_errors(frame, areas[3]);
+        frame.render_widget(
+            Paragraph::new(localized(
+                "p/空格 暂停/继续   +/- ↑/↓ 并发±1   PgUp/PgDn 并发±10   q/s 停止",
+                "p/space pause/resume   +/- ↑/↓ concurrency ±1   PgUp/PgDn concurrency ±10   q/s stop",
+            ))
+                .style(Style::default().fg(Color::DarkGray)),
+            areas[4],
+        );
+    }
+
+    fn draw_header(&self, frame: &mut Frame, area: Rect) {
+        let state = self.control.state();
+        let status = if state.stopped {
+            localized("停止中", "stopping")
+        } else if state.paused {
+            localized("已暂停", "paused")
+        } else if self.latest.as_ref().is_some_and(|r| r.warm_up) {
+            localized("预热中", "warming up")
+        } else {
+            localized("运行中", "running")
+        };
+        let text = match &self.latest {
+            None => format!("{} | {}", status, localized("等待统计数据...", "waiting for statistics...")),
+            Some(r) => format!(
+                "{} | {} {:.0}s | {} {} | rps {:.1} | p95 {}ms | {} {:.2}% | {} {}/{}",
+                status,
+                localized("耗时", "elapsed"),
+                r.total_duration,
+                localized("请求", "requests"),
+                r.total_requests,
+                r.rps,
+                r.response_time_95,
+                localized("错误率", "errors"),
+                finite_or_zero(r.error_rate),
+                localized("并发", "concurrency"),
+                r.total_concurrent_number,
+                self.concurrency,
+            ),
+        };
+        let style = if state.paused || state.stopped {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().add_modifier(Modifier::BOLD)
+        };
+        frame.render_widget(
+            Paragraph::new(text).style(style).block(Block::default().borders(Borders::ALL).title("atomic-bomb-engine")),
+            area,
+        );
+    }
+
+    fn draw_charts(&self, frame: &mut Frame, area: Rect) {
+        let areas = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(area);
+        let rps: Vec<u64> = tail(&self.rps_history, areas[0].width.saturating_sub(2) as usize);
+        let latency: Vec<u64> = tail(&self.latency_history, areas[1].width.saturating_sub(2) as usize);
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::default().borders(Borders::ALL).title(format!("rps {}", rps.last().copied().unwrap_or(0))))
+                .data(&rps)
+                .style(Style::default().fg(Color::Green)),
+            areas[0],
+        );
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::default().borders(Borders::ALL).title(format!("p95 {}ms", latency.last().copied().unwrap_or(0))))
+                .data(&latency)
+                .style(Style::default().fg(Color::Cyan)),
+            areas[1],
+        );
+    }
+
+    fn draw_endpoints(&self, frame: &mut Frame, area: Rect) {
+        let header = Row::new(localized(
+            ["接口", "方法", "并发", "请求数", "rps", "p50", "p95", "p99", "max", "错误率"],
+            ["endpoint", "method", "vus", "requests", "rps", "p50", "p95", "p99", "max", "errors"],
+        ))
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows: Vec<Row> = self
+            .latest
+            .iter()
+            .flat_map(|r| r.api_results.iter())
+            .map(|api| {
+                let error_style = if api.err_count > 0 { Style::default().fg(Color::Red) } else { Style::default() };
+                Row::new(vec![
+                    Cell::from(api.name.clone()),
+                    Cell::from(api.method.clone()),
+                    Cell::from(api.concurrent_number.to_string()),
+                    Cell::from(api.total_requests.to_string()),
+                    Cell::from(format!("{:.1}", api.rps)),
+                    Cell::from(api.median_response_time.to_string()),
+                    Cell::from(api.response_time_95.to_string()),
+                    Cell::from(api.response_time_99.to_string()),
+                    Cell::from(api.max_response_time.to_string()),
+                    Cell::from(format!("{:.2}%", finite_or_zero(api.error_rate))).style(error_style),
+                ])
+            })
+            .collect();
+        let widths = [
+            Constraint::Min(16),
+            Constraint::Length(7),
+            Constraint::Length(6),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(7),
+            Constraint::Length(7),
+            Constraint::Length(7),
+            Constraint::Length(7),
+            Constraint::Length(8),
+        ];
+        frame.render_widget(
+            Table::new(rows, widths).header(header).block(Block::default().borders(Borders::ALL).title(localized("接口", "endpoints"))),
+            area,
+        );
+    }
+
+    fn draw_errors(&self, frame: &mut Frame, area: Rect) {
+        let mut errors: Vec<(String, u32)> = Vec::new();
+        if let Some(r) = &self.latest {
+            errors.extend(r.http_errors.iter().map(|((code, msg, url), count)| (format!("[http] {} {} {}", code, msg, url), *count)));
+            errors.extend(r.assert_errors.iter().map(|((url, msg), count)| (format!("[{}] {} {}", localized("断言", "assert"), url, msg), *count)));
+        }
+        errors.sort_by_key(|e| Reverse(e.1));
+        let items: Vec<ListItem> = errors
+            .into_iter()
+            .map(|(error, count)| ListItem::new(Line::from(format!("{:>8}  {}", count, error))).style(Style::default().fg(Color::Red)))
+            .collect();
+        frame.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(localized("错误", "errors"))), area);
+    }
+}
+
+// 在阻塞线程中运行仪表盘，finished置为true(压测结束)后退出，按q时通过control停止压测
+pub(crate) fn spawn_dashboard(control: Arc<BatchControl>, concurrency: usize, finished: Arc<AtomicBool>) -> JoinHandle<std::io::Result<()>> {
+    tokio::task::spawn_blocking(move || {
+        let mut dashboard = Dashboard {
+            control,
+            concurrency,
+            latest: None,
+            rps_history: VecDeque::with_capacity(HISTORY_SIZE),
+            latency_history: VecDeque::with_capacity(HISTORY_SIZE),
+        };
+        // init会注册panic hook，panic时也能恢复终端
+        let mut terminal = ratatui::init();
+        let result = run_dashboard(&mut terminal, &mut dashboard, &finished);
+        ratatui::restore();
+        result
+    })
+}
+
+fn run_dashboard(terminal: &mut DefaultTerminal, dashboard: &mut Dashboard, finished: &AtomicBool) -> std::io::Result<()> {
+    while !finished.load(Ordering::Relaxed) {
+        dashboard.refresh();
+        terminal.draw(|frame| dashboard.draw(frame))?;
+        if event::poll(Duration::from_millis(250))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !dashboard.handle_key(key.code, key.modifiers) {
+                    dashboard.control.stop();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn push_sample(history: &mut VecDeque<u64>, value: u64) {
+    if history.len() == HISTORY_SIZE {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+// 取最近的count个采样点
+fn tail(history: &VecDeque<u64>, count: usize) -> Vec<u64> {
+    history.iter().skip(history.len().saturating_sub(count)).copied().collect()
+}
+
+fn finite_or_zero(value: f64) -> f64 {
+    if value.is_finite() { value } else { 0.0 }
+}
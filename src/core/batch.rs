@@ -1,11 +1,14 @@
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use histogram::Histogram;
 use std::time::{Duration, Instant};
-use anyhow::{Context, Error};
-use reqwest::{Client, Method, StatusCode};
-use tokio::sync::Mutex;
-use reqwest::header::{COOKIE, HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use anyhow::Error;
+use reqwest::{Client, Method, Request, RequestBuilder, Response, StatusCode, Version};
+use tokio::sync::{watch, Mutex};
+use reqwest::header::{CONTENT_TYPE, COOKIE, HeaderMap, HeaderName, HeaderValue, USER_AGENT};
 use serde_json::Value;
 use std::time::{SystemTime, UNIX_EPOCH};
 use jsonpath_lib::select;
@@ -14,68 +17,464 @@ use std::env;
 use futures::stream::StreamExt;
 use futures::future::join_all;
 use tokio::task::JoinHandle;
+use hyper_util::client::legacy::connect::HttpInfo;
 
 
-use crate::core::check_endpoints_names::check_endpoints_names;
 use crate::core::concurrency_controller::ConcurrencyController;
+use crate::core::endpoint_mixer::EndpointMixer;
+use crate::core::fast_rng::FastRng;
+use crate::core::http_client::{request_url, ClientConfig, HttpClients};
 use crate::core::sleep_guard::SleepGuard;
-use crate::core::status_share::{RESULTS_QUEUE, RESULTS_SHOULD_STOP};
+use crate::core::unix_socket::{parse_unix_url, unix_request_url, UnixConnectionId, UnixRequestError};
+use crate::core::status_share::{StopGuard, RESULTS_QUEUE, RESULTS_SHOULD_STOP};
 use crate::models::assert_error_stats::AssertErrorStats;
 use crate::models::http_error_stats::HttpErrorStats;
 use crate::models::result::{ApiResult, BatchResult};
 use crate::models::api_endpoint::ApiEndpoint;
+use crate::models::http_version::HttpVersion;
 use crate::models::step_option::{InnerStepOption, StepOption};
+use crate::models::batch_option::BatchOption;
+use crate::models::error_kind::{port_exhausted_message, tls_error_message, ErrorKind};
+use crate::models::engine_error::{localized, EngineError};
+use crate::models::mix_mode::MixMode;
+use crate::models::end_reason::EndReason;
+use crate::models::connection_option::ConnectionStrategy;
+use crate::models::request_record::{RequestDetail, RequestRecord};
+use crate::core::request_log::RequestLogger;
+use crate::core::validate::dry_run;
+use crate::core::batch_control::{BatchControl, ControlState};
 
-pub async fn batch(
-    test_duration_secs: u64,
-    concurrent_requests: usize,
-    verbose: bool,
-    should_prevent: bool,
-    api_endpoints: Vec<ApiEndpoint>,
-    step_option: Option<StepOption>
-) -> anyhow::Result<BatchResult> {
-    // 阻止电脑休眠
-    let _guard = SleepGuard::new(should_prevent);
-    // 检查每个接口的名称
-    if let Err(e) = check_endpoints_names(api_endpoints.clone()){
-        return Err(Error::msg(e));
-    }
+// 并发任务句柄
+type WorkerHandle = JoinHandle<Result<(), Error>>;
+
+// 所有接口共用的统计
+struct SharedStats {
     // 总响应时间统计
-    let histogram = Arc::new(Mutex::new(Histogram::new(14, 20).unwrap()));
+    histogram: Mutex<Histogram>,
     // 成功数据统计
-    let successful_requests = Arc::new(Mutex::new(0));
+    successful_requests: Mutex<i32>,
     // 请求总数统计
-    let total_requests = Arc::new(Mutex::new(0));
+    total_requests: Mutex<u64>,
     // 统计最大响应时间
-    let max_response_time = Arc::new(Mutex::new(0u64));
+    max_response_time: Mutex<u64>,
     // 统计最小响应时间
-    let min_response_time = Arc::new(Mutex::new(u64::MAX));
+    min_response_time: Mutex<u64>,
     // 统计错误数量
-    let err_count = Arc::new(Mutex::new(0));
-    // 已开始并发数
-    let concurrent_number = Arc::new(Mutex::new(0));
-    // 接口线程池
-    let mut handles:Vec<JoinHandle<Result<(), Error>>> = Vec::new();
+    err_count: Mutex<i32>,
     // 统计响应大小
-    let total_response_size = Arc::new(Mutex::new(0u64));
+    total_response_size: Mutex<u64>,
+    // 优雅停止时被中断的请求数
+    interrupted_requests: Mutex<u64>,
     // 统计http错误
-    let http_errors = Arc::new(Mutex::new(HttpErrorStats::new()));
+    http_errors: HttpErrorStats,
     // 统计断言错误
-    let assert_errors = Arc::new(Mutex::new(AssertErrorStats::new()));
+    assert_errors: AssertErrorStats,
+    // 每个接口的测试结果
+    api_results: Mutex<Vec<ApiResult>>,
+}
+
+impl SharedStats {
+    fn new(endpoint_count: usize) -> Self {
+        SharedStats {
+            histogram: Mutex::new(new_histogram()),
+            successful_requests: Mutex::new(0),
+            total_requests: Mutex::new(0),
+            max_response_time: Mutex::new(0),
+            min_response_time: Mutex::new(u64::MAX),
+            err_count: Mutex::new(0),
+            total_response_size: Mutex::new(0),
+            interrupted_requests: Mutex::new(0),
+            http_errors: HttpErrorStats::new(),
+            assert_errors: AssertErrorStats::new(),
+            api_results: Mutex::new(vec![ApiResult::new(); endpoint_count]),
+        }
+    }
+}
+
+// 一个并发使用的客户端
+struct WorkerClients {
+    clients: Arc<HttpClients>,
+    // 共用连接池时占用的共享客户端
+    shared_slot: Option<SharedSlot>,
+    // 绑定的本地地址
+    local_address: Option<IpAddr>,
+    built_at: Instant,
+}
+
+// 共享客户端和分配到的并发数
+struct SharedClients {
+    built_at: Instant,
+    clients: Arc<HttpClients>,
+    vus: usize,
+}
+
+// 并发占用的共享客户端，并发结束后释放，调整并发数时新的并发可以使用
+struct SharedSlot {
+    context: Arc<RequestContext>,
+    index: usize,
+}
+
+impl Drop for SharedSlot {
+    fn drop(&mut self) {
+        self.context.shared_clients.lock()[self.index].vus -= 1;
+    }
+}
+
+// 选择共享客户端的下标：限制了每个客户端的并发数时使用第一个没有满的客户端，
+// 否则在每个本地地址一个的客户端中选择并发数最少的，等于长度时需要新建客户端
+fn pick_shared_slot(vus: &[usize], vus_per_client: Option<usize>, fixed_clients: usize) -> usize {
+    match vus_per_client {
+        Some(limit) => vus.iter().position(|count| *count < limit).unwrap_or(vus.len()),
+        None if vus.len() < fixed_clients => vus.len(),
+        None => (0..fixed_clients).min_by_key(|index| vus[*index]).unwrap_or(0),
+    }
+}
+
+// 单个接口的配置和统计
+struct EndpointState {
+    // 在结果中的下标
+    index: usize,
+    endpoint: ApiEndpoint,
+    method: Method,
+    // 实际请求的url，覆盖SNI时主机名换成server_name
+    url: String,
+    // 使用的客户端下标
+    client_index: usize,
+    // 接口并发数，混合模式下是正在进行中的请求数
+    concurrent_number: Mutex<i32>,
+    stats: EndpointStats,
+    // 预热期间的统计，不计入最终结果
+    warm_up_stats: EndpointStats,
+    // 已经占用的请求预算
+    issued_requests: AtomicU64,
+}
+
+// 单个接口的统计
+struct EndpointStats {
+    // 接口数据的统计
+    histogram: Mutex<Histogram>,
+    // 接口成功数据统计
+    successful_requests: Mutex<i32>,
+    // 接口请求总数统计
+    total_requests: Mutex<u64>,
+    // 接口统计最大响应时间
+    max_response_time: Mutex<u64>,
+    // 接口统计最小响应时间
+    min_response_time: Mutex<u64>,
+    // 接口统计错误数量
+    err_count: Mutex<i32>,
+    // 接口响应大小
+    total_response_size: Mutex<u64>,
+    // 接口被中断的请求数
+    interrupted_requests: Mutex<u64>,
+    // 接口新建的连接数
+    connections_opened: Mutex<u64>,
+    // 接口复用连接的次数
+    connections_reused: Mutex<u64>,
+    // 每种协议版本的响应数
+    http_versions: Mutex<HashMap<String, u64>>,
+    // 接口结果
+    result: Mutex<ApiResult>,
+}
+
+impl EndpointStats {
+    fn new(result: ApiResult) -> Self {
+        EndpointStats {
+            histogram: Mutex::new(new_histogram()),
+            successful_requests: Mutex::new(0),
+            total_requests: Mutex::new(0),
+            max_response_time: Mutex::new(0),
+            min_response_time: Mutex::new(u64::MAX),
+            err_count: Mutex::new(0),
+            total_response_size: Mutex::new(0),
+            interrupted_requests: Mutex::new(0),
+            connections_opened: Mutex::new(0),
+            connections_reused: Mutex::new(0),
+            http_versions: Mutex::new(HashMap::new()),
+            result: Mutex::new(result),
+        }
+    }
+}
+
+// 所有并发任务共用的请求上下文
+struct RequestContext {
+    stats: SharedStats,
+    // 预热期间的统计，只出现在周期快照中
+    warm_up_stats: SharedStats,
+    // 已开始并发数
+    concurrent_number: Mutex<i32>,
+    endpoints: Vec<EndpointState>,
+    user_agent: HeaderValue,
+    request_logger: Option<Arc<RequestLogger>>,
+    verbose: bool,
+    test_start: Instant,
+    // 预热结束时间，之后开始的请求才计入结果
+    warm_up_end: Instant,
+    // 预热期间逐步启动并发
+    warm_up_ramp: bool,
+    // 到达截止时间后等待进行中请求的最长时间，为空时一直等待
+    graceful_stop: Option<Duration>,
+    // 客户端配置
+    client_config: ClientConfig,
+    // 共用连接池时的客户端，按每个客户端的并发数分组
+    shared_clients: parking_lot::Mutex<Vec<SharedClients>>,
+    // 重新构建客户端的间隔，新连接会重新解析DNS
+    dns_refresh: Option<Duration>,
+    // 已经单独构建客户端的并发数，用来轮流分配本地地址
+    worker_count: AtomicUsize,
+    // 已经出现过的连接(本地地址, 远端地址)
+    connections: parking_lot::Mutex<HashSet<(SocketAddr, SocketAddr)>>,
+    // 已经出现过的unix socket连接
+    unix_connections: parking_lot::Mutex<HashSet<UnixConnectionId>>,
+    // 每个并发两次请求开始之间的最小间隔
+    pacing: Option<Duration>,
+    // 总请求数上限
+    max_requests: Option<u64>,
+    // 已经发出的请求数
+    issued_requests: AtomicU64,
+    // 每个并发的迭代次数
+    iterations_per_vu: Option<u64>,
+    // 达到总请求数或者所有接口预算用完时通知所有并发结束
+    finished: watch::Sender<Option<EndReason>>,
+    // 最后一个结束的并发的结束原因
+    end_reason: parking_lot::Mutex<EndReason>,
+}
+
+impl RequestContext {
+    // 返回请求使用的汇总统计、接口统计和统计窗口的开始时间
+    fn stats_for<'a>(&'a self, state: &'a EndpointState, warm_up: bool) -> (&'a SharedStats, &'a EndpointStats, Instant) {
+        if warm_up {
+            (&self.warm_up_stats, &state.warm_up_stats, self.test_start)
+        } else {
+            (&self.stats, &state.stats, self.warm_up_end)
+        }
+    }
+
+    // 占用一个总请求数，超过上限时返回false
+    fn reserve_request(&self) -> bool {
+        match self.max_requests {
+            None => true,
+            Some(max) => self.issued_requests.fetch_add(1, Ordering::Relaxed) < max,
+        }
+    }
+
+    // 占用接口的一个请求预算，用完时返回false
+    fn reserve_endpoint(&self, index: usize) -> bool {
+        let state = &self.endpoints[index];
+        match state.endpoint.max_requests {
+            None => true,
+            Some(max) => state.issued_requests.fetch_add(1, Ordering::Relaxed) < max,
+        }
+    }
+
+    // 混合模式下跳过预算用完的接口，全部用完时返回空
+    fn pick_endpoint(&self, mixer: &EndpointMixer, rng: &mut FastRng, step: &mut usize) -> Option<&EndpointState> {
+        loop {
+            let index = mixer.pick(rng, step);
+            if self.reserve_endpoint(index) {
+                return Some(&self.endpoints[index]);
+            }
+            if self.all_budgets_exhausted() {
+                return None;
+            }
+        }
+    }
+
+    fn all_budgets_exhausted(&self) -> bool {
+        self.endpoints
+            .iter()
+            .all(|state| state.endpoint.max_requests.is_some_and(|max| state.issued_requests.load(Ordering::Relaxed) >= max))
+    }
+
+    // 通知所有并发结束，只记录第一次的原因
+    fn finish(&self, reason: EndReason) {
+        self.finished.send_if_modified(|finished| {
+            if finished.is_none() {
+                *finished = Some(reason);
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    async fn wait_finished(&self) -> EndReason {
+        let mut receiver = self.finished.subscribe();
+        let finished = receiver.wait_for(|finished| finished.is_some()).await.map(|finished| finished.unwrap_or_default());
+        match finished {
+            Ok(reason) => reason,
+            Err(_) => futures::future::pending().await,
+        }
+    }
+
+    fn record_end(&self, reason: EndReason) {
+        *self.end_reason.lock() = reason;
+    }
+
+    // 共用连接池时按每个客户端的并发数分配共享客户端，结束的并发空出的位置可以重新分配；
+    // 否则每个并发单独构建，本地地址按编号轮流分配
+    fn worker_clients(self: &Arc<Self>) -> Result<WorkerClients, EngineError> {
+        let connection = &self.client_config.connection;
+        if connection.strategy != ConnectionStrategy::Shared {
+            let worker = self.worker_count.fetch_add(1, Ordering::Relaxed);
+            let local_address = self.local_address(worker);
+            let clients = Arc::new(HttpClients::build(&self.client_config, local_address)?);
+            return Ok(WorkerClients { clients, shared_slot: None, local_address, built_at: Instant::now() });
+        }
+        let mut shared_clients = self.shared_clients.lock();
+        let vus: Vec<usize> = shared_clients.iter().map(|shared| shared.vus).collect();
+        let index = pick_shared_slot(&vus, connection.vus_per_client, connection.local_addresses.len().max(1));
+        if index == shared_clients.len() {
+            let clients = Arc::new(HttpClients::build(&self.client_config, self.local_address(index))?);
+            shared_clients.push(SharedClients { built_at: Instant::now(), clients, vus: 0 });
+        }
+        let shared = &mut shared_clients[index];
+        shared.vus += 1;
+        Ok(WorkerClients {
+            clients: shared.clients.clone(),
+            shared_slot: Some(SharedSlot { context: self.clone(), index }),
+            local_address: self.local_address(index),
+            built_at: shared.built_at,
+        })
+    }
+
+    fn local_address(&self, index: usize) -> Option<IpAddr> {
+        let local_addresses = &self.client_config.connection.local_addresses;
+        local_addresses.get(index % local_addresses.len().max(1)).copied()
+    }
+
+    // 到达DNS刷新间隔后重新构建客户端，旧的连接随旧客户端释放，构建失败时继续使用旧客户端
+    fn refresh_clients(&self, worker: &mut WorkerClients) {
+        let Some(refresh) = self.dns_refresh else {
+            return;
+        };
+        if worker.built_at.elapsed() < refresh {
+            return;
+        }
+        match worker.shared_slot.as_ref().map(|slot| slot.index) {
+            None => {
+                if let Ok(clients) = HttpClients::build(&self.client_config, worker.local_address) {
+                    worker.clients = Arc::new(clients);
+                }
+                worker.built_at = Instant::now();
+            }
+            Some(index) => {
+                let mut shared_clients = self.shared_clients.lock();
+                let shared = &mut shared_clients[index];
+                if shared.built_at.elapsed() >= refresh {
+                    if let Ok(new_clients) = HttpClients::build(&self.client_config, worker.local_address) {
+                        shared.clients = Arc::new(new_clients);
+                    }
+                    shared.built_at = Instant::now();
+                }
+                worker.clients = shared.clients.clone();
+                worker.built_at = shared.built_at;
+            }
+        }
+    }
+
+    // 按响应所在的连接判断是否新建，每个请求新建连接时本地端口可能被重新使用，直接算作新建
+    // unix socket连接没有地址，按连接器分配的编号判断
+    fn is_new_connection(&self, response: &Response) -> bool {
+        if self.client_config.connection.strategy == ConnectionStrategy::PerRequest {
+            return true;
+        }
+        if let Some(id) = response.extensions().get::<UnixConnectionId>() {
+            return self.unix_connections.lock().insert(*id);
+        }
+        match response.extensions().get::<HttpInfo>() {
+            Some(info) => self.connections.lock().insert((info.local_addr(), info.remote_addr())),
+            None => false,
+        }
+    }
+}
+
+// 每次迭代选择接口的方式
+enum EndpointSelector {
+    // 固定请求一个接口
+    Fixed(usize),
+    // 按权重混合选择
+    Mix(EndpointMixer),
+}
+
+// 一组并发任务，按权重拆分时每个接口一组，混合模式下所有接口一组
+struct WorkerGroup {
+    selector: EndpointSelector,
+    controller: Arc<ConcurrencyController>,
+    // 这一组在总并发数中的比例
+    weight_ratio: f64,
+    // 这一组启动时的并发数
+    concurrency: usize,
+}
+
+pub async fn batch(
+    test_duration_secs: u64,
+    concurrent_requests: usize,
+    verbose: bool,
+    should_prevent: bool,
+    api_endpoints: Vec<ApiEndpoint>,
+    step_option: Option<StepOption>,
+    batch_option: Option<BatchOption>,
+) -> Result<BatchResult, EngineError> {
+    // 任何路径结束时都设置停止标志，迭代周期结果不会一直等待
+    let _stop_guard = StopGuard::batch();
+    // 发送请求前校验全部配置，避免在并发任务中出错
+    dry_run(test_duration_secs, concurrent_requests, &api_endpoints, step_option.as_ref(), batch_option.as_ref())?;
+    // 阻止电脑休眠
+    let _guard = SleepGuard::new(should_prevent);
+    // 可选配置
+    let batch_option = batch_option.unwrap_or_default();
+    // 重置停止标志和上一次压测的周期结果
+    *RESULTS_SHOULD_STOP.lock().await = false;
+    RESULTS_QUEUE.lock().await.clear();
+    // 单请求日志
+    let request_logger = match batch_option.request_log.clone() {
+        None => None,
+        Some(option) => Some(Arc::new(RequestLogger::new(option).await?)),
+    };
+    // 接口线程池
+    let mut handles: Vec<WorkerHandle> = Vec::new();
+    // 运行中追加的任务
+    let extra_handles: Arc<Mutex<Vec<WorkerHandle>>> = Arc::new(Mutex::new(Vec::new()));
+    // 监听并发数调整的任务
+    let mut supervisors: Vec<JoinHandle<()>> = Vec::new();
     // 总权重
     let total_weight: u32 = api_endpoints.iter().map(|e| e.weight).sum();
-    // 用arc包装每一个endpoint
-    let api_endpoints_arc: Vec<Arc<Mutex<ApiEndpoint>>> = api_endpoints
+    let weights: Vec<u32> = api_endpoints.iter().map(|e| e.weight).collect();
+    // 相同协议版本和TLS配置的接口共用一个客户端
+    let (client_config, client_indexes) = ClientConfig::new(&batch_option, &api_endpoints).await?;
+    // 初始化每个接口的统计和结果
+    let endpoints: Vec<EndpointState> = api_endpoints
         .into_iter()
-        .map(|endpoint| Arc::new(Mutex::new(endpoint)))
+        .enumerate()
+        .map(|(index, endpoint)| {
+            let url = match parse_unix_url(&endpoint.url) {
+                Some((_, http_path)) => unix_request_url(http_path),
+                None => request_url(&endpoint, batch_option.tls.as_ref()),
+            };
+            let mut r = ApiResult::new();
+            r.name = endpoint.name.clone();
+            r.url = endpoint.url.clone();
+            EndpointState {
+                index,
+                // 请求方法已经校验过
+                method: Method::from_str(&endpoint.method.to_uppercase()).unwrap_or(Method::GET),
+                endpoint,
+                url,
+                client_index: client_indexes[index],
+                concurrent_number: Mutex::new(0),
+                stats: EndpointStats::new(r.clone()),
+                warm_up_stats: EndpointStats::new(r),
+                issued_requests: AtomicU64::new(0),
+            }
+        })
         .collect();
     // 开始测试时间
     let test_start = Instant::now();
+    // 预热结束时间，压测时长从预热结束后开始计算
+    let warm_up_end = test_start + Duration::from_secs(batch_option.warm_up_secs.unwrap_or(0));
     // 测试结束时间
-    let test_end = test_start + Duration::from_secs(test_duration_secs);
-    // 每个接口的测试结果
-    let results: Vec<ApiResult> = Vec::new();
-    let results_arc = Arc::new(Mutex::new(results));
+    let test_end = warm_up_end + Duration::from_secs(test_duration_secs);
     // user_agent
     let info = os_info::get();
     let os_type = info.os_type();
@@ -86,453 +485,160 @@ pub async fn batch(
         "{} {} ({}; {})",
         app_name, app_version, os_type, os_version
     );
-    // 针对每一个接口开始配置
-    for (index, endpoint_arc) in api_endpoints_arc.clone().into_iter().enumerate() {
-        let endpoint = endpoint_arc.lock().await;
-        let weight = endpoint.weight.clone();
-        let name = endpoint.name.clone();
-        let url = endpoint.url.clone();
-        drop(endpoint);
-        results_arc.lock().await.push(ApiResult::new());
-        // 计算权重比例
-        let weight_ratio = weight as f64 / total_weight as f64;
-        // 计算每个接口的并发量
-        let mut concurrency_for_endpoint = ((concurrent_requests as f64) * weight_ratio).round() as usize;
-        // 如果这个接口的并发量四舍五入成0了， 就把他定为1
-        if concurrency_for_endpoint == 0{
-            concurrency_for_endpoint = 1
-        }
-        // 接口数据的统计
-        let api_histogram = Arc::new(Mutex::new(Histogram::new(14, 20).unwrap()));
-        // 接口成功数据统计
-        let api_successful_requests = Arc::new(Mutex::new(0));
-        // 接口请求总数统计
-        let api_total_requests = Arc::new(Mutex::new(0));
-        // 接口统计最大响应时间
-        let api_max_response_time = Arc::new(Mutex::new(0u64));
-        // 接口统计最小响应时间
-        let api_min_response_time = Arc::new(Mutex::new(u64::MAX));
-        // 接口统计错误数量
-        let api_err_count = Arc::new(Mutex::new(0));
-        // 接口并发数统计
-        let api_concurrent_number = Arc::new(Mutex::new(0));
-        // 接口响应大小
-        let api_total_response_size = Arc::new(Mutex::new(0u64));
-        // 初始化api结果
-        let mut r = ApiResult::new();
-        r.name = name.clone();
-        r.url = url.clone();
-        let api_result = Arc::new(Mutex::new(r));
-        // 根据step初始化并发控制器
-        let controller =  match step_option.clone() {
-            None => {
-                Arc::new(ConcurrencyController::new(concurrency_for_endpoint, None))
-            }
-            Some(option) => {
+    let user_agent = HeaderValue::from_str(&user_agent_value).unwrap_or(HeaderValue::from_static(app_name));
+    let context = Arc::new(RequestContext {
+        stats: SharedStats::new(endpoints.len()),
+        warm_up_stats: SharedStats::new(endpoints.len()),
+        concurrent_number: Mutex::new(0),
+        endpoints,
+        user_agent,
+        request_logger: request_logger.clone(),
+        verbose,
+        test_start,
+        warm_up_end,
+        warm_up_ramp: batch_option.warm_up_ramp,
+        graceful_stop: batch_option.graceful_stop_secs.map(Duration::from_secs),
+        client_config,
+        shared_clients: parking_lot::Mutex::new(Vec::new()),
+        dns_refresh: batch_option.dns.refresh_secs.map(Duration::from_secs),
+        worker_count: AtomicUsize::new(0),
+        connections: parking_lot::Mutex::new(HashSet::new()),
+        unix_connections: parking_lot::Mutex::new(HashSet::new()),
+        pacing: batch_option.pacing_ms.map(Duration::from_millis),
+        max_requests: batch_option.max_requests,
+        issued_requests: AtomicU64::new(0),
+        iterations_per_vu: batch_option.iterations_per_vu,
+        finished: watch::channel(None).0,
+        end_reason: parking_lot::Mutex::new(EndReason::Duration),
+    });
+    // 按选择方式划分并发任务组
+    let groups: Vec<Arc<WorkerGroup>> = match batch_option.mix_mode {
+        MixMode::Partition => weights
+            .iter()
+            .enumerate()
+            .map(|(index, weight)| {
+                // 计算权重比例
+                let weight_ratio = *weight as f64 / total_weight as f64;
+                // 计算每个接口的并发量，如果四舍五入成0了，就把他定为1
+                let concurrency = (((concurrent_requests as f64) * weight_ratio).round() as usize).max(1);
                 // 计算每个接口的步长
-                let step = option.increase_step as f64 * weight_ratio;
-                Arc::new(ConcurrencyController::new(concurrency_for_endpoint, Option::from(InnerStepOption { increase_step: step, increase_interval: option.increase_interval })))
-            }
-        };
+                let step = step_option.as_ref().map(|option| InnerStepOption {
+                    increase_step: option.increase_step as f64 * weight_ratio,
+                    increase_interval: option.increase_interval,
+                });
+                Arc::new(WorkerGroup {
+                    selector: EndpointSelector::Fixed(index),
+                    controller: Arc::new(ConcurrencyController::new(concurrency, step)),
+                    weight_ratio,
+                    concurrency,
+                })
+            })
+            .collect(),
+        mode => {
+            let step = step_option.as_ref().map(|option| InnerStepOption {
+                increase_step: option.increase_step as f64,
+                increase_interval: option.increase_interval,
+            });
+            vec![Arc::new(WorkerGroup {
+                selector: EndpointSelector::Mix(EndpointMixer::new(mode, &weights)),
+                controller: Arc::new(ConcurrencyController::new(concurrent_requests, step)),
+                weight_ratio: 1.0,
+                concurrency: concurrent_requests,
+            })]
+        }
+    };
+    for group in groups {
         // 后台启动并发控制器
         tokio::spawn({
-            let controller_clone = Arc::clone(&controller);
+            let controller_clone = Arc::clone(&group.controller);
             async move {
                 controller_clone.distribute_permits().await;
             }
         });
-        for _ in 0..concurrency_for_endpoint {
-            // 数据桶副本
-            let histogram_clone = histogram.clone();
-            // 任务名称
-            let api_name_clone = name.clone();
-            // api数据桶副本
-            let api_histogram_clone = api_histogram.clone();
-            // api成功数量统计副本
-            let api_successful_requests_clone = api_successful_requests.clone();
-            // api总统计数量统计副本
-            let api_total_requests_clone = api_total_requests.clone();
-            // api最大响应时间副本
-            let api_max_response_time_clone = api_max_response_time.clone();
-            // api最小响应时间副本
-            let api_min_response_time_clone = api_min_response_time.clone();
-            // api错误数量统计副本
-            let api_err_count_clone = api_err_count.clone();
-            // api并发数统计副本
-            let api_concurrent_number_clone = api_concurrent_number.clone();
-            // api结果副本
-            let api_result_clone = api_result.clone();
-            // api吞吐量副本
-            let api_total_response_size_clone = api_total_response_size.clone();
-            // 总请求数记录副本
-            let total_requests_clone = Arc::clone(&total_requests);
-            // 每个接口端点副本
-            let endpoint_clone = Arc::clone(&endpoint_arc);
-            // 最大响应时间副本
-            let max_response_time_clone = max_response_time.clone();
-            // 响应大小统计副本
-            let total_response_size_clone = total_response_size.clone();
-            // 最小响应时间副本
-            let min_response_time_clone = min_response_time.clone();
-            // 错误次数副本
-            let err_count_clone = err_count.clone();
-            // 并发数统计副本
-            let concurrent_number_clone = concurrent_number.clone();
-            // 断言错误副本
-            let assert_errors_clone = assert_errors.clone();
-            // 成功次数副本
-            let successful_requests_clone = successful_requests.clone();
-            // http错误副本
-            let http_errors_clone = http_errors.clone();
-            // results副本
-            let results_clone = results_arc.clone();
-            // user-agent副本
-            let user_agent_clone = user_agent_value.clone();
-            // 构建http客户端
-            let client_builder = Client::builder();
-            // 如果有超时时间就将client设置
-            let client = if endpoint_clone.lock().await.timeout_secs > 0 {
-                client_builder.timeout(Duration::from_secs(endpoint_clone.lock().await.timeout_secs)).build().context("构建带超时的http客户端失败")?
-            } else {
-                client_builder.build().context("构建http客户端失败")?
-            };
-            let controller_clone = controller.clone();
-            // 开启并发
-            let handle: JoinHandle<Result<(), Error>> = tokio::spawn(async move {
-                let semaphore = controller_clone.get_semaphore();
-                let _permit = semaphore.acquire().await.expect("获取信号量许可失败");
-                // 统计并发数
-                *api_concurrent_number_clone.lock().await += 1;
-                *concurrent_number_clone.lock().await += 1;
-                while Instant::now() < test_end {
-                    // 总请求数
-                    *total_requests_clone.lock().await += 1;
-                    // api请求数
-                    *api_total_requests_clone.lock().await += 1;
-                    // 请求方法副本
-                    let method_clone = endpoint_clone.lock().await.method.clone();
-                    // json副本
-                    let json_obj_clone = endpoint_clone.lock().await.json.clone();
-                    // form副本
-                    let form_data_clone = endpoint_clone.lock().await.form_data.clone();
-                    // headers副本
-                    let headers_clone = endpoint_clone.lock().await.headers.clone();
-                    // cookie副本
-                    let cookie_clone = endpoint_clone.lock().await.cookies.clone();
-                    // 断言副本
-                    let assert_options_clone = endpoint_clone.lock().await.assert_options.clone();
-                    // 构建请求方式
-                    let method = Method::from_str(&method_clone.to_uppercase()).map_err(|_| Error::msg("构建请求方法失败"))?;
-                    // 构建请求
-                    let mut request = client.request(method, endpoint_clone.lock().await.url.clone());
-                    // 构建请求头
-                    let mut headers = HeaderMap::new();
-                    headers.insert(USER_AGENT, user_agent_clone.parse()?);
-                    if let Some(headers_map) = headers_clone {
-                        headers.extend(headers_map.iter().map(|(k, v)| {
-                            let header_name = k.parse::<HeaderName>().expect("无效的header名称");
-                            let header_value = v.parse::<HeaderValue>().expect("无效的header值");
-                            (header_name, header_value)
-                        }));
+        for slot in 0..group.concurrency {
+            handles.push(spawn_worker(context.clone(), group.clone(), batch_option.control.clone(), slot, true, test_end)?);
+        }
+        // 运行中调高并发数时追加任务
+        if let Some(control) = batch_option.control.clone() {
+            let extra_handles_clone = extra_handles.clone();
+            let context = context.clone();
+            supervisors.push(tokio::spawn(async move {
+                let mut receiver = control.subscribe();
+                let mut spawned = group.concurrency;
+                while let Ok(Ok(())) = tokio::time::timeout_at(test_end.into(), receiver.changed()).await {
+                    let state = *receiver.borrow_and_update();
+                    if state.stopped {
+                        break;
                     }
-                    // 构建cookies
-                    if let Some(ref c) = cookie_clone{
-                        match HeaderValue::from_str(c){
-                            Ok(h) => {
-                                headers.insert(COOKIE, h);
-                            },
-                            Err(e) =>{
-                                return Err(Error::msg(format!("设置cookie失败:{:?}", e)))
+                    let target = endpoint_concurrency(&state, group.weight_ratio, group.concurrency);
+                    while spawned < target {
+                        match spawn_worker(context.clone(), group.clone(), Some(control.clone()), spawned, false, test_end) {
+                            Ok(handle) => extra_handles_clone.lock().await.push(handle),
+                            Err(e) => {
+                                eprintln!("追加并发失败::{:?}", e);
+                                break;
                             }
                         }
-                    }
-                    request = request.headers(headers);
-                    // 构建json请求
-                    if let Some(json_value) = json_obj_clone{
-                        request = request.json(&json_value);
-                    }
-                    // 构建form表单
-                    if let Some(form_data) = form_data_clone{
-                        request = request.form(&form_data);
-                    };
-                    // 记录开始时间
-                    let start = Instant::now();
-                    // 发送请求
-                    match request.send().await {
-                        Ok(response) => {
-                            let status = response.status();
-                            match status{
-                                // 正确的状态码
-                                StatusCode::OK |
-                                StatusCode::CREATED |
-                                StatusCode::ACCEPTED |
-                                StatusCode::NON_AUTHORITATIVE_INFORMATION |
-                                StatusCode::NO_CONTENT |
-                                StatusCode::RESET_CONTENT |
-                                StatusCode::PARTIAL_CONTENT |
-                                StatusCode::MULTI_STATUS |
-                                StatusCode::ALREADY_REPORTED |
-                                StatusCode::IM_USED |
-                                StatusCode::MULTIPLE_CHOICES |
-                                StatusCode::MOVED_PERMANENTLY |
-                                StatusCode::FOUND |
-                                StatusCode::SEE_OTHER |
-                                StatusCode::NOT_MODIFIED |
-                                StatusCode::USE_PROXY |
-                                StatusCode::TEMPORARY_REDIRECT |
-                                StatusCode::PERMANENT_REDIRECT => {
-                                    /*
-                                    ---------------
-                                        请求成功
-                                    ---------------
-                                    */
-                                    let mut api_histogram = api_histogram_clone.lock().await;
-                                    // 响应时间
-                                    let duration = start.elapsed().as_millis() as u64;
-                                    // 最大请求时间
-                                    let mut max_rt = max_response_time_clone.lock().await;
-                                    *max_rt = (*max_rt).max(duration);
-                                    // api最大请求时间
-                                    let mut api_max_rt = api_max_response_time_clone.lock().await;
-                                    *api_max_rt = (*api_max_rt).max(duration);
-                                    // 最小响应时间
-                                    let mut min_rt = min_response_time_clone.lock().await;
-                                    *min_rt = (*min_rt).min(duration);
-                                    // api最小响应时间
-                                    let mut api_min_rt = api_min_response_time_clone.lock().await;
-                                    *api_min_rt = (*api_min_rt).min(duration);
-                                    // 将数据放入全局统计桶
-                                    if let Err(e) = histogram_clone.lock().await.increment(duration){
-                                        eprintln!("histogram设置数据错误:{:?}", e)
-                                    };
-                                    // 将数据放入api统计桶
-                                    if let Err(e) = api_histogram.increment(duration){
-                                        eprintln!("api histogram设置错误:{:?}", e)
-                                    }
-                                    // 响应流
-                                    let mut stream = response.bytes_stream();
-                                    // 响应体
-                                    let mut body_bytes = Vec::new();
-                                    while let Some(item) = stream.next().await {
-                                        match item{
-                                            Ok(chunk) => {
-                                                // 获取当前的chunk
-                                                let mut total_size = total_response_size_clone.lock().await;
-                                                *total_size += chunk.len() as u64;
-                                                let mut api_total_size = api_total_response_size_clone.lock().await;
-                                                *api_total_size += chunk.len() as u64;
-                                                body_bytes.extend_from_slice(&chunk);
-                                            }
-                                            Err(e) => {
-                                                *api_err_count_clone.lock().await += 1;
-                                                *err_count_clone.lock().await += 1;
-                                                http_errors_clone.lock().await.increment(0, format!("获取响应流失败::{:?}", e), endpoint_clone.lock().await.url.clone()).await;
-                                                break
-                                            }
-                                        };
-                                    }
-                                    if verbose {
-                                        let body_bytes_clone = body_bytes.clone();
-                                        let buffer = String::from_utf8(body_bytes_clone).expect("无法转换响应体为字符串");
-                                        println!("{:+?}", buffer);
-                                    }
-                                    // 断言失败的标志
-                                    let mut assertion_failed = false;
-                                    // 断言
-                                    if let Some(assert_options) = assert_options_clone{
-                                        // 多断言
-                                        for assert_option in assert_options {
-                                            if body_bytes.len() == 0{
-                                                eprintln!("无法获取到结构体，不进行断言");
-                                                break
-                                            }
-                                            let json_value: Value = match serde_json::from_slice(&*body_bytes) {
-                                                Err(e) =>{
-                                                    if verbose{
-                                                        eprintln!("JSONPath 查询失败: {}", e);
-                                                    };
-                                                    *err_count_clone.lock().await += 1;
-                                                    *api_err_count_clone.lock().await += 1;
-                                                    assert_errors_clone.lock().await.increment(
-                                                        String::from(endpoint_clone.lock().await.url.clone()),
-                                                        format!("{:?}-JSONPath查询失败:{:?}", api_name_clone, e)).await;
-                                                    assertion_failed = true;
-                                                    break;
-                                                }
-                                                Ok(val) => {
-                                                    val
-                                                }
-                                            };
-                                            // 通过jsonpath提取数据
-                                            match select(&json_value, &*assert_option.jsonpath) {
-                                                Ok(results) => {
-                                                    if results.is_empty(){
-                                                        if verbose{
-                                                            eprintln!("没有匹配到任何结果");
-                                                        }
-                                                        *err_count_clone.lock().await += 1;
-                                                        *api_err_count_clone.lock().await += 1;
-                                                        assert_errors_clone.lock().await.increment(
-                                                            String::from(endpoint_clone.lock().await.url.clone()),
-                                                            format!("{:?}-JSONPath查询失败:{:?}", api_name_clone, "没有匹配到任何结果")).await;
-                                                        assertion_failed = true;
-                                                        break;
-                                                    }
-                                                    if results.len() >1{
-                                                        if verbose{
-                                                            eprintln!("匹配到多个值，无法进行断言");
-                                                        }
-                                                        *err_count_clone.lock().await += 1;
-                                                        *api_err_count_clone.lock().await += 1;
-                                                        assert_errors_clone.lock().await.increment(
-                                                            String::from(endpoint_clone.lock().await.url.clone()),
-                                                            format!("{:?}-JSONPath查询失败:{:?}", api_name_clone, "匹配到多个值，无法进行断言")).await;
-                                                        assertion_failed = true;
-                                                        break;
-                                                    }
-                                                    // 取出匹配到的唯一值
-                                                    if let Some(result) = results.get(0).map(|&v|v) {
-                                                        if *result != assert_option.reference_object{
-                                                            // 将失败情况加入到一个容器中
-                                                            assert_errors_clone.
-                                                                lock().
-                                                                await.
-                                                                increment(
-                                                                    String::from(endpoint_clone.lock().await.url.clone()),
-                                                                    format!(
-                                                                        "{:?}-预期结果：{:?}, 实际结果：{:?}", api_name_clone, assert_option.reference_object, result
-                                                                    )
-                                                                ).await;
-                                                            if verbose{
-                                                                eprintln!("{:?}-预期结果：{:?}, 实际结果：{:?}",api_name_clone ,assert_option.reference_object, result)
-                                                            }
-                                                            // 错误数据增加
-                                                            *err_count_clone.lock().await += 1;
-                                                            *api_err_count_clone.lock().await += 1;
-                                                            // 退出断言
-                                                            assertion_failed = true;
-                                                            break;
-                                                        }
-                                                    }
-                                                },
-                                                Err(e) => {
-                                                    eprintln!("JSONPath 查询失败: {}", e);
-                                                    assertion_failed = true;
-                                                    break;
-                                                },
-                                            }
-                                        }
-                                    }
-                                    if !assertion_failed{
-                                        // 正确统计+1
-                                        *successful_requests_clone.lock().await += 1;
-                                        // api正确统计+1
-                                        *api_successful_requests_clone.lock().await += 1;
-                                    };
-
-                                    let api_total_data_bytes = *api_total_response_size_clone.lock().await;
-                                    let api_total_data_kb = api_total_data_bytes as f64 / 1024f64;
-                                    let api_total_requests = api_total_requests_clone.lock().await.clone();
-                                    let api_success_requests = api_successful_requests_clone.lock().await.clone();
-                                    let api_rps = api_total_requests as f64/ (Instant::now() - test_start).as_secs_f64();
-                                    let api_success_rate = api_success_requests as f64 / api_total_requests as f64 * 100.0;
-                                    let throughput_per_second_kb = api_total_data_kb / (Instant::now() - test_start).as_secs_f64();
-                                    // 给结果赋值
-                                    let  mut api_res = api_result_clone.lock().await;
-                                    api_res.response_time_95 = *api_histogram.percentile(95.0)?.range().start();
-                                    api_res.response_time_99 = *api_histogram.percentile(99.0)?.range().start();
-                                    api_res.median_response_time = *api_histogram.percentile(50.0)?.range().start();
-                                    api_res.max_response_time = *api_max_rt;
-                                    api_res.min_response_time = *api_min_rt;
-                                    api_res.total_requests = api_total_requests;
-                                    api_res.total_data_kb = api_total_data_kb;
-                                    api_res.rps = api_rps;
-                                    api_res.success_rate = api_success_rate;
-                                    api_res.err_count = *api_err_count_clone.lock().await;
-                                    api_res.throughput_per_second_kb = throughput_per_second_kb;
-                                    api_res.error_rate = api_res.err_count as f64 / api_res.total_requests as f64 * 100.0;
-                                    api_res.method = method_clone.clone().to_uppercase();
-                                    api_res.concurrent_number = *api_concurrent_number_clone.lock().await;
-                                    // 向最终结果中添加数据
-                                    let mut res = results_clone.lock().await;
-                                    if index < res.len() {
-                                        res[index] = api_res.clone();
-                                    } else {
-                                        eprintln!("results索引越界");
-                                    }
-                                }
-                                // 状态码错误
-                                _ =>{
-                                    *err_count_clone.lock().await += 1;
-                                    *api_err_count_clone.lock().await += 1;
-                                    let status_code = u16::from(response.status());
-                                    let err_msg = format!("HTTP 错误: 状态码 {}", status_code);
-                                    let url = response.url().to_string();
-                                    http_errors_clone.lock().await.increment(status_code, err_msg, url).await;
-                                    if verbose{
-                                        println!("{:?}-HTTP 错误: 状态码 {:?}",api_name_clone, status_code)
-                                    }
-                                }
-                            }
-
-                        },
-                        Err(e) => {
-                            *err_count_clone.lock().await += 1;
-                            *api_err_count_clone.lock().await+=1;
-                            let status_code: u16;
-                            match e.status(){
-                                None => {
-                                    status_code = 0;
-                                }
-                                Some(code) => {
-                                    status_code = u16::from(code);
-                                }
-                            }
-                            let err_msg = e.to_string();
-                            http_errors_clone.lock().await.increment(status_code, err_msg, endpoint_clone.lock().await.url.clone()).await;
-                        },
+                        spawned += 1;
                     }
                 }
-                Ok(())
-            });
-            handles.push(handle);
+            }));
         }
     }
 
+    // 周期快照通过watch发给每个推送目标各自的任务，慢的推送目标只会跳过中间的快照，不会阻塞统计
+    let (snapshot_sender, _) = watch::channel::<Option<Arc<BatchResult>>>(None);
+    let sink_tasks: Vec<JoinHandle<()>> = batch_option
+        .result_sinks
+        .iter()
+        .map(|sink| {
+            let sink = sink.clone();
+            let mut receiver = snapshot_sender.subscribe();
+            tokio::spawn(async move {
+                while receiver.changed().await.is_ok() {
+                    let snapshot = receiver.borrow_and_update().clone();
+                    if let Some(result) = snapshot {
+                        if let Err(e) = sink.on_interval(&result).await {
+                            eprintln!("推送周期结果失败::{:?}", e);
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
     // 共享任务状态
-    {
-        let total_requests_clone = Arc::clone(&total_requests);
-        let successful_requests_clone = Arc::clone(&successful_requests);
-        let histogram_clone = Arc::clone(&histogram);
-        let total_response_size_clone = Arc::clone(&total_response_size);
-        let http_errors_clone = Arc::clone(&http_errors);
-        let err_count_clone = Arc::clone(&err_count);
-        let max_resp_time_clone = Arc::clone(&max_response_time);
-        let min_resp_time_clone = Arc::clone(&min_response_time);
-        let assert_error_clone = Arc::clone(&assert_errors);
-        let api_results_clone = Arc::clone(&results_arc);
-        let concurrent_number_clone = Arc::clone(&concurrent_number);
+    let snapshot_task = {
+        let context = context.clone();
 
         tokio::spawn(async move {
             let mut interval = interval(Duration::from_secs(1));
-            let should_stop = *RESULTS_SHOULD_STOP.lock().await;
-            while !should_stop {
+            loop {
                 interval.tick().await;
+                if *RESULTS_SHOULD_STOP.lock().await {
+                    break;
+                }
+                // 预热期间推送预热的统计，之后从预热结束开始统计
+                let warm_up = Instant::now() < context.warm_up_end;
+                let (stats, window_start) = if warm_up {
+                    (&context.warm_up_stats, test_start)
+                } else {
+                    (&context.stats, context.warm_up_end)
+                };
 
-                let err_count = *err_count_clone.lock().await;
-                let max_response_time_c = *max_resp_time_clone.lock().await;
-                let min_response_time_c = *min_resp_time_clone.lock().await;
-                let total_duration = (Instant::now() - test_start).as_secs_f64();
-                let total_requests = *total_requests_clone.lock().await as f64;
-                let successful_requests = *successful_requests_clone.lock().await as f64;
-                let success_rate = successful_requests / total_requests * 100.0;
-                let error_rate = err_count as f64 / total_requests * 100.0;
-                let histogram = histogram_clone.lock().await;
-                let total_response_size_kb = *total_response_size_clone.lock().await as f64 / 1024.0;
-                let throughput_kb_s = total_response_size_kb / total_duration;
-                let http_errors = http_errors_clone.lock().await.errors.clone();
-                let assert_errors = assert_error_clone.lock().await.errors.clone();
-                let rps = total_requests / total_duration;
+                let err_count = *stats.err_count.lock().await;
+                let max_response_time_c = *stats.max_response_time.lock().await;
+                let min_response_time_c = *stats.min_response_time.lock().await;
+                let total_duration = (Instant::now() - window_start).as_secs_f64();
+                let total_requests = *stats.total_requests.lock().await as f64;
+                let successful_requests = *stats.successful_requests.lock().await as f64;
+                let success_rate = percent(successful_requests, total_requests);
+                let error_rate = percent(err_count as f64, total_requests);
+                let histogram = stats.histogram.lock().await;
+                let total_response_size_kb = *stats.total_response_size.lock().await as f64 / 1024.0;
+                let throughput_kb_s = per_second(total_response_size_kb, total_duration);
+                let http_errors = stats.http_errors.errors.clone();
+                let assert_errors = stats.assert_errors.errors.clone();
+                let rps = per_second(total_requests, total_duration);
                 let resp_median_line = match  histogram.percentile(50.0){
                     Ok(bucket) => *bucket.range().start(),
                     Err(_) =>0
@@ -549,10 +655,9 @@ pub async fn batch(
                     Ok(n) => n.as_millis(),
                     Err(_) => 0,
                 };
-                let api_results = api_results_clone.lock().await;
-                // println!("{:?}", api_results);
+                let api_results = stats.api_results.lock().await;
                 // 已开启的并发量
-                let total_concurrent_number = *concurrent_number_clone.lock().await;
+                let total_concurrent_number = *context.concurrent_number.lock().await;
                 let mut queue = RESULTS_QUEUE.lock().await;
                 // 如果队列中有了一个数据了，就移除旧数据
                 if queue.len() == 1 {
@@ -577,19 +682,30 @@ pub async fn batch(
                     assert_errors: assert_errors.lock().await.clone(),
                     total_concurrent_number,
                     api_results: api_results.to_vec().clone(),
+                    end_reason: EndReason::Duration,
+                    warm_up,
+                    interrupted_requests: *stats.interrupted_requests.lock().await,
                 };
                 let elapsed = test_start.elapsed();
                 if verbose{
                     println!("{:?}-{:#?}",elapsed.as_millis(), result.clone());
                 };
                 // 添加新结果
-                queue.push_back(result);
+                queue.push_back(result.clone());
+                drop(queue);
+                drop(histogram);
+                drop(api_results);
+                // 推送周期快照
+                snapshot_sender.send_replace(Some(Arc::new(result)));
             }
-        });
-    }
+        })
+    };
 
     // 等待任务完成
-    let task_results = join_all(handles).await;
+    let mut task_results = join_all(handles).await;
+    join_all(supervisors).await;
+    let extra = std::mem::take(&mut *extra_handles.lock().await);
+    task_results.extend(join_all(extra).await);
     for task_result in task_results{
         match task_result {
             Ok(res) => {
@@ -611,51 +727,803 @@ pub async fn batch(
     }
 
     // 对结果进行赋值
-    let err_count_clone = Arc::clone(&err_count);
-    let err_count = *err_count_clone.lock().await;
-    let total_duration = (Instant::now() - test_start).as_secs_f64();
-    let total_requests = *total_requests.lock().await as u64;
-    let successful_requests = *successful_requests.lock().await as f64;
-    let success_rate = successful_requests / total_requests as f64 * 100.0;
-    let histogram = histogram.lock().await;
-    let total_response_size_kb = *total_response_size.lock().await as f64 / 1024.0;
-    let throughput_kb_s = total_response_size_kb / test_duration_secs as f64;
-    let http_errors = http_errors.lock().await.errors.clone();
-    let assert_errors = assert_errors.lock().await.errors.clone();
+    let stats = &context.stats;
+    let err_count = *stats.err_count.lock().await;
+    let total_duration = (Instant::now() - warm_up_end).as_secs_f64();
+    let total_requests = *stats.total_requests.lock().await;
+    let successful_requests = *stats.successful_requests.lock().await as f64;
+    let success_rate = percent(successful_requests, total_requests as f64);
+    let histogram = stats.histogram.lock().await;
+    let total_response_size_kb = *stats.total_response_size.lock().await as f64 / 1024.0;
+    // 按实际的统计时长计算，包括优雅停止的等待时间和提前结束的情况
+    let throughput_kb_s = per_second(total_response_size_kb, total_duration);
     let timestamp = match SystemTime::now().duration_since(UNIX_EPOCH) {
         Ok(n) => n.as_millis(),
         Err(_) => 0,
     };
-    let api_results = results_arc.lock().await;
-    let error_rate = err_count as f64 / total_requests as f64 * 100.0;
-    let total_concurrent_number_clone = concurrent_number.lock().await.clone();
+    let api_results = stats.api_results.lock().await;
+    let error_rate = percent(err_count as f64, total_requests as f64);
+    let total_concurrent_number_clone = *context.concurrent_number.lock().await;
+    let end_reason = *context.end_reason.lock();
 
-    let result = Ok(BatchResult{
+    let result = BatchResult{
         total_duration,
         success_rate,
         error_rate,
-        median_response_time: *histogram.percentile(50.0)?.range().start(),
-        response_time_95: *histogram.percentile(95.0)?.range().start(),
-        response_time_99: *histogram.percentile(99.0)?.range().start(),
+        median_response_time: histogram.percentile(50.0).map(|b| *b.range().start()).unwrap_or(0),
+        response_time_95: histogram.percentile(95.0).map(|b| *b.range().start()).unwrap_or(0),
+        response_time_99: histogram.percentile(99.0).map(|b| *b.range().start()).unwrap_or(0),
         total_requests,
-        rps: total_requests as f64 / test_duration_secs as f64,
-        max_response_time: *max_response_time.lock().await,
-        min_response_time: *min_response_time.lock().await,
-        err_count:*err_count_clone.lock().await,
+        rps: per_second(total_requests as f64, total_duration),
+        max_response_time: *stats.max_response_time.lock().await,
+        min_response_time: *stats.min_response_time.lock().await,
+        err_count,
         total_data_kb:total_response_size_kb,
         throughput_per_second_kb: throughput_kb_s,
-        http_errors: http_errors.lock().await.clone(),
+        http_errors: stats.http_errors.errors.lock().await.clone(),
         timestamp,
-        assert_errors: assert_errors.lock().await.clone(),
+        assert_errors: stats.assert_errors.errors.lock().await.clone(),
         total_concurrent_number: total_concurrent_number_clone,
         api_results:api_results.to_vec().clone(),
-    });
-    let mut should_stop = RESULTS_SHOULD_STOP.lock().await;
-    *should_stop = true;
+        end_reason,
+        warm_up: false,
+        interrupted_requests: *stats.interrupted_requests.lock().await,
+    };
+    drop(histogram);
+    drop(api_results);
+    *RESULTS_SHOULD_STOP.lock().await = true;
+    // 停止周期快照和还没完成的周期推送，最终结果由on_finish推送，慢的推送目标不拖延压测结束
+    snapshot_task.abort();
+    for task in sink_tasks {
+        task.abort();
+        let _ = task.await;
+    }
+    // 写完请求日志
+    if let Some(logger) = request_logger {
+        if let Err(e) = logger.finish().await {
+            eprintln!("写入请求日志失败::{:?}", e);
+        }
+        if logger.dropped() > 0 {
+            eprintln!("{}", localized(
+                format!("请求日志写入跟不上，丢弃了{}条记录", logger.dropped()),
+                format!("request log could not keep up, {} records dropped", logger.dropped()),
+            ));
+        }
+    }
+    // 推送最终结果
+    for sink_result in join_all(batch_option.result_sinks.iter().map(|sink| sink.on_finish(&result))).await {
+        if let Err(e) = sink_result {
+            eprintln!("推送最终结果失败::{:?}", e);
+        }
+    }
     eprintln!("测试完成！");
-    result
+    Ok(result)
+}
+
+// 创建并发任务，use_permit为false时不受阶梯加压控制(运行中追加的并发)
+fn spawn_worker(
+    context: Arc<RequestContext>,
+    group: Arc<WorkerGroup>,
+    control: Option<Arc<BatchControl>>,
+    slot: usize,
+    use_permit: bool,
+    test_end: Instant,
+) -> Result<WorkerHandle, EngineError> {
+    let mut clients = context.worker_clients()?;
+    let handle: WorkerHandle = tokio::spawn(async move {
+        let semaphore = group.controller.get_semaphore();
+        // 停止或者提前结束时不再等待阶梯加压的许可
+        let _permit = if use_permit {
+            tokio::select! {
+                permit = semaphore.acquire() => match permit {
+                    Ok(permit) => Some(permit),
+                    // 信号量被关闭时直接结束
+                    Err(_) => return Ok(()),
+                },
+                reason = wait_interrupted(&context, &control) => {
+                    context.record_end(reason);
+                    return Ok(());
+                }
+            }
+        } else {
+            None
+        };
+        // 按权重拆分时并发数同时计入接口
+        let fixed_endpoint = match &group.selector {
+            EndpointSelector::Fixed(index) => Some(&context.endpoints[*index]),
+            EndpointSelector::Mix(_) => None,
+        };
+        // 预热期间逐步启动，每个并发按编号错开
+        if context.warm_up_ramp && use_permit {
+            let offset = context.warm_up_end.duration_since(context.test_start).mul_f64(slot as f64 / group.concurrency as f64);
+            tokio::select! {
+                _ = tokio::time::sleep_until((context.test_start + offset).into()) => {},
+                reason = wait_interrupted(&context, &control) => {
+                    context.record_end(reason);
+                    return Ok(());
+                }
+            }
+        }
+        let concurrent_number = &context.concurrent_number;
+        // 统计并发数
+        *concurrent_number.lock().await += 1;
+        if let Some(state) = fixed_endpoint {
+            *state.concurrent_number.lock().await += 1;
+        }
+        let mut rng = FastRng::new();
+        // 按顺序选择接口时当前并发的位置
+        let mut step = 0usize;
+        // 已完成的迭代次数
+        let mut iterations = 0u64;
+        // 当前迭代的开始时间，按顺序执行场景时一次迭代包含多个请求
+        let mut iteration_start: Option<Instant> = None;
+        let end_reason = loop {
+            // 暂停或者并发数被调低时挂起，挂起期间不计入并发数
+            if let Some(control) = &control {
+                let active = |s: &ControlState| s.stopped || (!s.paused && slot < endpoint_concurrency(s, group.weight_ratio, group.concurrency));
+                if !active(&control.state()) {
+                    *concurrent_number.lock().await -= 1;
+                    if let Some(state) = fixed_endpoint {
+                        *state.concurrent_number.lock().await -= 1;
+                    }
+                    let mut receiver = control.subscribe();
+                    tokio::select! {
+                        _ = tokio::time::timeout_at(test_end.into(), receiver.wait_for(|s| active(s))) => {},
+                        _ = context.wait_finished() => {},
+                    }
+                    *concurrent_number.lock().await += 1;
+                    if let Some(state) = fixed_endpoint {
+                        *state.concurrent_number.lock().await += 1;
+                    }
+                }
+                if control.state().stopped {
+                    break EndReason::Stopped;
+                }
+            }
+            if Instant::now() >= test_end {
+                break EndReason::Duration;
+            }
+            if let Some(reason) = *context.finished.borrow() {
+                break reason;
+            }
+            if context.iterations_per_vu.is_some_and(|max| iterations >= max) {
+                break EndReason::Iterations;
+            }
+            let started = *iteration_start.get_or_insert_with(Instant::now);
+            // 选择接口并占用请求预算
+            let selected = match &group.selector {
+                EndpointSelector::Fixed(index) => context.reserve_endpoint(*index).then(|| (&context.endpoints[*index], false)),
+                EndpointSelector::Mix(mixer) => context.pick_endpoint(mixer, &mut rng, &mut step).map(|state| (state, true)),
+            };
+            let Some((state, count_in_flight)) = selected else {
+                if context.all_budgets_exhausted() {
+                    context.finish(EndReason::EndpointBudget);
+                }
+                break EndReason::EndpointBudget;
+            };
+            if !context.reserve_request() {
+                context.finish(EndReason::MaxRequests);
+                break EndReason::MaxRequests;
+            }
+            // 按请求开始的时间区分预热和正式统计
+            let warm_up = Instant::now() < context.warm_up_end;
+            begin_request(&context, state, warm_up, count_in_flight).await;
+            context.refresh_clients(&mut clients);
+            let request = send_request(&context, &clients.clients, state, warm_up, count_in_flight);
+            // 到达截止时间后最多再等待优雅停止时间，超时的请求记为中断
+            let completed = match context.graceful_stop {
+                None => {
+                    request.await;
+                    true
+                }
+                Some(graceful_stop) => tokio::time::timeout_at((test_end + graceful_stop).into(), request).await.is_ok(),
+            };
+            if !completed {
+                record_interrupted(&context, state, warm_up, count_in_flight).await;
+                continue;
+            }
+            let iteration_done = match &group.selector {
+                EndpointSelector::Fixed(_) => true,
+                EndpointSelector::Mix(mixer) => mixer.completes_iteration(step),
+            };
+            if iteration_done {
+                iterations += 1;
+                iteration_start = None;
+            }
+            // 思考时间和固定节奏，不计入响应时间，固定节奏按整个迭代计算
+            let think_until = state.endpoint.think_time.as_ref().map(|t| Instant::now() + t.sample(&mut rng));
+            let pacing_until = context.pacing.filter(|_| iteration_done).map(|pacing| started.checked_add(pacing).unwrap_or(test_end));
+            if let Some(wait_until) = think_until.max(pacing_until) {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(wait_until.min(test_end).into()) => {},
+                    reason = wait_interrupted(&context, &control) => break reason,
+                }
+            }
+        };
+        context.record_end(end_reason);
+        Ok(())
+    });
+    Ok(handle)
+}
+
+// 开始一次请求，在发送前统计请求数，保证请求被中断时统计是完整的
+// count_in_flight为true时接口并发数按进行中的请求统计
+async fn begin_request(context: &RequestContext, state: &EndpointState, warm_up: bool, count_in_flight: bool) {
+    let (stats, api, _) = context.stats_for(state, warm_up);
+    // 总请求数
+    *stats.total_requests.lock().await += 1;
+    // api请求数
+    *api.total_requests.lock().await += 1;
+    if count_in_flight {
+        *state.concurrent_number.lock().await += 1;
+    }
+}
+
+// 优雅停止时间内没有完成的请求单独统计，不计入请求总数
+async fn record_interrupted(context: &RequestContext, state: &EndpointState, warm_up: bool, count_in_flight: bool) {
+    let (stats, api, _) = context.stats_for(state, warm_up);
+    *stats.total_requests.lock().await -= 1;
+    *stats.interrupted_requests.lock().await += 1;
+    let api_total_requests = {
+        let mut total_requests = api.total_requests.lock().await;
+        *total_requests -= 1;
+        *total_requests
+    };
+    let api_interrupted_requests = {
+        let mut interrupted_requests = api.interrupted_requests.lock().await;
+        *interrupted_requests += 1;
+        *interrupted_requests
+    };
+    let api_success_requests = *api.successful_requests.lock().await;
+    let mut api_res = api.result.lock().await;
+    api_res.method = state.endpoint.method.to_uppercase();
+    api_res.total_requests = api_total_requests;
+    api_res.interrupted_requests = api_interrupted_requests;
+    api_res.success_rate = percent(api_success_requests as f64, api_total_requests as f64);
+    api_res.error_rate = percent(api_res.err_count as f64, api_total_requests as f64);
+    stats.api_results.lock().await[state.index] = api_res.clone();
+    drop(api_res);
+    if count_in_flight {
+        *state.concurrent_number.lock().await -= 1;
+    }
+}
+
+// 按接口配置构建请求，只记录失败请求时失败后再构建一次用来读取请求详情
+fn build_request(context: &RequestContext, client: &Client, state: &EndpointState) -> RequestBuilder {
+    let endpoint = &state.endpoint;
+    let mut request = client.request(state.method.clone(), state.url.clone());
+    if endpoint.timeout_secs > 0 {
+        request = request.timeout(Duration::from_secs(endpoint.timeout_secs));
+    }
+    // 要求HTTP/2，ALPN没有协商出HTTP/2时请求报错
+    if endpoint.http_version == HttpVersion::Http2 {
+        request = request.version(Version::HTTP_2);
+    }
+    // 构建请求头
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, context.user_agent.clone());
+    if let Some(headers_map) = &endpoint.headers {
+        // 请求头在压测开始前已经检查过
+        headers.extend(headers_map.iter().filter_map(|(k, v)| {
+            Some((k.parse::<HeaderName>().ok()?, v.parse::<HeaderValue>().ok()?))
+        }));
+    }
+    // 构建cookies
+    if let Some(cookie) = &endpoint.cookies {
+        if let Ok(h) = HeaderValue::from_str(cookie) {
+            headers.insert(COOKIE, h);
+        }
+    }
+    request = request.headers(headers);
+    // 构建json请求
+    if let Some(json_value) = &endpoint.json {
+        request = request.json(json_value);
+    }
+    // 构建form表单
+    if let Some(form_data) = &endpoint.form_data {
+        request = request.form(form_data);
+    };
+    request
+}
+
+// 一次请求的请求日志记录
+enum PendingRecord {
+    // 没有请求日志或者没有被采样
+    Skipped,
+    // 发送前已经采样并创建的记录
+    Sampled(Box<RequestRecord>),
+    // 只记录失败请求，记下开始时间，请求失败后再采样和创建
+    Deferred(u128),
+}
+
+impl PendingRecord {
+    // 请求结束后取出需要写入的记录，成功的请求不会创建延后的记录
+    fn take<'a>(&mut self, context: &'a RequestContext, client: &Client, state: &EndpointState, failed: bool) -> Option<(&'a RequestLogger, RequestRecord)> {
+        let logger = context.request_logger.as_deref()?;
+        match std::mem::replace(self, PendingRecord::Skipped) {
+            PendingRecord::Skipped => None,
+            PendingRecord::Sampled(record) => Some((logger, *record)),
+            PendingRecord::Deferred(timestamp) => {
+                if !failed || !logger.sample() {
+                    return None;
+                }
+                let request = build_request(context, client, state).build().ok();
+                let mut record = new_request_record(state, request.as_ref(), logger.capture_detail());
+                record.timestamp = timestamp;
+                Some((logger, record))
+            }
+        }
+    }
+}
+
+// 发送请求失败的原因，unix socket接口不经过reqwest发送
+enum SendError {
+    Http(reqwest::Error),
+    Unix(UnixRequestError),
+}
+
+// 发送一次请求并更新统计，需要先调用begin_request
+async fn send_request(context: &RequestContext, clients: &HttpClients, state: &EndpointState, warm_up: bool, count_in_flight: bool) {
+    let (stats, api, window_start) = context.stats_for(state, warm_up);
+    let endpoint = &state.endpoint;
+    let verbose = context.verbose;
+    // 先构建请求，请求记录直接读取构建好的请求，不需要再复制一份
+    let (client, request) = build_request(context, clients.get(state.client_index), state).build_split();
+    // 请求记录，先采样再创建；只记录失败请求时等请求结束后再决定
+    let mut request_record = match &context.request_logger {
+        Some(logger) if logger.errors_only() => PendingRecord::Deferred(unix_millis()),
+        Some(logger) if logger.sample() => PendingRecord::Sampled(Box::new(new_request_record(state, request.as_ref().ok(), logger.capture_detail()))),
+        _ => PendingRecord::Skipped,
+    };
+    // 记录开始时间
+    let start = Instant::now();
+    // 请求完成后才写入的连接统计
+    let mut connection = None;
+    // 发送请求
+    let response = match (request, clients.get_unix(state.client_index)) {
+        (Ok(request), Some(unix_client)) => unix_client.execute(request).await.map_err(SendError::Unix),
+        (Ok(request), None) => client.execute(request).await.map_err(SendError::Http),
+        (Err(e), _) => Err(SendError::Http(e)),
+    };
+    match response {
+        Ok(response) => {
+            // 新建还是复用的连接和协商的协议版本，请求完成后再统计
+            connection = Some((context.is_new_connection(&response), format!("{:?}", response.version())));
+            let status = response.status();
+            match status{
+                // 正确的状态码
+                status if is_expected_status(status, &endpoint.expected_status) => {
+                    /*
+                    ---------------
+                        请求成功
+                    ---------------
+                    */
+                    // 响应时间
+                    let duration = start.elapsed().as_millis() as u64;
+                    // 响应头留给请求记录，读取响应体不需要
+                    let version = response.version();
+                    let mut response = response;
+                    let response_headers = std::mem::take(response.headers_mut());
+                    // 读取响应体失败的原因
+                    let mut body_error = None;
+                    // 响应流
+                    let mut stream = response.bytes_stream();
+                    // 响应体
+                    let mut body_bytes = Vec::new();
+                    while let Some(item) = stream.next().await {
+                        match item{
+                            Ok(chunk) => {
+                                body_bytes.extend_from_slice(&chunk);
+                            }
+                            Err(e) => {
+                                *api.err_count.lock().await += 1;
+                                *stats.err_count.lock().await += 1;
+                                body_error = Some(e.to_string());
+                                stats.http_errors.increment(0, localized(format!("获取响应流失败::{:?}", e), format!("failed to read response body::{:?}", e)), endpoint.url.clone()).await;
+                                break
+                            }
+                        };
+                    }
+                    // 响应体读完后再写入响应时间和流量，优雅停止时被中断的请求不会留下部分统计
+                    // 最大请求时间
+                    {
+                        let mut max_rt = stats.max_response_time.lock().await;
+                        *max_rt = (*max_rt).max(duration);
+                    }
+                    // api最大请求时间
+                    {
+                        let mut api_max_rt = api.max_response_time.lock().await;
+                        *api_max_rt = (*api_max_rt).max(duration);
+                    }
+                    // 最小响应时间
+                    {
+                        let mut min_rt = stats.min_response_time.lock().await;
+                        *min_rt = (*min_rt).min(duration);
+                    }
+                    // api最小响应时间
+                    {
+                        let mut api_min_rt = api.min_response_time.lock().await;
+                        *api_min_rt = (*api_min_rt).min(duration);
+                    }
+                    // 将数据放入全局统计桶
+                    if let Err(e) = stats.histogram.lock().await.increment(duration){
+                        eprintln!("histogram设置数据错误:{:?}", e)
+                    };
+                    // 将数据放入api统计桶
+                    if let Err(e) = api.histogram.lock().await.increment(duration){
+                        eprintln!("api histogram设置错误:{:?}", e)
+                    }
+                    *stats.total_response_size.lock().await += body_bytes.len() as u64;
+                    *api.total_response_size.lock().await += body_bytes.len() as u64;
+                    if verbose {
+                        let buffer = String::from_utf8_lossy(&body_bytes);
+                        println!("{:+?}", buffer);
+                    }
+                    // 断言失败的标志
+                    let mut assertion_failed = false;
+                    // 断言失败信息
+                    let mut assertion_failures: Vec<String> = Vec::new();
+                    // 断言
+                    if let Some(assert_options) = &endpoint.assert_options{
+                        // 多断言
+                        for assert_option in assert_options {
+                            if body_bytes.is_empty(){
+                                eprintln!("{}", localized("无法获取到结构体，不进行断言", "empty response body, assertions skipped"));
+                                break
+                            }
+                            let json_value: Value = match serde_json::from_slice(&body_bytes) {
+                                Err(e) =>{
+                                    if verbose{
+                                        eprintln!("{}", localized(format!("JSONPath 查询失败: {}", e), format!("JSONPath query failed: {}", e)));
+                                    };
+                                    *stats.err_count.lock().await += 1;
+                                    *api.err_count.lock().await += 1;
+                                    let message = localized(
+                                        format!("{:?}-JSONPath查询失败:{:?}", endpoint.name, e),
+                                        format!("{:?}-JSONPath query failed:{:?}", endpoint.name, e),
+                                    );
+                                    assertion_failures.push(message.clone());
+                                    stats.assert_errors.increment(endpoint.url.clone(), message).await;
+                                    assertion_failed = true;
+                                    break;
+                                }
+                                Ok(val) => {
+                                    val
+                                }
+                            };
+                            // 通过jsonpath提取数据
+                            match select(&json_value, &assert_option.jsonpath) {
+                                Ok(results) => {
+                                    if results.is_empty(){
+                                        if verbose{
+                                            eprintln!("{}", localized("没有匹配到任何结果", "no value matched"));
+                                        }
+                                        *stats.err_count.lock().await += 1;
+                                        *api.err_count.lock().await += 1;
+                                        let message = localized(
+                                            format!("{:?}-JSONPath查询失败:{:?}", endpoint.name, "没有匹配到任何结果"),
+                                            format!("{:?}-JSONPath query failed:{:?}", endpoint.name, "no value matched"),
+                                        );
+                                        assertion_failures.push(message.clone());
+                                        stats.assert_errors.increment(endpoint.url.clone(), message).await;
+                                        assertion_failed = true;
+                                        break;
+                                    }
+                                    if results.len() >1{
+                                        if verbose{
+                                            eprintln!("{}", localized("匹配到多个值，无法进行断言", "multiple values matched, cannot assert"));
+                                        }
+                                        *stats.err_count.lock().await += 1;
+                                        *api.err_count.lock().await += 1;
+                                        let message = localized(
+                                            format!("{:?}-JSONPath查询失败:{:?}", endpoint.name, "匹配到多个值，无法进行断言"),
+                                            format!("{:?}-JSONPath query failed:{:?}", endpoint.name, "multiple values matched, cannot assert"),
+                                        );
+                                        assertion_failures.push(message.clone());
+                                        stats.assert_errors.increment(endpoint.url.clone(), message).await;
+                                        assertion_failed = true;
+                                        break;
+                                    }
+                                    // 取出匹配到的唯一值
+                                    if let Some(result) = results.first().copied() {
+                                        if *result != assert_option.reference_object{
+                                            let message = localized(
+                                                format!("{:?}-预期结果：{:?}, 实际结果：{:?}", endpoint.name, assert_option.reference_object, result),
+                                                format!("{:?}-expected: {:?}, actual: {:?}", endpoint.name, assert_option.reference_object, result),
+                                            );
+                                            if verbose{
+                                                eprintln!("{}", message)
+                                            }
+                                            assertion_failures.push(message.clone());
+                                            // 将失败情况加入到一个容器中
+                                            stats.assert_errors.increment(endpoint.url.clone(), message).await;
+                                            // 错误数据增加
+                                            *stats.err_count.lock().await += 1;
+                                            *api.err_count.lock().await += 1;
+                                            // 退出断言
+                                            assertion_failed = true;
+                                            break;
+                                        }
+                                    }
+                                },
+                                Err(e) => {
+                                    eprintln!("{}", localized(format!("JSONPath 查询失败: {}", e), format!("JSONPath query failed: {}", e)));
+                                    assertion_failures.push(localized(
+                                        format!("{:?}-JSONPath查询失败:{:?}", endpoint.name, e.to_string()),
+                                        format!("{:?}-JSONPath query failed:{:?}", endpoint.name, e.to_string()),
+                                    ));
+                                    assertion_failed = true;
+                                    break;
+                                },
+                            }
+                        }
+                    }
+                    if !assertion_failed{
+                        // 正确统计+1
+                        *stats.successful_requests.lock().await += 1;
+                        // api正确统计+1
+                        *api.successful_requests.lock().await += 1;
+                    };
+                    // 写入请求日志
+                    let failed = body_error.is_some() || assertion_failed;
+                    if let Some((logger, mut record)) = request_record.take(context, &client, state, failed) {
+                        record.status = Some(status.as_u16());
+                        record.latency_ms = duration;
+                        record_response_head(&mut record, version, &response_headers);
+                        record.bytes = body_bytes.len() as u64;
+                        if let Some(message) = body_error {
+                            record.error_kind = Some(ErrorKind::Body);
+                            record.error_message = Some(message);
+                        } else if assertion_failed {
+                            record.error_kind = Some(ErrorKind::Assertion);
+                        }
+                        record.assertion_failures = assertion_failures;
+                        if let Some(detail) = record.detail.as_mut() {
+                            detail.response_body = Some(String::from_utf8_lossy(&body_bytes).to_string());
+                        }
+                        logger.record(record);
+                    }
+
+                }
+                // 状态码错误
+                _ =>{
+                    *stats.err_count.lock().await += 1;
+                    *api.err_count.lock().await += 1;
+                    let status_code = u16::from(response.status());
+                    let err_msg = localized(format!("HTTP 错误: 状态码 {}", status_code), format!("HTTP error: status {}", status_code));
+                    stats.http_errors.increment(status_code, err_msg.clone(), endpoint.url.clone()).await;
+                    if verbose{
+                        println!("{:?}-{}", endpoint.name, err_msg)
+                    }
+                    // 写入请求日志
+                    if let Some((logger, mut record)) = request_record.take(context, &client, state, true) {
+                        record.status = Some(status_code);
+                        record.latency_ms = start.elapsed().as_millis() as u64;
+                        record.error_kind = Some(ErrorKind::HttpStatus);
+                        record.error_message = Some(err_msg);
+                        record_response_head(&mut record, response.version(), response.headers());
+                        let body = if record.detail.is_some() { response.bytes().await.ok() } else { None };
+                        if let Some(body) = body {
+                            record.bytes = body.len() as u64;
+                            if let Some(detail) = record.detail.as_mut() {
+                                detail.response_body = Some(String::from_utf8_lossy(&body).to_string());
+                            }
+                        }
+                        logger.record(record);
+                    }
+                }
+            }
+
+        },
+        Err(e) => {
+            *stats.err_count.lock().await += 1;
+            *api.err_count.lock().await += 1;
+            let status = match &e {
+                SendError::Http(e) => e.status().map(|code| code.as_u16()),
+                SendError::Unix(_) => None,
+            };
+            let status_code = status.unwrap_or(0);
+            // TLS握手失败、本地端口耗尽和连接不上unix socket单独归类，带上底层的原因
+            let (error_kind, err_msg) = match e {
+                SendError::Http(e) => {
+                    let err_msg = if let Some(reason) = tls_error_message(&e) {
+                        localized(format!("TLS握手失败::{}", reason), format!("TLS handshake failed::{}", reason))
+                    } else if let Some(reason) = port_exhausted_message(&e) {
+                        localized(format!("本地端口耗尽::{}", reason), format!("local ports exhausted::{}", reason))
+                    } else {
+                        e.to_string()
+                    };
+                    (ErrorKind::from_reqwest(&e), err_msg)
+                }
+                SendError::Unix(UnixRequestError { kind: ErrorKind::Connect, message }) => (
+                    ErrorKind::Connect,
+                    localized(format!("连接unix socket失败::{}", message), format!("failed to connect to unix socket::{}", message)),
+                ),
+                SendError::Unix(UnixRequestError { kind, message }) => (kind, message),
+            };
+            // 写入请求日志
+            if let Some((logger, mut record)) = request_record.take(context, &client, state, true) {
+                record.status = status;
+                record.latency_ms = start.elapsed().as_millis() as u64;
+                record.error_kind = Some(error_kind);
+                record.error_message = Some(err_msg.clone());
+                logger.record(record);
+            }
+            stats.http_errors.increment(status_code, err_msg, endpoint.url.clone()).await;
+        },
+    }
+    // 统计新建和复用的连接以及协商的协议版本
+    if let Some((new_connection, version)) = connection {
+        if new_connection {
+            *api.connections_opened.lock().await += 1;
+        } else {
+            *api.connections_reused.lock().await += 1;
+        }
+        *api.http_versions.lock().await.entry(version).or_insert(0) += 1;
+    }
+    // 更新接口结果，失败的请求也要更新
+    let elapsed = (Instant::now() - window_start).as_secs_f64();
+    let api_total_data_kb = *api.total_response_size.lock().await as f64 / 1024f64;
+    let api_total_requests = *api.total_requests.lock().await;
+    let api_success_requests = *api.successful_requests.lock().await;
+    // 给结果赋值
+    let mut api_res = api.result.lock().await;
+    {
+        let api_histogram = api.histogram.lock().await;
+        api_res.response_time_95 = api_histogram.percentile(95.0).map(|b| *b.range().start()).unwrap_or(0);
+        api_res.response_time_99 = api_histogram.percentile(99.0).map(|b| *b.range().start()).unwrap_or(0);
+        api_res.median_response_time = api_histogram.percentile(50.0).map(|b| *b.range().start()).unwrap_or(0);
+    }
+    api_res.max_response_time = *api.max_response_time.lock().await;
+    api_res.min_response_time = *api.min_response_time.lock().await;
+    api_res.total_requests = api_total_requests;
+    api_res.total_data_kb = api_total_data_kb;
+    api_res.rps = per_second(api_total_requests as f64, elapsed);
+    api_res.success_rate = percent(api_success_requests as f64, api_total_requests as f64);
+    api_res.err_count = *api.err_count.lock().await;
+    api_res.throughput_per_second_kb = per_second(api_total_data_kb, elapsed);
+    api_res.error_rate = percent(api_res.err_count as f64, api_res.total_requests as f64);
+    api_res.method = endpoint.method.to_uppercase();
+    api_res.concurrent_number = *state.concurrent_number.lock().await;
+    api_res.connections_opened = *api.connections_opened.lock().await;
+    api_res.connections_reused = *api.connections_reused.lock().await;
+    api_res.http_versions = api.http_versions.lock().await.clone();
+    // 向最终结果中添加数据
+    stats.api_results.lock().await[state.index] = api_res.clone();
+    drop(api_res);
+    if count_in_flight {
+        *state.concurrent_number.lock().await -= 1;
+    }
+}
+
+fn new_histogram() -> Histogram {
+    // 参数固定，不会出错
+    Histogram::new(14, 20).expect("创建histogram失败")
 }
 
+// 设置了期望状态码时按期望判断，否则2xx和3xx都算成功
+fn is_expected_status(status: StatusCode, expected_status: &Option<Vec<u16>>) -> bool {
+    match expected_status {
+        Some(codes) => codes.contains(&status.as_u16()),
+        None => matches!(
+            status,
+            StatusCode::OK
+                | StatusCode::CREATED
+                | StatusCode::ACCEPTED
+                | StatusCode::NON_AUTHORITATIVE_INFORMATION
+                | StatusCode::NO_CONTENT
+                | StatusCode::RESET_CONTENT
+                | StatusCode::PARTIAL_CONTENT
+                | StatusCode::MULTI_STATUS
+                | StatusCode::ALREADY_REPORTED
+                | StatusCode::IM_USED
+                | StatusCode::MULTIPLE_CHOICES
+                | StatusCode::MOVED_PERMANENTLY
+                | StatusCode::FOUND
+                | StatusCode::SEE_OTHER
+                | StatusCode::NOT_MODIFIED
+                | StatusCode::USE_PROXY
+                | StatusCode::TEMPORARY_REDIRECT
+                | StatusCode::PERMANENT_REDIRECT
+        ),
+    }
+}
+
+// 根据控制状态计算接口当前的并发数
+fn endpoint_concurrency(state: &ControlState, weight_ratio: f64, default_concurrency: usize) -> usize {
+    match state.concurrency {
+        None => default_concurrency,
+        Some(total) => ((total as f64 * weight_ratio).round() as usize).max(1),
+    }
+}
+
+// 等待压测被手动停止或者提前结束，返回结束原因
+async fn wait_interrupted(context: &RequestContext, control: &Option<Arc<BatchControl>>) -> EndReason {
+    tokio::select! {
+        _ = wait_stopped(control) => EndReason::Stopped,
+        reason = context.wait_finished() => reason,
+    }
+}
+
+// 等待压测被手动停止，没有控制器时一直等待
+async fn wait_stopped(control: &Option<Arc<BatchControl>>) {
+    match control {
+        None => futures::future::pending().await,
+        Some(control) => {
+            let mut receiver = control.subscribe();
+            let _ = receiver.wait_for(|s| s.stopped).await;
+        }
+    }
+}
+
+// 百分比，没有请求时为0，避免NaN无法写入json报告
+fn percent(part: f64, total: f64) -> f64 {
+    if total > 0.0 { part / total * 100.0 } else { 0.0 }
+}
+
+// 每秒的数量，统计时长为0时为0
+fn per_second(value: f64, secs: f64) -> f64 {
+    if secs > 0.0 { value / secs } else { 0.0 }
+}
+
+// 创建请求记录，需要时采集请求头和请求体
+fn unix_millis() -> u128 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(n) => n.as_millis(),
+        Err(_) => 0,
+    }
+}
+
+fn new_request_record(state: &EndpointState, request: Option<&Request>, capture_detail: bool) -> RequestRecord {
+    let endpoint = &state.endpoint;
+    let timestamp = unix_millis();
+    // unix socket接口记录原来的url
+    let url = match request {
+        Some(_) if parse_unix_url(&endpoint.url).is_some() => endpoint.url.clone(),
+        Some(r) => r.url().to_string(),
+        None => String::new(),
+    };
+    let detail = match (request, capture_detail) {
+        (Some(r), true) => Some(RequestDetail {
+            request_headers: header_pairs(r.headers()),
+            request_body: r.body().and_then(|b| b.as_bytes()).map(|b| String::from_utf8_lossy(b).to_string()),
+            request_content_type: r.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(|v| v.to_string()),
+            ..Default::default()
+        }),
+        _ => None,
+    };
+    RequestRecord {
+        timestamp,
+        name: endpoint.name.clone(),
+        url,
+        method: endpoint.method.to_uppercase(),
+        status: None,
+        latency_ms: 0,
+        bytes: 0,
+        error_kind: None,
+        error_message: None,
+        assertion_failures: Vec::new(),
+        http_version: None,
+        detail,
+    }
+}
+
+// 记录响应的版本和响应头
+fn record_response_head(record: &mut RequestRecord, version: Version, headers: &HeaderMap) {
+    record.http_version = Some(format!("{:?}", version));
+    if let Some(detail) = record.detail.as_mut() {
+        detail.http_version = format!("{:?}", version);
+        detail.response_headers = header_pairs(headers);
+        detail.response_content_type = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    }
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(k, v)| (k.to_string(), String::from_utf8_lossy(v.as_bytes()).to_string()))
+        .collect()
+}
 
 /*
     单测
@@ -664,10 +1532,14 @@ pub async fn batch(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use futures::future::BoxFuture;
     use crate::models::assert_option::AssertOption;
+    use crate::models::scenario::{Scenario, ScenarioStep};
+    use crate::sinks::result_sink::ResultSink;
 
 
     #[tokio::test]
+    #[allow(clippy::vec_init_then_push)]
     async fn test_batch() {
         let mut assert_vec: Vec<AssertOption> = Vec::new();
         let ref_obj = Value::from(2000000);
@@ -680,11 +1552,8 @@ mod tests {
             method: "GET".to_string(),
             timeout_secs: 10,
             weight: 1,
-            json: None,
-            form_data: None,
-            headers: None,
-            cookies: None,
             assert_options: Some(assert_vec.clone()),
+            ..Default::default()
         });
         //
         endpoints.push(ApiEndpoint{
@@ -693,11 +1562,7 @@ mod tests {
             method: "GET".to_string(),
             timeout_secs: 10,
             weight: 3,
-            json: None,
-            form_data: None,
-            headers: None,
-            cookies: None,
-            assert_options: None,
+            ..Default::default()
         });
 
         // endpoints.push(ApiEndpoint{
@@ -713,7 +1578,7 @@ mod tests {
         //     assert_options: None,
         // });
 
-        match batch(20, 100, true, true, endpoints, Option::from(StepOption { increase_step: 5, increase_interval: 2 })).await {
+        match batch(20, 100, true, true, endpoints, Option::from(StepOption { increase_step: 5, increase_interval: 2 }), None).await {
             Ok(r) => {
                 println!("{:#?}", r)
             }
@@ -722,4 +1587,220 @@ mod tests {
             }
         };
     }
+
+    #[tokio::test]
+    async fn test_batch_mix_mode() {
+        let (addr, _rx) = crate::core::test_server::spawn_http_server(200, "ok").await;
+        let endpoint = |name: &str, weight: u32| ApiEndpoint {
+            name: name.to_string(),
+            url: format!("http://{}/{}", addr, name),
+            weight,
+            ..Default::default()
+        };
+        let batch_option = BatchOption { mix_mode: MixMode::WeightedRoundRobin, ..Default::default() };
+        let result = batch(1, 3, false, false, vec![endpoint("a", 1), endpoint("b", 9)], None, Some(batch_option)).await.unwrap();
+        let a = result.api_results[0].total_requests as f64;
+        let b = result.api_results[1].total_requests as f64;
+        // 按比例轮询，只有正在进行中的请求会带来误差
+        assert!(a > 0.0 && (b / (a + b) - 0.9).abs() < 0.02, "{} {}", a, b);
+
+        // 所有接口的预算用完后提前结束
+        let budget = |name: &str, max_requests: u64| ApiEndpoint { max_requests: Some(max_requests), ..endpoint(name, 1) };
+        let batch_option = BatchOption { mix_mode: MixMode::WeightedRandom, ..Default::default() };
+        let result = batch(5, 3, false, false, vec![budget("a", 5), budget("b", 20)], None, Some(batch_option)).await.unwrap();
+        assert_eq!(result.end_reason, EndReason::EndpointBudget);
+        assert_eq!(result.api_results[0].total_requests, 5);
+        assert_eq!(result.api_results[1].total_requests, 20);
+        assert!(result.total_duration < 5.0);
+    }
+
+    #[tokio::test]
+    async fn test_batch_sequential_scenario() {
+        let (addr, mut rx) = crate::core::test_server::spawn_http_server(200, "ok").await;
+        let step = |path: &str, think_time_ms: u64| ScenarioStep {
+            endpoint: ApiEndpoint { name: path.to_string(), url: format!("http://{}{}", addr, path), ..Default::default() },
+            think_time_ms,
+        };
+        let scenario = Scenario { steps: vec![step("/login", 200), step("/list", 0), step("/logout", 0)], warnings: Vec::new() };
+        // 每个并发按场景的顺序请求，步骤之间保留等待时间，走完一遍场景算一次迭代，固定节奏按整个场景计算
+        let batch_option = BatchOption { mix_mode: MixMode::Sequential, iterations_per_vu: Some(2), pacing_ms: Some(500), ..Default::default() };
+        let result = batch(5, 1, false, false, scenario.endpoints(), None, Some(batch_option)).await.unwrap();
+        assert_eq!(result.total_requests, 6);
+        assert_eq!(result.end_reason, EndReason::Iterations);
+        let mut paths = Vec::new();
+        while let Ok(head) = rx.try_recv() {
+            paths.push(head.split(' ').nth(1).unwrap_or_default().to_string());
+        }
+        assert_eq!(paths, vec!["/login", "/list", "/logout", "/login", "/list", "/logout"]);
+        assert!((0.9..2.0).contains(&result.total_duration), "{}", result.total_duration);
+    }
+
+    // 记录每个周期快照是否处于预热期间
+    struct WarmUpSink(parking_lot::Mutex<Vec<bool>>);
+
+    impl ResultSink for WarmUpSink {
+        fn on_interval<'a>(&'a self, result: &'a BatchResult) -> BoxFuture<'a, anyhow::Result<()>> {
+            self.0.lock().push(result.warm_up);
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_warm_up() {
+        let (addr, _rx) = crate::core::test_server::spawn_http_server(200, "ok").await;
+        let endpoint = ApiEndpoint {
+            name: "warm_up".to_string(),
+            url: format!("http://{}/", addr),
+            ..Default::default()
+        };
+        let sink = Arc::new(WarmUpSink(parking_lot::Mutex::new(Vec::new())));
+        // 预热1秒后再压测2秒，每100ms一次请求，预热的请求不计入结果
+        let batch_option = BatchOption {
+            warm_up_secs: Some(1),
+            warm_up_ramp: true,
+            pacing_ms: Some(100),
+            result_sinks: vec![sink.clone()],
+            ..Default::default()
+        };
+        let result = batch(2, 2, false, false, vec![endpoint], None, Some(batch_option)).await.unwrap();
+        assert!((36..=42).contains(&result.total_requests), "{}", result.total_requests);
+        assert!((result.total_duration - 2.0).abs() < 0.5);
+        assert!(!result.warm_up);
+        let snapshots = sink.0.lock().clone();
+        assert_eq!(snapshots.first(), Some(&true));
+        assert_eq!(snapshots.last(), Some(&false));
+    }
+
+    #[tokio::test]
+    async fn test_batch_stop_flag_on_error() {
+        use crate::core::status_share::{now_millis, RESULTS_STOPPED_AT};
+        // 校验失败时没有周期结果，也要记录压测已经结束
+        let before = now_millis();
+        assert!(batch(0, 1, false, false, Vec::new(), None, None).await.is_err());
+        assert!(RESULTS_STOPPED_AT.load(Ordering::Relaxed) >= before);
+    }
+
+    #[tokio::test]
+    async fn test_batch_request_log_errors_only() {
+        let (addr, _rx) = crate::core::test_server::spawn_http_server(200, r#"{"code": 0}"#).await;
+        let dir = std::env::temp_dir().join(format!("atomic-bomb-errors-only-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("requests.jsonl");
+        let endpoint = ApiEndpoint { name: "ok".to_string(), url: format!("http://{}/", addr), ..Default::default() };
+        let failing = ApiEndpoint {
+            name: "bad".to_string(),
+            method: "POST".to_string(),
+            json: Some(serde_json::json!({"user": "a"})),
+            assert_options: Some(vec![AssertOption { jsonpath: "$.code".to_string(), reference_object: Value::from(1) }]),
+            ..endpoint.clone()
+        };
+        // 成功的请求不创建记录，失败的请求结束后再构建请求详情
+        let batch_option = BatchOption {
+            iterations_per_vu: Some(3),
+            request_log: Some(crate::models::request_log_option::RequestLogOption {
+                path: path.to_string_lossy().to_string(),
+                sample_rate: 1.0,
+                errors_only: true,
+                har_path: Some(dir.join("requests.har").to_string_lossy().to_string()),
+            }),
+            ..Default::default()
+        };
+        let result = batch(5, 1, false, false, vec![endpoint, failing], None, Some(batch_option)).await.unwrap();
+        let log = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let records: Vec<RequestRecord> = log.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records.len() as i32, result.err_count);
+        assert!(!records.is_empty());
+        for record in &records {
+            assert_eq!(record.name, "bad");
+            assert_eq!(record.error_kind, Some(ErrorKind::Assertion));
+            assert_eq!(record.status, Some(200));
+            let detail = record.detail.as_ref().unwrap();
+            assert_eq!(detail.request_body.as_deref(), Some(r#"{"user":"a"}"#));
+            assert!(!detail.response_headers.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_pick_shared_slot() {
+        // 每个共享客户端最多2个并发，结束的并发空出的位置优先分配
+        assert_eq!(pick_shared_slot(&[], Some(2), 1), 0);
+        assert_eq!(pick_shared_slot(&[2, 1], Some(2), 1), 1);
+        assert_eq!(pick_shared_slot(&[2, 2], Some(2), 1), 2);
+        assert_eq!(pick_shared_slot(&[1, 2], Some(2), 1), 0);
+        // 不限制时每个本地地址一个共享客户端，选择并发数最少的
+        assert_eq!(pick_shared_slot(&[3], None, 2), 1);
+        assert_eq!(pick_shared_slot(&[3, 1], None, 2), 1);
+        assert_eq!(pick_shared_slot(&[0, 1], None, 2), 0);
+        assert_eq!(pick_shared_slot(&[5], None, 1), 0);
+    }
+
+    #[tokio::test]
+    async fn test_batch_graceful_stop() {
+        let addr = crate::core::test_server::spawn_silent_server().await;
+        let endpoint = ApiEndpoint {
+            name: "silent".to_string(),
+            url: format!("http://{}/", addr),
+            ..Default::default()
+        };
+        // 服务一直不响应，到达截止时间后再等1秒，进行中的请求全部记为中断
+        let batch_option = BatchOption { graceful_stop_secs: Some(1), ..Default::default() };
+        let result = batch(1, 3, false, false, vec![endpoint], None, Some(batch_option)).await.unwrap();
+        assert_eq!(result.total_requests, 0);
+        assert_eq!(result.interrupted_requests, 3);
+        assert_eq!(result.api_results[0].interrupted_requests, 3);
+        assert!((result.total_duration - 2.0).abs() < 0.5, "{}", result.total_duration);
+    }
+
+    #[tokio::test]
+    async fn test_batch_graceful_stop_mid_body() {
+        let addr = crate::core::test_server::spawn_stalled_body_server().await;
+        let endpoint = ApiEndpoint {
+            name: "stalled".to_string(),
+            url: format!("http://{}/", addr),
+            ..Default::default()
+        };
+        // 响应头已经返回，响应体读到一半被中断，不能留下响应时间、流量和连接的统计
+        let batch_option = BatchOption { graceful_stop_secs: Some(1), ..Default::default() };
+        let result = batch(1, 2, false, false, vec![endpoint], None, Some(batch_option)).await.unwrap();
+        assert_eq!(result.total_requests, 0);
+        assert_eq!(result.interrupted_requests, 2);
+        assert_eq!(result.max_response_time, 0);
+        assert_eq!(result.median_response_time, 0);
+        assert_eq!(result.total_data_kb, 0.0);
+        let api_result = &result.api_results[0];
+        assert_eq!(api_result.interrupted_requests, 2);
+        assert_eq!(api_result.max_response_time, 0);
+        assert_eq!(api_result.connections_opened, 0);
+        assert!(api_result.http_versions.is_empty());
+    }
+
+    // 每次推送都很慢的推送目标
+    struct SlowSink;
+
+    impl ResultSink for SlowSink {
+        fn on_interval<'a>(&'a self, _result: &'a BatchResult) -> BoxFuture<'a, anyhow::Result<()>> {
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(())
+            })
+        }
+
+        fn on_finish<'a>(&'a self, _result: &'a BatchResult) -> BoxFuture<'a, anyhow::Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_slow_sink() {
+        let (addr, _rx) = crate::core::test_server::spawn_http_server(200, "ok").await;
+        let endpoint = ApiEndpoint { name: "slow_sink".to_string(), url: format!("http://{}/", addr), ..Default::default() };
+        // 慢的推送目标不影响其他推送目标的周期快照
+        let sink = Arc::new(WarmUpSink(parking_lot::Mutex::new(Vec::new())));
+        let batch_option = BatchOption { pacing_ms: Some(100), result_sinks: vec![Arc::new(SlowSink), sink.clone()], ..Default::default() };
+        let start = Instant::now();
+        batch(3, 1, false, false, vec![endpoint], None, Some(batch_option)).await.unwrap();
+        assert!(sink.0.lock().len() >= 2, "{:?}", sink.0.lock());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
 }
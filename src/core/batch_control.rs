@@ -0,0 +1,90 @@
+use tokio::sync::watch;
+
+// 压测运行中的控制状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ControlState {
+    pub paused: bool,
+    pub stopped: bool,
+    // 运行中调整后的总并发数，为空时使用启动时的并发数
+    pub concurrency: Option<usize>,
+}
+
+// 压测控制器，通过BatchOption传给batch后，可以在压测过程中暂停、停止或者调整并发数
+pub struct BatchControl {
+    state: watch::Sender<ControlState>,
+}
+
+impl BatchControl {
+    pub fn new() -> Self {
+        let (state, _) = watch::channel(ControlState::default());
+        BatchControl { state }
+    }
+
+    pub fn state(&self) -> ControlState {
+        *self.state.borrow()
+    }
+
+    // 暂停后不再发起新请求，已发出的请求会正常完成
+    pub fn pause(&self) {
+        self.state.send_modify(|s| s.paused = true);
+    }
+
+    pub fn resume(&self) {
+        self.state.send_modify(|s| s.paused = false);
+    }
+
+    // 提前结束压测，batch会正常返回已统计的结果
+    pub fn stop(&self) {
+        self.state.send_modify(|s| s.stopped = true);
+    }
+
+    // 调整总并发数，按权重分配到每个接口，每个接口至少保留1个并发
+    pub fn set_concurrency(&self, concurrency: usize) {
+        self.state.send_modify(|s| s.concurrency = Some(concurrency.max(1)));
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<ControlState> {
+        self.state.subscribe()
+    }
+}
+
+impl Default for BatchControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use crate::core::batch::batch;
+    use crate::core::test_server::spawn_http_server;
+    use crate::models::api_endpoint::ApiEndpoint;
+    use crate::models::batch_option::BatchOption;
+
+    #[tokio::test]
+    async fn test_batch_control() {
+        let (addr, _rx) = spawn_http_server(200, "{\"code\":0}").await;
+        let endpoints = vec![ApiEndpoint {
+            name: "control".to_string(),
+            url: format!("http://{}/", addr),
+            timeout_secs: 5,
+            ..Default::default()
+        }];
+        let control = Arc::new(BatchControl::new());
+        let batch_option = BatchOption { control: Some(control.clone()), ..Default::default() };
+        let start = Instant::now();
+        let handle = tokio::spawn(batch(60, 1, false, false, endpoints, None, Some(batch_option)));
+        // 调高并发数后停止
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        control.set_concurrency(3);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        control.stop();
+        let result = handle.await.unwrap().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(result.total_requests > 0);
+        assert_eq!(result.api_results[0].concurrent_number, 3);
+    }
+}
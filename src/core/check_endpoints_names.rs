@@ -1,16 +1,34 @@
-use std::collections::HashSet;
-use anyhow::{anyhow};
+use anyhow::anyhow;
+use crate::core::validate::validate_endpoints;
 use crate::models::api_endpoint::ApiEndpoint;
+use crate::models::engine_error::EngineError;
 
-pub(crate) fn check_endpoints_names(endpoints: Vec<ApiEndpoint>) -> anyhow::Result<()> {
-    let mut names_set = HashSet::new();
-    for endpoint in endpoints {
-        if endpoint.name.clone().is_empty(){
-            return Err(anyhow!("api名称不能为空"));
-        }
-        if !names_set.insert(endpoint.name.clone()) {
-            return Err(anyhow!("重复的name: {}", endpoint.name));
-        }
+// 只检查接口名称，保留给旧的调用方，和以前一样返回第一个问题
+#[deprecated(note = "use validate::dry_run or validate::validate_batch")]
+pub fn check_endpoints_names(endpoints: Vec<ApiEndpoint>) -> anyhow::Result<()> {
+    let problem = validate_endpoints(&endpoints)
+        .into_iter()
+        .map(|(_, e)| e)
+        .find(|e| matches!(e, EngineError::EmptyEndpointName | EngineError::DuplicateEndpointName(_)));
+    match problem {
+        Some(e) => Err(anyhow!(e)),
+        None => Ok(()),
     }
-    Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_check_endpoints_names() {
+        let endpoint = ApiEndpoint { name: "login".to_string(), url: "not a url".to_string(), ..Default::default() };
+        // 只检查名称，其他配置问题不影响
+        assert!(check_endpoints_names(vec![endpoint.clone()]).is_ok());
+        let err = check_endpoints_names(vec![endpoint.clone(), endpoint.clone()]).unwrap_err();
+        assert!(matches!(err.downcast_ref::<EngineError>(), Some(EngineError::DuplicateEndpointName(name)) if name == "login"));
+        let unnamed = ApiEndpoint { name: String::new(), ..endpoint };
+        assert!(matches!(check_endpoints_names(vec![unnamed]).unwrap_err().downcast_ref::<EngineError>(), Some(EngineError::EmptyEndpointName)));
+    }
+}
@@ -0,0 +1,172 @@
+use std::path::Path;
+use crate::core::report::load_json_report;
+use crate::models::compare_result::{CompareOption, CompareResult, EndpointDiff, MetricDiff};
+use crate::models::engine_error::EngineError;
+use crate::models::result::{ApiResult, BatchResult};
+
+// 参与对比的指标
+struct Snapshot {
+    median_response_time: u64,
+    response_time_95: u64,
+    response_time_99: u64,
+    rps: f64,
+    error_rate: f64,
+}
+
+impl From<&BatchResult> for Snapshot {
+    fn from(result: &BatchResult) -> Self {
+        Snapshot {
+            median_response_time: result.median_response_time,
+            response_time_95: result.response_time_95,
+            response_time_99: result.response_time_99,
+            rps: result.rps,
+            error_rate: result.error_rate,
+        }
+    }
+}
+
+impl From<&ApiResult> for Snapshot {
+    fn from(result: &ApiResult) -> Self {
+        Snapshot {
+            median_response_time: result.median_response_time,
+            response_time_95: result.response_time_95,
+            response_time_99: result.response_time_99,
+            rps: result.rps,
+            error_rate: result.error_rate,
+        }
+    }
+}
+
+// 对比两次测试结果，按接口名称匹配
+pub fn compare_results(baseline: &BatchResult, current: &BatchResult, option: &CompareOption) -> CompareResult {
+    let total = diff_snapshot("total", &Snapshot::from(baseline), &Snapshot::from(current), option);
+    let mut endpoints = Vec::new();
+    let mut only_in_baseline = Vec::new();
+    for baseline_api in &baseline.api_results {
+        match current.api_results.iter().find(|api| api.name == baseline_api.name) {
+            Some(current_api) => endpoints.push(diff_snapshot(
+                &baseline_api.name,
+                &Snapshot::from(baseline_api),
+                &Snapshot::from(current_api),
+                option,
+            )),
+            None => only_in_baseline.push(baseline_api.name.clone()),
+        }
+    }
+    let only_in_current = current.api_results
+        .iter()
+        .filter(|api| !baseline.api_results.iter().any(|b| b.name == api.name))
+        .map(|api| api.name.clone())
+        .collect();
+    CompareResult {
+        total,
+        endpoints,
+        only_in_baseline,
+        only_in_current,
+    }
+}
+
+// 对比两份保存的json报告
+pub fn compare_reports(baseline_path: impl AsRef<Path>, current_path: impl AsRef<Path>, option: &CompareOption) -> Result<CompareResult, EngineError> {
+    let baseline = load_json_report(baseline_path)?;
+    let current = load_json_report(current_path)?;
+    Ok(compare_results(&baseline, &current, option))
+}
+
+fn diff_snapshot(name: &str, baseline: &Snapshot, current: &Snapshot, option: &CompareOption) -> EndpointDiff {
+    let metrics = vec![
+        latency_diff("median_response_time", baseline.median_response_time, current.median_response_time, option),
+        latency_diff("response_time_95", baseline.response_time_95, current.response_time_95, option),
+        latency_diff("response_time_99", baseline.response_time_99, current.response_time_99, option),
+        {
+            // rps下降超过容忍度算回退
+            let change_percent = change_percent(baseline.rps, current.rps);
+            MetricDiff {
+                metric: "rps".to_string(),
+                baseline: baseline.rps,
+                current: current.rps,
+                change_percent,
+                regression: change_percent.map(|c| -c > option.rps_tolerance_percent).unwrap_or(false),
+            }
+        },
+        {
+            // 错误率按百分点比较，基线为0时也能判断
+            let baseline_rate = finite_or_zero(baseline.error_rate);
+            let current_rate = finite_or_zero(current.error_rate);
+            MetricDiff {
+                metric: "error_rate".to_string(),
+                baseline: baseline_rate,
+                current: current_rate,
+                change_percent: change_percent(baseline_rate, current_rate),
+                regression: current_rate - baseline_rate > option.error_rate_tolerance,
+            }
+        },
+    ];
+    EndpointDiff {
+        name: name.to_string(),
+        regression: metrics.iter().any(|m| m.regression),
+        metrics,
+    }
+}
+
+// 响应时间变慢超过容忍度算回退
+fn latency_diff(metric: &str, baseline: u64, current: u64, option: &CompareOption) -> MetricDiff {
+    let change_percent = change_percent(baseline as f64, current as f64);
+    MetricDiff {
+        metric: metric.to_string(),
+        baseline: baseline as f64,
+        current: current as f64,
+        change_percent,
+        regression: change_percent.map(|c| c > option.latency_tolerance_percent).unwrap_or(false),
+    }
+}
+
+fn change_percent(baseline: f64, current: f64) -> Option<f64> {
+    if baseline == 0.0 || !baseline.is_finite() || !current.is_finite() {
+        return None;
+    }
+    Some((current - baseline) / baseline * 100.0)
+}
+
+fn finite_or_zero(value: f64) -> f64 {
+    if value.is_finite() { value } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::report::save_json_report;
+    use crate::sinks::result_sink::sample_batch_result;
+
+    #[test]
+    fn test_compare_reports() {
+        let mut baseline = sample_batch_result();
+        baseline.http_errors.insert((500, "HTTP 错误: 状态码 500".to_string(), "http://127.0.0.1/api".to_string()), 2);
+        baseline.api_results[0].name = "checkout".to_string();
+        baseline.api_results[0].response_time_95 = 100;
+        baseline.api_results[0].rps = 50.0;
+        let mut current = baseline.clone();
+        current.api_results[0].response_time_95 = 118;
+        current.api_results[0].rps = 48.0;
+        current.error_rate = 5.0;
+
+        // 通过保存的报告对比
+        let dir = std::env::temp_dir().join(format!("atomic-bomb-compare-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        save_json_report(&baseline, dir.join("baseline.json")).unwrap();
+        save_json_report(&current, dir.join("current.json")).unwrap();
+        let result = compare_reports(dir.join("baseline.json"), dir.join("current.json"), &CompareOption::default()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.has_regression());
+        let checkout = &result.endpoints[0];
+        let p95 = checkout.metrics.iter().find(|m| m.metric == "response_time_95").unwrap();
+        assert!(p95.regression);
+        assert_eq!(p95.change_percent.map(|c| c.round()), Some(18.0));
+        // rps只下降了4%，在容忍度以内
+        assert!(!checkout.metrics.iter().find(|m| m.metric == "rps").unwrap().regression);
+        let regressions = result.regressions();
+        assert_eq!(regressions.len(), 2);
+        assert!(regressions.contains(&"checkout response_time_95 变差了 18.0% (100 -> 118)".to_string()));
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use base64::Engine;
+use serde_json::Value;
+use url::Url;
+
+use crate::models::api_endpoint::ApiEndpoint;
+use crate::models::engine_error::{localized, EngineError};
+use crate::models::http_version::HttpVersion;
+use crate::models::import_result::ImportResult;
+use crate::models::tls_option::TlsOption;
+
+// 没有参数、导入时可以忽略的选项
+const IGNORED_FLAGS: [&str; 15] = [
+    "-s", "--silent", "-S", "--show-error", "-L", "--location", "-v", "--verbose",
+    "-i", "--include", "--compressed", "-N", "--no-buffer", "-f", "--fail",
+];
+// 带参数、导入时可以忽略的选项
+const IGNORED_OPTIONS: [&str; 8] = ["-o", "--output", "--connect-timeout", "--retry", "-w", "--write-out", "-c", "--cookie-jar"];
+
+// 解析curl命令，例如浏览器开发者工具中"复制为cURL"的结果
+// 支持-X、-H、-d、--data-urlencode、--json、-F、-b、-u、-G、-I、-A、-e、-m、--http1.1、--http2、--http2-prior-knowledge、-k、--cacert、--cert、--key
+// 请求体只支持json和表单，multipart表单按普通表单导入并给出警告
+pub fn parse_curl(command: &str) -> Result<ImportResult, EngineError> {
+    let args = split_command(command)?;
+    let mut args = args.into_iter().peekable();
+    match args.next() {
+        Some(first) if first == "curl" => {}
+        _ => return Err(EngineError::NotCurlCommand),
+    }
+    let mut url: Option<String> = None;
+    let mut method: Option<String> = None;
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut data: Vec<String> = Vec::new();
+    let mut json_body = false;
+    let mut form_data: HashMap<String, String> = HashMap::new();
+    let mut multipart = false;
+    let mut cookies: Vec<String> = Vec::new();
+    let mut get = false;
+    let mut timeout_secs = 0;
+    let mut http_version = HttpVersion::Auto;
+    let mut tls = TlsOption::default();
+    while let Some(arg) = args.next() {
+        // -XPOST、--request=POST这类写法拆成选项和参数
+        let (option, inline_value) = split_option(&arg);
+        let mut value = || -> Result<String, EngineError> {
+            match &inline_value {
+                Some(value) => Ok(value.clone()),
+                None => args.next().ok_or_else(|| EngineError::MissingOptionValue(option.clone())),
+            }
+        };
+        match option.as_str() {
+            "-X" | "--request" => method = Some(value()?.to_uppercase()),
+            "-H" | "--header" => {
+                let header = value()?;
+                let (key, val) = header.split_once(':').ok_or_else(|| EngineError::InvalidHeaderName(header.clone()))?;
+                headers.push((key.trim().to_string(), val.trim().to_string()));
+            }
+            "-d" | "--data" | "--data-raw" | "--data-binary" | "--data-ascii" => {
+                let body = value()?;
+                if body.starts_with('@') && option != "--data-raw" {
+                    return Err(EngineError::UnsupportedFileReference(body));
+                }
+                data.push(body);
+            }
+            "--data-urlencode" => data.push(encode_data(&value()?)?),
+            "--json" => {
+                data.push(value()?);
+                json_body = true;
+            }
+            "-F" | "--form" | "--form-string" => {
+                let field = value()?;
+                let (key, val) = field.split_once('=').ok_or_else(|| EngineError::InvalidFormField(field.clone()))?;
+                if option != "--form-string" && (val.starts_with('@') || val.starts_with('<')) {
+                    return Err(EngineError::UnsupportedFileReference(field));
+                }
+                form_data.insert(key.to_string(), val.to_string());
+                multipart = true;
+            }
+            "-b" | "--cookie" => {
+                let cookie = value()?;
+                if !cookie.contains('=') {
+                    return Err(EngineError::UnsupportedFileReference(cookie));
+                }
+                cookies.push(cookie);
+            }
+            "-u" | "--user" => {
+                let user = value()?;
+                let (username, password) = user.split_once(':').unwrap_or((&user, ""));
+                headers.push(("Authorization".to_string(), basic_auth(username, password)));
+            }
+            "-A" | "--user-agent" => headers.push(("User-Agent".to_string(), value()?)),
+            "-e" | "--referer" => headers.push(("Referer".to_string(), value()?)),
+            "-m" | "--max-time" => {
+                let max_time = value()?;
+                let secs: f64 = max_time.parse().map_err(|_| EngineError::InvalidTimeout(max_time.clone()))?;
+                timeout_secs = secs.ceil() as u64;
+            }
+            "-G" | "--get" => get = true,
+            "-I" | "--head" => method = Some("HEAD".to_string()),
+            "--url" => url = Some(value()?),
+            "--http1.1" => http_version = HttpVersion::Http1,
+            "--http2" => http_version = HttpVersion::Http2,
+            "--http2-prior-knowledge" => http_version = HttpVersion::Http2PriorKnowledge,
+            "-k" | "--insecure" => tls.danger_accept_invalid_certs = true,
+            "--cacert" => tls.ca_cert = Some(value()?),
+            // curl的--cert可以是"证书:密码"，这里只支持PEM证书文件
+            "-E" | "--cert" => tls.client_cert = Some(value()?),
+            "--key" => tls.client_key = Some(value()?),
+            _ if IGNORED_FLAGS.contains(&option.as_str()) => {}
+            _ if IGNORED_OPTIONS.contains(&option.as_str()) => {
+                value()?;
+            }
+            _ if is_flag_group(&option) => {
+                if option.contains('k') {
+                    tls.danger_accept_invalid_certs = true;
+                }
+            }
+            _ if option.starts_with('-') => return Err(EngineError::UnsupportedCurlOption(option)),
+            _ => url = Some(arg),
+        }
+    }
+    let url = url.ok_or(EngineError::MissingUrl)?;
+    let mut url = Url::parse(&url).map_err(|_| EngineError::InvalidUrl(url.clone()))?;
+    // 明文http时curl通过Upgrade切换到HTTP/2，这里按自动协商处理
+    if http_version == HttpVersion::Http2 && url.scheme() != "https" {
+        http_version = HttpVersion::Auto;
+    }
+
+    // cookie请求头放到cookies里
+    headers.retain(|(key, val)| {
+        if key.eq_ignore_ascii_case("cookie") {
+            cookies.push(val.clone());
+            return false;
+        }
+        !key.eq_ignore_ascii_case("content-length")
+    });
+    let content_type = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+        .map(|(_, val)| val.to_lowercase())
+        .unwrap_or_default();
+
+    let body = data.join("&");
+    let mut json = None;
+    if get {
+        // -G把数据追加到查询参数
+        if !body.is_empty() {
+            let query = match url.query() {
+                Some(query) if !query.is_empty() => format!("{}&{}", query, body),
+                _ => body.clone(),
+            };
+            url.set_query(Some(&query));
+        }
+    } else if !data.is_empty() {
+        if json_body || content_type.contains("json") {
+            json = Some(serde_json::from_str::<Value>(&body).map_err(|e| EngineError::InvalidJson(e.to_string()))?);
+        } else {
+            // 没有指定json时，对象或数组按json发送，表单格式的数据按表单发送，其他请求体不支持
+            match serde_json::from_str::<Value>(&body) {
+                Ok(value) if value.is_object() || value.is_array() => json = Some(value),
+                _ if is_form_body(&content_type, &body) => {
+                    form_data.extend(url::form_urlencoded::parse(body.as_bytes()).into_owned())
+                }
+                _ => {
+                    let content_type = if content_type.is_empty() { body } else { content_type };
+                    return Err(EngineError::UnsupportedRequestBody(content_type));
+                }
+            }
+        }
+    }
+    if json_body && !headers.iter().any(|(key, _)| key.eq_ignore_ascii_case("accept")) {
+        headers.push(("Accept".to_string(), "application/json".to_string()));
+    }
+    let has_body = json.is_some() || !form_data.is_empty();
+    let method = match method {
+        Some(method) => method,
+        None if get => "GET".to_string(),
+        None if has_body => "POST".to_string(),
+        None => "GET".to_string(),
+    };
+    let name = format!("{} {}", method, url.path());
+    let mut warnings = Vec::new();
+    if multipart {
+        warnings.push(localized(
+            format!("{} 的multipart请求体按普通表单导入", name),
+            format!("{}: multipart body is imported as a plain form", name),
+        ));
+    }
+    let endpoint = ApiEndpoint {
+        name,
+        url: url.to_string(),
+        method,
+        timeout_secs,
+        json,
+        form_data: if form_data.is_empty() { None } else { Some(form_data) },
+        headers: if headers.is_empty() { None } else { Some(headers.into_iter().collect()) },
+        cookies: if cookies.is_empty() { None } else { Some(cookies.join("; ")) },
+        http_version,
+        tls: if tls == TlsOption::default() { None } else { Some(tls) },
+        ..Default::default()
+    };
+    Ok(ImportResult { endpoints: vec![endpoint], warnings })
+}
+
+// 没有指定类型或者指定为表单时，每一段都是"名称=值"的数据才按表单发送
+fn is_form_body(content_type: &str, body: &str) -> bool {
+    (content_type.is_empty() || content_type.starts_with("application/x-www-form-urlencoded"))
+        && body.split('&').all(|pair| pair.contains('='))
+}
+
+// basic认证请求头的值
+pub(crate) fn basic_auth(username: &str, password: &str) -> String {
+    format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password)))
+}
+
+// --data-urlencode的几种写法: content、=content、name=content
+fn encode_data(data: &str) -> Result<String, EngineError> {
+    let encode = |s: &str| url::form_urlencoded::byte_serialize(s.as_bytes()).collect::<String>();
+    if let Some(content) = data.strip_prefix('=') {
+        return Ok(encode(content));
+    }
+    match data.split_once('=') {
+        Some((name, content)) => Ok(format!("{}={}", name, encode(content))),
+        None if data.contains('@') => Err(EngineError::UnsupportedFileReference(data.to_string())),
+        None => Ok(encode(data)),
+    }
+}
+
+fn split_option(arg: &str) -> (String, Option<String>) {
+    if let Some(rest) = arg.strip_prefix("--") {
+        return match rest.split_once('=') {
+            Some((name, value)) => (format!("--{}", name), Some(value.to_string())),
+            None => (arg.to_string(), None),
+        };
+    }
+    // 带参数的短选项可以和参数连写，例如-XPOST
+    // 按字符边界截取，非ascii的参数交给后面报不支持的选项
+    if let (Some(option), Some(value)) = (arg.get(..2), arg.get(2..)) {
+        if !value.is_empty() && ["-X", "-H", "-d", "-F", "-b", "-u", "-A", "-e", "-m"].contains(&option) {
+            return (option.to_string(), Some(value.to_string()));
+        }
+    }
+    (arg.to_string(), None)
+}
+
+// 多个无参数的短选项写在一起，例如-sSL
+fn is_flag_group(option: &str) -> bool {
+    option.len() > 2
+        && option.starts_with('-')
+        && !option.starts_with("--")
+        && option[1..].chars().all(|c| c == 'k' || IGNORED_FLAGS.contains(&format!("-{}", c).as_str()))
+}
+
+// 按shell规则拆分参数，支持单引号、双引号、$'...'和行尾的反斜杠
+fn split_command(command: &str) -> Result<Vec<String>, EngineError> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err(EngineError::UnclosedQuote('\'')),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(EngineError::UnclosedQuote('"')),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(EngineError::UnclosedQuote('"')),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => current.push('\n'),
+                            Some('t') => current.push('\t'),
+                            Some('r') => current.push('\r'),
+                            Some(c) => current.push(c),
+                            None => return Err(EngineError::UnclosedQuote('\'')),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(EngineError::UnclosedQuote('\'')),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // 续行
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(c) => {
+                    in_arg = true;
+                    current.push(c);
+                }
+                None => {}
+            },
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::engine_error::Language;
+
+    #[test]
+    fn test_parse_curl() {
+        let endpoint = parse_curl(
+            r#"curl 'https://api.example.com/login?from=web' \
+  -H 'Content-Type: application/json' \
+  -H "X-Token: a\"b" \
+  -b 'sid=1' -H 'cookie: theme=dark' \
+  --data-raw $'{"user":"test","note":"it\'s"}' \
+  --compressed -sSL"#,
+        ).unwrap().endpoints.remove(0);
+        assert_eq!(endpoint.method, "POST");
+        assert_eq!(endpoint.name, "POST /login");
+        assert_eq!(endpoint.url, "https://api.example.com/login?from=web");
+        assert_eq!(endpoint.json, Some(serde_json::json!({"user": "test", "note": "it's"})));
+        assert_eq!(endpoint.headers.as_ref().unwrap()["X-Token"], "a\"b");
+        assert_eq!(endpoint.cookies.as_deref(), Some("sid=1; theme=dark"));
+
+        let mut result = parse_curl("curl -XPUT -u admin:secret --data-urlencode 'q=a b&c' -d x=1 -F name=tom http://127.0.0.1/items").unwrap();
+        assert_eq!(result.warnings.len(), 1);
+        let endpoint = result.endpoints.remove(0);
+        assert_eq!(endpoint.method, "PUT");
+        let form_data = endpoint.form_data.unwrap();
+        assert_eq!(form_data["q"], "a b&c");
+        assert_eq!(form_data["x"], "1");
+        assert_eq!(form_data["name"], "tom");
+        assert_eq!(endpoint.headers.unwrap()["Authorization"], "Basic YWRtaW46c2VjcmV0");
+
+        let endpoint = parse_curl("curl -G -d page=2 --max-time 1.5 http://127.0.0.1/items?size=10").unwrap().endpoints.remove(0);
+        assert_eq!(endpoint.method, "GET");
+        assert_eq!(endpoint.url, "http://127.0.0.1/items?size=10&page=2");
+        assert_eq!(endpoint.timeout_secs, 2);
+
+        assert!(matches!(parse_curl("curl -F file=@a.png http://127.0.0.1/upload"), Err(EngineError::UnsupportedFileReference(_))));
+        let err = parse_curl("curl --unknown http://127.0.0.1/").err().unwrap();
+        assert_eq!(err.message(Language::English), "unsupported curl option: --unknown");
+        assert_eq!(err.message(Language::Chinese), "不支持的curl选项: --unknown");
+        // 非ascii的短选项不能在字符中间截断
+        assert!(matches!(parse_curl("curl -中 http://127.0.0.1/"), Err(EngineError::UnsupportedCurlOption(option)) if option == "-中"));
+        assert!(matches!(parse_curl("curl -é http://127.0.0.1/"), Err(EngineError::UnsupportedCurlOption(option)) if option == "-é"));
+        // json和表单以外的请求体不能改写成表单
+        let err = parse_curl("curl -H 'Content-Type: application/xml' -d '<a/>' http://127.0.0.1/").err().unwrap();
+        assert!(matches!(err, EngineError::UnsupportedRequestBody(ref content_type) if content_type == "application/xml"));
+        assert!(matches!(parse_curl("curl -d '<a/>' http://127.0.0.1/"), Err(EngineError::UnsupportedRequestBody(_))));
+        let endpoint = |command: &str| parse_curl(command).unwrap().endpoints.remove(0);
+        assert_eq!(endpoint("curl --http2-prior-knowledge http://127.0.0.1/").http_version, HttpVersion::Http2PriorKnowledge);
+        assert_eq!(endpoint("curl --http2 http://127.0.0.1/").http_version, HttpVersion::Auto);
+        let tls = endpoint("curl -sk --cacert ca.pem https://127.0.0.1/").tls.unwrap();
+        assert!(tls.danger_accept_invalid_certs);
+        assert_eq!(tls.ca_cert.as_deref(), Some("ca.pem"));
+    }
+}
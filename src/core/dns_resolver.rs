@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+use crate::models::dns_option::DnsOption;
+
+// 按配置解析主机名，所有客户端共用一个，轮询的位置在并发之间共享
+#[derive(Clone)]
+pub(crate) struct DnsResolver {
+    // 固定解析的主机
+    overrides: Arc<HashMap<String, Arc<HostAddrs>>>,
+    // 设置刷新间隔时缓存系统DNS的解析结果(解析时间, 结果)
+    cache: Option<Arc<parking_lot::Mutex<DnsCache>>>,
+    refresh: Option<Duration>,
+}
+
+type DnsCache = HashMap<String, (Instant, Arc<HostAddrs>)>;
+
+// 一个主机的地址列表，新建连接时轮流从不同的地址开始
+struct HostAddrs {
+    ips: Vec<IpAddr>,
+    next: AtomicUsize,
+}
+
+impl HostAddrs {
+    fn new(ips: Vec<IpAddr>) -> Self {
+        HostAddrs { ips, next: AtomicUsize::new(0) }
+    }
+
+    // 按轮询位置旋转地址列表，第一个地址连接失败时还可以尝试后面的地址
+    fn rotated(&self) -> Vec<SocketAddr> {
+        if self.ips.is_empty() {
+            return Vec::new();
+        }
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % self.ips.len();
+        // 端口由连接器按url设置
+        self.ips[start..].iter().chain(&self.ips[..start]).map(|ip| SocketAddr::new(*ip, 0)).collect()
+    }
+}
+
+impl DnsResolver {
+    pub(crate) fn new(option: &DnsOption) -> Self {
+        // 键已经校验过，主机名不区分大小写
+        let overrides = option
+            .resolve
+            .iter()
+            .filter_map(|(key, ips)| DnsOption::split_key(key).map(|(host, _)| (host.to_lowercase(), Arc::new(HostAddrs::new(ips.clone())))))
+            .collect();
+        DnsResolver {
+            overrides: Arc::new(overrides),
+            cache: option.refresh_secs.map(|_| Arc::new(parking_lot::Mutex::new(HashMap::new()))),
+            refresh: option.refresh_secs.map(Duration::from_secs),
+        }
+    }
+
+    // 解析主机名，返回的地址端口为0
+    pub(crate) async fn lookup(&self, host: &str) -> std::io::Result<Vec<SocketAddr>> {
+        let host = host.to_lowercase();
+        if let Some(addrs) = self.overrides.get(&host) {
+            return Ok(addrs.rotated());
+        }
+        let (Some(cache), Some(refresh)) = (&self.cache, self.refresh) else {
+            return Ok(tokio::net::lookup_host((host.as_str(), 0)).await?.collect());
+        };
+        let cached = cache.lock().get(&host).filter(|(resolved_at, _)| resolved_at.elapsed() < refresh).map(|(_, addrs)| addrs.clone());
+        if let Some(addrs) = cached {
+            return Ok(addrs.rotated());
+        }
+        let mut ips: Vec<IpAddr> = Vec::new();
+        for addr in tokio::net::lookup_host((host.as_str(), 0)).await? {
+            if !ips.contains(&addr.ip()) {
+                ips.push(addr.ip());
+            }
+        }
+        let addrs = Arc::new(HostAddrs::new(ips));
+        cache.lock().insert(host, (Instant::now(), addrs.clone()));
+        Ok(addrs.rotated())
+    }
+}
+
+impl Resolve for DnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addrs = resolver.lookup(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::batch::batch;
+    use crate::core::test_server::spawn_http_server;
+    use crate::models::api_endpoint::ApiEndpoint;
+    use crate::models::batch_option::BatchOption;
+
+    #[tokio::test]
+    async fn test_resolve_round_robin() {
+        let option = DnsOption {
+            resolve: [("API.example.com:443".to_string(), vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()])].into_iter().collect(),
+            refresh_secs: Some(60),
+        };
+        let resolver = DnsResolver::new(&option);
+        let first: Vec<IpAddr> = resolver.lookup("api.example.com").await.unwrap().iter().map(|a| a.ip()).collect();
+        let second: Vec<IpAddr> = resolver.lookup("api.example.com").await.unwrap().iter().map(|a| a.ip()).collect();
+        assert_eq!(first, vec!["10.0.0.1".parse::<IpAddr>().unwrap(), "10.0.0.2".parse().unwrap()]);
+        assert_eq!(second, vec!["10.0.0.2".parse::<IpAddr>().unwrap(), "10.0.0.1".parse().unwrap()]);
+        // 系统DNS的解析结果被缓存
+        assert!(!resolver.lookup("localhost").await.unwrap().is_empty());
+        assert!(resolver.cache.as_ref().unwrap().lock().contains_key("localhost"));
+        assert_eq!(DnsOption::split_key("example.com"), Some(("example.com", None)));
+        assert_eq!(DnsOption::split_key("[::1]:8080"), Some(("::1", Some(8080))));
+        assert_eq!(DnsOption::split_key("example.com:http"), None);
+    }
+
+    #[tokio::test]
+    async fn test_batch_resolve() {
+        let (addr, mut rx) = spawn_http_server(200, "ok").await;
+        let endpoint = ApiEndpoint {
+            name: "resolve".to_string(),
+            url: format!("http://node.atomic-bomb.test:{}/", addr.port()),
+            timeout_secs: 5,
+            ..Default::default()
+        };
+        let dns = DnsOption {
+            resolve: [(format!("node.atomic-bomb.test:{}", addr.port()), vec![addr.ip()])].into_iter().collect(),
+            refresh_secs: Some(1),
+        };
+        let batch_option = BatchOption { iterations_per_vu: Some(3), dns, ..Default::default() };
+        let result = batch(5, 2, false, false, vec![endpoint], None, Some(batch_option)).await.unwrap();
+        assert_eq!(result.err_count, 0);
+        assert_eq!(result.total_requests, 6);
+        assert!(rx.recv().await.unwrap().contains("node.atomic-bomb.test"));
+    }
+}
@@ -0,0 +1,98 @@
+use parking_lot::Mutex;
+
+use crate::core::fast_rng::FastRng;
+use crate::models::mix_mode::MixMode;
+
+// 混合模式下每次迭代按权重选择接口
+pub(crate) struct EndpointMixer {
+    mode: MixMode,
+    weights: Vec<i64>,
+    total_weight: i64,
+    // 平滑加权轮询的当前权重，所有并发共用
+    current_weights: Mutex<Vec<i64>>,
+}
+
+impl EndpointMixer {
+    pub(crate) fn new(mode: MixMode, weights: &[u32]) -> Self {
+        let weights: Vec<i64> = weights.iter().map(|w| *w as i64).collect();
+        EndpointMixer {
+            mode,
+            total_weight: weights.iter().sum(),
+            current_weights: Mutex::new(vec![0; weights.len()]),
+            weights,
+        }
+    }
+
+    // 返回下一次要请求的接口下标，step是当前并发已经选择的次数
+    pub(crate) fn pick(&self, rng: &mut FastRng, step: &mut usize) -> usize {
+        match self.mode {
+            MixMode::Sequential => {
+                let index = *step % self.weights.len();
+                *step += 1;
+                index
+            }
+            MixMode::WeightedRandom => {
+                let mut target = (rng.next_u64() % self.total_weight as u64) as i64;
+                for (index, weight) in self.weights.iter().enumerate() {
+                    if target < *weight {
+                        return index;
+                    }
+                    target -= weight;
+                }
+                self.weights.len() - 1
+            }
+            // 平滑加权轮询，每total_weight次选择中每个接口正好出现weight次
+            _ => {
+                let mut current_weights = self.current_weights.lock();
+                let mut selected = 0;
+                for index in 0..self.weights.len() {
+                    current_weights[index] += self.weights[index];
+                    if current_weights[index] > current_weights[selected] {
+                        selected = index;
+                    }
+                }
+                current_weights[selected] -= self.total_weight;
+                selected
+            }
+        }
+    }
+
+    // 选择step次之后是否完成了一次迭代，按顺序选择时走完全部接口才算一次迭代
+    pub(crate) fn completes_iteration(&self, step: usize) -> bool {
+        match self.mode {
+            MixMode::Sequential => step.is_multiple_of(self.weights.len()),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_mixer() {
+        let mut rng = FastRng::new();
+        let mixer = EndpointMixer::new(MixMode::WeightedRoundRobin, &[1, 3, 6]);
+        let mut counts = [0; 3];
+        for _ in 0..100 {
+            counts[mixer.pick(&mut rng, &mut 0)] += 1;
+        }
+        assert_eq!(counts, [10, 30, 60]);
+
+        let mixer = EndpointMixer::new(MixMode::WeightedRandom, &[1, 3, 6]);
+        let mut counts = [0; 3];
+        for _ in 0..10000 {
+            counts[mixer.pick(&mut rng, &mut 0)] += 1;
+        }
+        assert!((800..1200).contains(&counts[0]), "{:?}", counts);
+        assert!((5600..6400).contains(&counts[2]), "{:?}", counts);
+
+        // 按顺序选择时每个并发有自己的位置
+        let mixer = EndpointMixer::new(MixMode::Sequential, &[1, 3, 6]);
+        let (mut first, mut second) = (0, 0);
+        let picks: Vec<usize> = (0..4).map(|_| mixer.pick(&mut rng, &mut first)).collect();
+        assert_eq!(picks, vec![0, 1, 2, 0]);
+        assert_eq!(mixer.pick(&mut rng, &mut second), 0);
+    }
+}
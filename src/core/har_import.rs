@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+use std::path::Path;
+use url::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::core::import_names::unique_name;
+use crate::models::api_endpoint::ApiEndpoint;
+use crate::models::engine_error::{localized, EngineError};
+use crate::models::har_import_option::HarImportOption;
+use crate::models::import_result::ImportResult;
+use crate::models::scenario::{Scenario, ScenarioStep};
+
+// 浏览器自动生成、每次请求都会变化或者由http客户端负责的请求头
+const VOLATILE_HEADERS: [&str; 17] = [
+    "host",
+    "content-length",
+    "connection",
+    "keep-alive",
+    "accept-encoding",
+    "cookie",
+    "if-none-match",
+    "if-modified-since",
+    "upgrade-insecure-requests",
+    "priority",
+    "te",
+    "x-request-id",
+    "x-correlation-id",
+    "traceparent",
+    "tracestate",
+    "sentry-trace",
+    "x-amzn-trace-id",
+];
+// 按前缀去掉的请求头，":"开头的是http2伪头
+const VOLATILE_HEADER_PREFIXES: [&str; 4] = [":", "sec-ch-", "sec-fetch-", "x-b3-"];
+
+#[derive(Deserialize)]
+struct Har {
+    log: HarLog,
+}
+
+#[derive(Deserialize)]
+struct HarLog {
+    #[serde(default)]
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarEntry {
+    started_date_time: String,
+    #[serde(default)]
+    time: f64,
+    request: HarRequest,
+    response: Option<HarResponse>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<HarPair>,
+    #[serde(default)]
+    cookies: Vec<HarPair>,
+    post_data: Option<HarPostData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarPostData {
+    #[serde(default)]
+    mime_type: String,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    params: Vec<HarParam>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarParam {
+    name: String,
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(default)]
+    file_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct HarPair {
+    name: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct HarResponse {
+    content: Option<HarContent>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarContent {
+    #[serde(default)]
+    mime_type: String,
+}
+
+// 从har文件导入按录制顺序执行的场景，保留请求之间的等待时间
+pub fn load_har_scenario(path: impl AsRef<Path>, option: &HarImportOption) -> Result<Scenario, EngineError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|e| EngineError::Io(path.display().to_string(), e))?;
+    parse_har_scenario(&source, option)
+}
+
+// 从har文件导入接口列表，相同的请求合并成一个接口，录制次数作为权重
+pub fn load_har_endpoints(path: impl AsRef<Path>, option: &HarImportOption) -> Result<ImportResult, EngineError> {
+    let scenario = load_har_scenario(path, option)?;
+    Ok(ImportResult { endpoints: merge_endpoints(scenario.steps), warnings: scenario.warnings })
+}
+
+pub fn parse_har_endpoints(source: &str, option: &HarImportOption) -> Result<ImportResult, EngineError> {
+    let scenario = parse_har_scenario(source, option)?;
+    Ok(ImportResult { endpoints: merge_endpoints(scenario.steps), warnings: scenario.warnings })
+}
+
+pub fn parse_har_scenario(source: &str, option: &HarImportOption) -> Result<Scenario, EngineError> {
+    let har: Har = serde_json::from_str(source).map_err(|e| EngineError::InvalidDocument("har".to_string(), e.to_string()))?;
+    let mut entries: Vec<(i128, f64, HarEntry)> = Vec::new();
+    for entry in har.log.entries {
+        let started = OffsetDateTime::parse(&entry.started_date_time, &Rfc3339)
+            .map_err(|e| EngineError::InvalidDocument("har".to_string(), format!("startedDateTime {}: {}", entry.started_date_time, e)))?;
+        entries.push((started.unix_timestamp_nanos() / 1_000_000, entry.time.max(0.0), entry));
+    }
+    // har一般已经按开始时间排序，这里再保证一次
+    entries.sort_by_key(|(started, _, _)| *started);
+
+    let mut steps: Vec<ScenarioStep> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut names: HashMap<String, usize> = HashMap::new();
+    // 上一个导入请求的结束时间
+    let mut last_end: Option<f64> = None;
+    for (started, time, entry) in entries {
+        let url = match Url::parse(&entry.request.url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+            // data:、blob:、ws:等请求不导入
+            _ => continue,
+        };
+        if !matches_filters(&url, &entry, option) {
+            continue;
+        }
+        let started = started as f64;
+        if let (Some(last_end), Some(previous)) = (last_end, steps.last_mut()) {
+            previous.think_time_ms = (started - last_end).max(0.0).round() as u64;
+        }
+        last_end = Some(started + time);
+        let endpoint = convert_entry(&url, entry, option, &mut names, &mut warnings);
+        steps.push(ScenarioStep { endpoint, think_time_ms: 0 });
+    }
+    if steps.is_empty() {
+        return Err(EngineError::NothingToImport("har".to_string()));
+    }
+    Ok(Scenario { steps, warnings })
+}
+
+fn matches_filters(url: &Url, entry: &HarEntry, option: &HarImportOption) -> bool {
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    if !option.domains.is_empty()
+        && !option.domains.iter().any(|domain| {
+            let domain = domain.to_lowercase();
+            host == domain || host.ends_with(&format!(".{}", domain))
+        })
+    {
+        return false;
+    }
+    if !option.methods.is_empty() && !option.methods.iter().any(|m| m.eq_ignore_ascii_case(&entry.request.method)) {
+        return false;
+    }
+    if !option.content_types.is_empty() {
+        let mime_type = entry
+            .response
+            .as_ref()
+            .and_then(|r| r.content.as_ref())
+            .map(|c| c.mime_type.to_lowercase())
+            .unwrap_or_default();
+        if !option.content_types.iter().any(|c| mime_type.starts_with(&c.to_lowercase())) {
+            return false;
+        }
+    }
+    true
+}
+
+fn convert_entry(url: &Url, entry: HarEntry, option: &HarImportOption, names: &mut HashMap<String, usize>, warnings: &mut Vec<String>) -> ApiEndpoint {
+    let request = entry.request;
+    let method = request.method.to_uppercase();
+    // 接口名称用方法和路径，重复时加序号
+    let name = unique_name(names, format!("{} {}", method, url.path()));
+
+    let (json, form_data) = match request.post_data {
+        None => (None, None),
+        Some(post_data) => convert_body(&name, post_data, warnings),
+    };
+    let has_body = json.is_some() || form_data.is_some();
+    let mut headers = HashMap::new();
+    for header in &request.headers {
+        let key = header.name.to_lowercase();
+        if VOLATILE_HEADERS.contains(&key.as_str())
+            || VOLATILE_HEADER_PREFIXES.iter().any(|p| key.starts_with(p))
+            || option.strip_headers.iter().any(|h| h.eq_ignore_ascii_case(&key))
+            // 请求体由http客户端重新编码，content-type也由客户端设置
+            || (has_body && key == "content-type")
+        {
+            continue;
+        }
+        headers.insert(header.name.clone(), header.value.clone());
+    }
+    let cookies = if !option.keep_cookies {
+        None
+    } else if !request.cookies.is_empty() {
+        Some(request.cookies.iter().map(|c| format!("{}={}", c.name, c.value)).collect::<Vec<_>>().join("; "))
+    } else {
+        request.headers.iter().find(|h| h.name.eq_ignore_ascii_case("cookie")).map(|h| h.value.clone())
+    };
+    ApiEndpoint {
+        name,
+        url: url.to_string(),
+        method,
+        json,
+        form_data,
+        headers: if headers.is_empty() { None } else { Some(headers) },
+        cookies,
+        ..Default::default()
+    }
+}
+
+// 转换请求体，只支持json和表单，其他类型记录警告后忽略
+fn convert_body(name: &str, post_data: HarPostData, warnings: &mut Vec<String>) -> (Option<Value>, Option<HashMap<String, String>>) {
+    let mime_type = post_data.mime_type.to_lowercase();
+    let text = post_data.text.unwrap_or_default();
+    if mime_type.contains("json") {
+        return match serde_json::from_str(&text) {
+            Ok(json) => (Some(json), None),
+            Err(e) => {
+                warnings.push(localized(
+                    format!("{} 的json请求体解析失败，已忽略: {}", name, e),
+                    format!("{}: failed to parse json body, ignored: {}", name, e),
+                ));
+                (None, None)
+            }
+        };
+    }
+    if mime_type.starts_with("application/x-www-form-urlencoded") {
+        let form_data: HashMap<String, String> = if post_data.params.is_empty() {
+            url::form_urlencoded::parse(text.as_bytes()).into_owned().collect()
+        } else {
+            post_data.params.into_iter().map(|p| (p.name, p.value.unwrap_or_default())).collect()
+        };
+        return (None, Some(form_data));
+    }
+    if mime_type.starts_with("multipart/form-data") {
+        if post_data.params.iter().any(|p| p.file_name.is_some()) {
+            warnings.push(localized(
+                format!("{} 的multipart请求体包含文件，文件字段已忽略", name),
+                format!("{}: file fields in the multipart body are ignored", name),
+            ));
+        }
+        let form_data = post_data
+            .params
+            .into_iter()
+            .filter(|p| p.file_name.is_none())
+            .map(|p| (p.name, p.value.unwrap_or_default()))
+            .collect();
+        warnings.push(localized(
+            format!("{} 的multipart请求体按普通表单导入", name),
+            format!("{}: multipart body is imported as a plain form", name),
+        ));
+        return (None, Some(form_data));
+    }
+    if !text.is_empty() {
+        warnings.push(localized(
+            format!("{} 的请求体类型({})不支持，已忽略", name, post_data.mime_type),
+            format!("{}: unsupported request body type ({}), ignored", name, post_data.mime_type),
+        ));
+    }
+    (None, None)
+}
+
+// 方法、url、请求头、cookies和请求体都相同的请求合并，权重为出现次数
+fn merge_endpoints(steps: Vec<ScenarioStep>) -> Vec<ApiEndpoint> {
+    let mut endpoints: Vec<ApiEndpoint> = Vec::new();
+    for step in steps {
+        let endpoint = step.endpoint;
+        match endpoints.iter_mut().find(|e| {
+            e.method == endpoint.method
+                && e.url == endpoint.url
+                && e.headers == endpoint.headers
+                && e.cookies == endpoint.cookies
+                && e.json == endpoint.json
+                && e.form_data == endpoint.form_data
+        }) {
+            Some(existing) => existing.weight += 1,
+            None => endpoints.push(endpoint),
+        }
+    }
+    endpoints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HAR: &str = r#"{"log": {"entries": [
+        {"startedDateTime": "2024-03-01T10:00:00.000Z", "time": 120,
+         "request": {"method": "POST", "url": "https://api.example.com/login",
+           "headers": [{"name": ":authority", "value": "api.example.com"}, {"name": "Content-Type", "value": "application/json"},
+                       {"name": "X-Token", "value": "abc"}, {"name": "sec-fetch-mode", "value": "cors"}, {"name": "Cookie", "value": "sid=1"}],
+           "cookies": [{"name": "sid", "value": "1"}],
+           "postData": {"mimeType": "application/json", "text": "{\"user\":\"test\"}"}},
+         "response": {"content": {"mimeType": "application/json; charset=utf-8"}}},
+        {"startedDateTime": "2024-03-01T10:00:00.050Z", "time": 10,
+         "request": {"method": "GET", "url": "https://cdn.other.com/app.js", "headers": []},
+         "response": {"content": {"mimeType": "application/javascript"}}},
+        {"startedDateTime": "2024-03-01T10:00:01.620Z", "time": 30,
+         "request": {"method": "GET", "url": "https://www.api.example.com/list?page=1", "headers": []},
+         "response": {"content": {"mimeType": "application/json"}}},
+        {"startedDateTime": "2024-03-01T10:00:02.000Z", "time": 30,
+         "request": {"method": "GET", "url": "https://www.api.example.com/list?page=1", "headers": []},
+         "response": {"content": {"mimeType": "application/json"}}}
+    ]}}"#;
+
+    #[test]
+    fn test_parse_har_scenario() {
+        let option = HarImportOption { domains: vec!["api.example.com".to_string()], ..Default::default() };
+        let scenario = parse_har_scenario(HAR, &option).unwrap();
+        assert_eq!(scenario.steps.len(), 3);
+        let login = &scenario.steps[0];
+        assert_eq!(login.endpoint.name, "POST /login");
+        assert_eq!(login.endpoint.json, Some(serde_json::json!({"user": "test"})));
+        assert_eq!(login.endpoint.cookies.as_deref(), Some("sid=1"));
+        // 易变的请求头和content-type被去掉
+        let headers = login.endpoint.headers.as_ref().unwrap();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["X-Token"], "abc");
+        // 登录结束(0.12s)到列表开始(1.62s)之间等待了1.5s
+        assert_eq!(login.think_time_ms, 1500);
+        assert_eq!(scenario.steps[2].endpoint.name, "GET /list #2");
+
+        // 合并相同的请求，按响应类型过滤
+        let option = HarImportOption { content_types: vec!["application/json".to_string()], keep_cookies: false, ..Default::default() };
+        let result = parse_har_endpoints(HAR, &option).unwrap();
+        assert_eq!(result.endpoints.len(), 2);
+        assert_eq!(result.endpoints[0].cookies, None);
+        assert_eq!(result.endpoints[1].weight, 2);
+
+        // 请求头或cookies不同的请求不合并，导入警告保留在结果中
+        let har = HAR
+            .replacen(r#""url": "https://www.api.example.com/list?page=1", "headers": []"#, r#""url": "https://www.api.example.com/list?page=1", "headers": [{"name": "X-Tenant", "value": "a"}]"#, 1)
+            .replace(r#""text": "{\"user\":\"test\"}""#, r#""text": "<user/>""#);
+        let result = parse_har_endpoints(&har, &option).unwrap();
+        assert_eq!(result.endpoints.len(), 3);
+        assert!(result.endpoints.iter().all(|e| e.weight == 1));
+        assert_eq!(result.warnings.len(), 1, "{:?}", result.warnings);
+    }
+}
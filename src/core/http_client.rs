@@ -0,0 +1,513 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use reqwest::tls::{Certificate, Identity, Version};
+use reqwest::{Client, NoProxy, Proxy};
+use url::Url;
+
+use crate::models::api_endpoint::ApiEndpoint;
+use crate::models::batch_option::BatchOption;
+use crate::core::dns_resolver::DnsResolver;
+use crate::core::unix_socket::{parse_unix_url, UnixClient, UnixClientOption};
+use crate::models::connection_option::{ConnectionOption, ConnectionStrategy};
+use crate::models::dns_option::DnsOption;
+use crate::models::proxy_option::{ProxyOption, ProxyTarget};
+use crate::models::engine_error::EngineError;
+use crate::models::http2_option::Http2Option;
+use crate::models::http_version::HttpVersion;
+use crate::models::tls_option::{TlsOption, TlsVersion};
+
+// 构建http客户端用到的配置
+#[derive(Clone, Default)]
+pub(crate) struct ClientConfig {
+    pub(crate) connection: ConnectionOption,
+    pub(crate) http2: Http2Option,
+    // 接口级的客户端配置，配置相同的接口共用一个客户端
+    pub(crate) profiles: Vec<Arc<ClientProfile>>,
+    // 配置了固定解析或DNS刷新时使用的解析器，所有客户端共用
+    pub(crate) resolver: Option<DnsResolver>,
+    pub(crate) proxies: Vec<ProxyOption>,
+}
+
+impl ClientConfig {
+    // 返回配置和每个接口使用的客户端下标
+    pub(crate) async fn new(batch_option: &BatchOption, endpoints: &[ApiEndpoint]) -> Result<(Self, Vec<usize>), EngineError> {
+        let resolver = (batch_option.dns != DnsOption::default()).then(|| DnsResolver::new(&batch_option.dns));
+        let mut keys: Vec<ProfileKey> = Vec::new();
+        let mut profiles = Vec::new();
+        let mut indexes = Vec::new();
+        for endpoint in endpoints {
+            let key = ProfileKey::new(endpoint, batch_option.tls.as_ref());
+            let index = match keys.iter().position(|k| *k == key) {
+                Some(index) => index,
+                None => {
+                    profiles.push(Arc::new(ClientProfile::load(&key, resolver.as_ref()).await?));
+                    keys.push(key);
+                    keys.len() - 1
+                }
+            };
+            indexes.push(index);
+        }
+        let config = ClientConfig {
+            connection: batch_option.connection.clone(),
+            http2: batch_option.http2.clone(),
+            profiles,
+            resolver,
+            proxies: batch_option.proxies.clone(),
+        };
+        Ok((config, indexes))
+    }
+}
+
+// 区分客户端的接口配置
+#[derive(PartialEq)]
+struct ProfileKey {
+    http_version: HttpVersion,
+    tls: Option<TlsOption>,
+    // 覆盖SNI时需要把名称解析到url中的地址
+    target: Option<(String, u16)>,
+    // unix socket接口连接的socket路径
+    unix_socket: Option<String>,
+}
+
+impl ProfileKey {
+    fn new(endpoint: &ApiEndpoint, default_tls: Option<&TlsOption>) -> Self {
+        let tls = endpoint.tls.as_ref().or(default_tls).cloned();
+        let target = match tls.as_ref().and_then(|tls| tls.server_name.as_ref()) {
+            Some(_) => Url::parse(&endpoint.url)
+                .ok()
+                .filter(|url| url.scheme() == "https")
+                .and_then(|url| Some((url.host_str()?.to_string(), url.port_or_known_default()?))),
+            None => None,
+        };
+        let unix_socket = parse_unix_url(&endpoint.url).map(|(socket_path, _)| socket_path.into_owned());
+        ProfileKey { http_version: endpoint.http_version, tls, target, unix_socket }
+    }
+}
+
+// 一个客户端的接口级配置，证书和私钥已经加载
+pub(crate) struct ClientProfile {
+    http_version: HttpVersion,
+    tls: Option<TlsOption>,
+    root_certs: Vec<Certificate>,
+    identity: Option<Identity>,
+    // server_name实际连接的地址
+    resolve: Option<(String, Vec<SocketAddr>)>,
+    unix_socket: Option<String>,
+}
+
+impl ClientProfile {
+    async fn load(key: &ProfileKey, resolver: Option<&DnsResolver>) -> Result<Self, EngineError> {
+        let (root_certs, identity) = match &key.tls {
+            Some(tls) => load_tls(tls)?,
+            None => (Vec::new(), None),
+        };
+        let server_name = key.tls.as_ref().and_then(|tls| tls.server_name.clone());
+        let resolve = match (server_name, &key.target) {
+            (Some(server_name), Some((host, port))) => {
+                // 原来的主机也按固定解析处理
+                let addrs: Vec<SocketAddr> = match resolver {
+                    Some(resolver) => resolver.lookup(host).await.map(|addrs| {
+                        addrs.into_iter().map(|addr| SocketAddr::new(addr.ip(), *port)).collect()
+                    }),
+                    None => tokio::net::lookup_host((host.as_str(), *port)).await.map(|addrs| addrs.collect()),
+                }
+                .map_err(|e| EngineError::InvalidTls(format!("server_name {} -> {}: {}", server_name, host, e)))?;
+                Some((server_name, addrs))
+            }
+            _ => None,
+        };
+        Ok(ClientProfile {
+            http_version: key.http_version,
+            tls: key.tls.clone(),
+            root_certs,
+            identity,
+            resolve,
+            unix_socket: key.unix_socket.clone(),
+        })
+    }
+}
+
+// 加载根证书和客户端证书，校验配置时也会调用
+pub(crate) fn load_tls(option: &TlsOption) -> Result<(Vec<Certificate>, Option<Identity>), EngineError> {
+    let read = |path: &String| std::fs::read(path).map_err(|e| EngineError::InvalidCertificate(path.clone(), e.to_string()));
+    let root_certs = match &option.ca_cert {
+        Some(path) => Certificate::from_pem_bundle(&read(path)?).map_err(|e| EngineError::InvalidCertificate(path.clone(), e.to_string()))?,
+        None => Vec::new(),
+    };
+    // 证书组合已经校验过，pkcs12优先
+    let identity = match (&option.pkcs12, &option.client_cert, &option.client_key) {
+        (Some(path), _, _) => Some(
+            Identity::from_pkcs12_der(&read(path)?, option.pkcs12_password.as_deref().unwrap_or(""))
+                .map_err(|e| EngineError::InvalidCertificate(path.clone(), e.to_string()))?,
+        ),
+        (None, Some(cert), Some(key)) => Some(
+            Identity::from_pkcs8_pem(&read(cert)?, &read(key)?).map_err(|e| EngineError::InvalidCertificate(cert.clone(), e.to_string()))?,
+        ),
+        _ => None,
+    };
+    Ok((root_certs, identity))
+}
+
+// 覆盖SNI时请求发送到server_name，再由客户端解析到url中的地址
+pub(crate) fn request_url(endpoint: &ApiEndpoint, default_tls: Option<&TlsOption>) -> String {
+    let server_name = endpoint.tls.as_ref().or(default_tls).and_then(|tls| tls.server_name.as_ref());
+    match (server_name, Url::parse(&endpoint.url)) {
+        (Some(server_name), Ok(mut url)) if url.scheme() == "https" => {
+            let port = url.port_or_known_default();
+            if url.set_host(Some(server_name)).is_err() {
+                return endpoint.url.clone();
+            }
+            // 默认端口会被省略，改名后按原来的地址连接
+            let _ = url.set_port(port);
+            url.to_string()
+        }
+        _ => endpoint.url.clone(),
+    }
+}
+
+// 一个并发使用的客户端，每个接口级配置一个
+// unix socket接口用reqwest客户端构建请求，再通过unix socket客户端发送
+pub(crate) struct HttpClients {
+    clients: Vec<Client>,
+    unix_clients: Vec<Option<UnixClient>>,
+}
+
+impl HttpClients {
+    pub(crate) fn build(config: &ClientConfig, local_address: Option<IpAddr>) -> Result<Self, EngineError> {
+        let clients = config.profiles.iter().map(|profile| build_client(config, profile, local_address)).collect::<Result<_, _>>()?;
+        let unix_clients = config
+            .profiles
+            .iter()
+            .map(|profile| profile.unix_socket.as_deref().map(|socket_path| build_unix_client(config, profile, socket_path)).transpose())
+            .collect::<Result<_, _>>()?;
+        Ok(HttpClients { clients, unix_clients })
+    }
+
+    pub(crate) fn get(&self, index: usize) -> &Client {
+        &self.clients[index]
+    }
+
+    pub(crate) fn get_unix(&self, index: usize) -> Option<&UnixClient> {
+        self.unix_clients[index].as_ref()
+    }
+}
+
+// unix socket客户端使用相同的连接池和HTTP/2配置，没有TLS和代理
+fn build_unix_client(config: &ClientConfig, profile: &ClientProfile, socket_path: &str) -> Result<UnixClient, EngineError> {
+    let option = &config.connection;
+    let http2 = &config.http2;
+    let pool_max_idle_per_host = match option.strategy {
+        ConnectionStrategy::PerRequest => Some(0),
+        _ => option.pool_max_idle_per_host,
+    };
+    let option = UnixClientOption {
+        pool_max_idle_per_host,
+        pool_idle_timeout: option.pool_idle_timeout_secs.map(Duration::from_secs),
+        http2_only: profile.http_version == HttpVersion::Http2PriorKnowledge,
+        http2_initial_stream_window_size: http2.initial_stream_window_size,
+        http2_initial_connection_window_size: http2.initial_connection_window_size,
+        http2_adaptive_window: http2.adaptive_window,
+        http2_keep_alive_interval: http2.keep_alive_interval_secs.map(Duration::from_secs),
+    };
+    UnixClient::new(socket_path, option)
+}
+
+// 按配置构建http客户端，超时时间在每个请求上设置
+fn build_client(config: &ClientConfig, profile: &ClientProfile, local_address: Option<IpAddr>) -> Result<Client, EngineError> {
+    let option = &config.connection;
+    // unix socket接口的客户端只用来构建请求，不使用本地地址和代理
+    let local_address = local_address.filter(|_| profile.unix_socket.is_none());
+    let mut builder = Client::builder().local_address(local_address);
+    if let Some(max_idle) = option.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    if let Some(idle_timeout) = option.pool_idle_timeout_secs {
+        builder = builder.pool_idle_timeout(Duration::from_secs(idle_timeout));
+    }
+    // 不保留空闲连接，每个请求都会新建连接
+    if option.strategy == ConnectionStrategy::PerRequest {
+        builder = builder.pool_max_idle_per_host(0);
+    }
+    builder = match profile.http_version {
+        HttpVersion::Http1 => builder.http1_only(),
+        HttpVersion::Http2PriorKnowledge => builder.http2_prior_knowledge(),
+        // 通过ALPN协商，请求时指定HTTP/2版本
+        HttpVersion::Auto | HttpVersion::Http2 => builder,
+    };
+    let http2 = &config.http2;
+    builder = builder
+        .http2_initial_stream_window_size(http2.initial_stream_window_size)
+        .http2_initial_connection_window_size(http2.initial_connection_window_size)
+        .http2_adaptive_window(http2.adaptive_window)
+        .http2_keep_alive_interval(http2.keep_alive_interval_secs.map(Duration::from_secs));
+    if let Some(tls) = &profile.tls {
+        for cert in &profile.root_certs {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        if let Some(identity) = &profile.identity {
+            builder = builder.identity(identity.clone());
+        }
+        if tls.danger_accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true).danger_accept_invalid_hostnames(true);
+        }
+        if let Some(min_version) = tls.min_version {
+            builder = builder.min_tls_version(tls_version(min_version));
+        }
+        if let Some(max_version) = tls.max_version {
+            builder = builder.max_tls_version(tls_version(max_version));
+        }
+    }
+    if profile.unix_socket.is_some() {
+        builder = builder.no_proxy();
+    } else {
+        for option in &config.proxies {
+            builder = builder.proxy(build_proxy(option)?);
+        }
+    }
+    if let Some(resolver) = &config.resolver {
+        builder = builder.dns_resolver(Arc::new(resolver.clone()));
+    }
+    if let Some((server_name, addrs)) = &profile.resolve {
+        builder = builder.resolve_to_addrs(server_name, addrs);
+    }
+    builder.build().map_err(EngineError::ClientBuild)
+}
+
+// 代理地址已经校验过
+fn build_proxy(option: &ProxyOption) -> Result<Proxy, EngineError> {
+    let proxy = match option.target {
+        ProxyTarget::All => Proxy::all(&option.url),
+        ProxyTarget::Http => Proxy::http(&option.url),
+        ProxyTarget::Https => Proxy::https(&option.url),
+    };
+    let mut proxy = proxy.map_err(|e| EngineError::InvalidProxy(option.url.clone(), e.to_string()))?;
+    if let Some(username) = &option.username {
+        proxy = proxy.basic_auth(username, option.password.as_deref().unwrap_or(""));
+    }
+    Ok(proxy.no_proxy(NoProxy::from_string(&option.no_proxy.join(","))))
+}
+
+fn tls_version(version: TlsVersion) -> Version {
+    match version {
+        TlsVersion::Tls10 => Version::TLS_1_0,
+        TlsVersion::Tls11 => Version::TLS_1_1,
+        TlsVersion::Tls12 => Version::TLS_1_2,
+        TlsVersion::Tls13 => Version::TLS_1_3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::batch::batch;
+    use crate::core::test_server::{spawn_h2c_server, spawn_http_server, spawn_peer_recording_server, spawn_plain_reply_server, spawn_socks5_server};
+    use crate::core::validate::dry_run;
+    use crate::models::engine_error::{set_language, EngineError, Language};
+    use crate::models::api_endpoint::ApiEndpoint;
+    use crate::models::batch_option::BatchOption;
+
+    #[tokio::test]
+    async fn test_connection_strategy() {
+        let (addr, _rx) = spawn_http_server(200, "ok").await;
+        let endpoint = ApiEndpoint {
+            name: "connection".to_string(),
+            url: format!("http://{}/", addr),
+            ..Default::default()
+        };
+        // 2个并发各请求5次，返回(新建连接数, 复用次数)
+        let connections = |strategy: ConnectionStrategy| {
+            let endpoint = endpoint.clone();
+            async move {
+                let batch_option = BatchOption {
+                    iterations_per_vu: Some(5),
+                    connection: ConnectionOption { strategy, ..Default::default() },
+                    ..Default::default()
+                };
+                let result = batch(5, 2, false, false, vec![endpoint], None, Some(batch_option)).await.unwrap();
+                (result.api_results[0].connections_opened, result.api_results[0].connections_reused)
+            }
+        };
+        assert_eq!(connections(ConnectionStrategy::PerVu).await, (2, 8));
+        assert_eq!(connections(ConnectionStrategy::PerRequest).await, (10, 0));
+        let (opened, reused) = connections(ConnectionStrategy::Shared).await;
+        assert!((1..=2).contains(&opened) && opened + reused == 10, "{} {}", opened, reused);
+    }
+
+    #[tokio::test]
+    async fn test_http_version() {
+        let (addr, connections) = spawn_h2c_server("ok").await;
+        let endpoint = ApiEndpoint {
+            name: "h2c".to_string(),
+            url: format!("http://{}/", addr),
+            http_version: HttpVersion::Http2PriorKnowledge,
+            ..Default::default()
+        };
+        // 4个并发共用连接池，每个共享客户端分配2个并发，每个连接最多2个并发流
+        let batch_option = BatchOption {
+            iterations_per_vu: Some(5),
+            connection: ConnectionOption { strategy: ConnectionStrategy::Shared, vus_per_client: Some(2), ..Default::default() },
+            ..Default::default()
+        };
+        let result = batch(5, 4, false, false, vec![endpoint.clone()], None, Some(batch_option)).await.unwrap();
+        let api_result = &result.api_results[0];
+        assert_eq!(api_result.http_versions.get("HTTP/2.0"), Some(&20));
+        assert_eq!(api_result.connections_opened, 2);
+        assert_eq!(connections.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+        let (addr, _rx) = spawn_http_server(200, "ok").await;
+        let http1 = ApiEndpoint { url: format!("http://{}/", addr), http_version: HttpVersion::Http1, ..endpoint.clone() };
+        let batch_option = BatchOption { iterations_per_vu: Some(1), ..Default::default() };
+        let result = batch(5, 1, false, false, vec![http1], None, Some(batch_option)).await.unwrap();
+        assert_eq!(result.api_results[0].http_versions.get("HTTP/1.1"), Some(&1));
+
+        // 明文http不能通过ALPN协商HTTP/2
+        let alpn = ApiEndpoint { http_version: HttpVersion::Http2, ..endpoint };
+        let problems = match dry_run(5, 1, &[alpn], None, None) {
+            Err(EngineError::Validation(problems)) => problems,
+            _ => panic!("应该校验失败"),
+        };
+        assert!(matches!(problems[0], EngineError::InvalidEndpoint(_, ref e) if matches!(**e, EngineError::Http2RequiresHttps(_))));
+    }
+
+    #[tokio::test]
+    async fn test_tls() {
+        set_language(Language::Chinese);
+        // 对明文http服务发起https请求，握手失败单独归类
+        let addr = spawn_plain_reply_server().await;
+        let endpoint = ApiEndpoint {
+            name: "tls".to_string(),
+            url: format!("https://{}/", addr),
+            timeout_secs: 5,
+            tls: Some(TlsOption { danger_accept_invalid_certs: true, min_version: Some(TlsVersion::Tls12), ..Default::default() }),
+            ..Default::default()
+        };
+        let batch_option = BatchOption { iterations_per_vu: Some(2), ..Default::default() };
+        let result = batch(5, 1, false, false, vec![endpoint.clone()], None, Some(batch_option)).await.unwrap();
+        assert_eq!(result.err_count, 2);
+        assert!(result.http_errors.keys().all(|(_, message, _)| message.starts_with("TLS握手失败::")), "{:?}", result.http_errors);
+
+        let client = HttpClients::build(&ClientConfig::new(&BatchOption::default(), std::slice::from_ref(&endpoint)).await.unwrap().0, None).unwrap();
+        let error = client.get(0).get(&endpoint.url).send().await.unwrap_err();
+        assert_eq!(crate::models::error_kind::ErrorKind::from_reqwest(&error), crate::models::error_kind::ErrorKind::Tls);
+
+        // 证书文件不存在和证书组合错误在校验时报出
+        let invalid = ApiEndpoint {
+            tls: Some(TlsOption { ca_cert: Some("not_exists.pem".to_string()), client_key: Some("key.pem".to_string()), ..Default::default() }),
+            ..endpoint.clone()
+        };
+        assert!(dry_run(5, 1, std::slice::from_ref(&invalid), None, None).is_err());
+        let invalid = ApiEndpoint { tls: Some(TlsOption { ca_cert: Some("not_exists.pem".to_string()), ..Default::default() }), ..endpoint };
+        let problems = match dry_run(5, 1, &[invalid], None, None) {
+            Err(EngineError::Validation(problems)) => problems,
+            _ => panic!("应该校验失败"),
+        };
+        assert!(matches!(problems[0], EngineError::InvalidEndpoint(_, ref e) if matches!(**e, EngineError::InvalidCertificate(..))));
+    }
+
+    #[tokio::test]
+    async fn test_proxy() {
+        // 本地服务作为http代理，请求行是完整的url
+        let (proxy_addr, mut proxy_rx) = spawn_http_server(200, "ok").await;
+        let (direct_addr, mut direct_rx) = spawn_http_server(200, "ok").await;
+        let endpoint = ApiEndpoint {
+            name: "proxy".to_string(),
+            url: "http://only-via-proxy.atomic-bomb.test/items".to_string(),
+            timeout_secs: 5,
+            ..Default::default()
+        };
+        // no_proxy中的主机直接连接
+        let direct = ApiEndpoint { name: "direct".to_string(), url: format!("http://{}/", direct_addr), ..endpoint.clone() };
+        let proxy = ProxyOption {
+            url: format!("http://{}", proxy_addr),
+            username: Some("user".to_string()),
+            password: Some("secret".to_string()),
+            no_proxy: vec!["127.0.0.1".to_string()],
+            ..Default::default()
+        };
+        let batch_option = BatchOption { iterations_per_vu: Some(2), proxies: vec![proxy.clone()], ..Default::default() };
+        let result = batch(5, 1, false, false, vec![endpoint.clone(), direct], None, Some(batch_option)).await.unwrap();
+        assert_eq!(result.err_count, 0);
+        let request = proxy_rx.recv().await.unwrap();
+        assert!(request.starts_with("GET http://only-via-proxy.atomic-bomb.test/items HTTP/1.1"), "{}", request);
+        assert!(request.to_lowercase().contains("proxy-authorization: basic dxnlcjpzzwnyzxq="), "{}", request);
+        assert!(direct_rx.recv().await.unwrap().starts_with("GET / HTTP/1.1"));
+
+        let socks = ProxyOption { url: "socks4://127.0.0.1:1080".to_string(), ..proxy };
+        let batch_option = BatchOption { proxies: vec![socks], ..Default::default() };
+        let problems = match dry_run(5, 1, &[endpoint], None, Some(&batch_option)) {
+            Err(EngineError::Validation(problems)) => problems,
+            _ => panic!("应该校验失败"),
+        };
+        assert!(matches!(problems[0], EngineError::UnsupportedProxy(_)));
+    }
+
+    #[tokio::test]
+    async fn test_socks5_proxy() {
+        let (upstream_addr, mut upstream_rx) = spawn_http_server(200, "ok").await;
+        let (direct_addr, mut direct_rx) = spawn_http_server(200, "ok").await;
+        let (socks_addr, mut socks_rx) = spawn_socks5_server(upstream_addr).await;
+        // socks5h由代理解析域名，本地解析不了的域名也能访问
+        let endpoint = ApiEndpoint {
+            name: "socks".to_string(),
+            url: "http://only-via-proxy.atomic-bomb.test/items".to_string(),
+            timeout_secs: 5,
+            ..Default::default()
+        };
+        // no_proxy中的主机直接连接，不经过socks5代理
+        let direct = ApiEndpoint { name: "direct".to_string(), url: format!("http://{}/", direct_addr), ..endpoint.clone() };
+        let proxy = ProxyOption {
+            url: format!("socks5h://{}", socks_addr),
+            username: Some("user".to_string()),
+            password: Some("secret".to_string()),
+            no_proxy: vec!["127.0.0.1".to_string()],
+            ..Default::default()
+        };
+        let batch_option = BatchOption { iterations_per_vu: Some(2), proxies: vec![proxy], ..Default::default() };
+        let result = batch(5, 1, false, false, vec![endpoint, direct], None, Some(batch_option)).await.unwrap();
+        assert_eq!(result.err_count, 0);
+        assert_eq!(socks_rx.recv().await.unwrap(), "user:secret@only-via-proxy.atomic-bomb.test:80");
+        while let Ok(connection) = socks_rx.try_recv() {
+            assert_eq!(connection, "user:secret@only-via-proxy.atomic-bomb.test:80");
+        }
+        assert!(upstream_rx.recv().await.unwrap().starts_with("GET /items HTTP/1.1"));
+        assert!(direct_rx.recv().await.unwrap().starts_with("GET / HTTP/1.1"));
+    }
+
+    #[tokio::test]
+    async fn test_local_addresses() {
+        let (addr, mut peers) = spawn_peer_recording_server("ok").await;
+        let endpoint = ApiEndpoint {
+            name: "local".to_string(),
+            url: format!("http://{}/", addr),
+            timeout_secs: 5,
+            ..Default::default()
+        };
+        // 4个并发轮流绑定两个回环地址
+        let local_addresses: Vec<IpAddr> = vec!["127.0.0.1".parse().unwrap(), "127.0.0.2".parse().unwrap()];
+        let batch_option = BatchOption {
+            iterations_per_vu: Some(2),
+            connection: ConnectionOption { local_addresses: local_addresses.clone(), ..Default::default() },
+            ..Default::default()
+        };
+        let result = batch(5, 4, false, false, vec![endpoint.clone()], None, Some(batch_option)).await.unwrap();
+        assert_eq!(result.err_count, 0);
+        let mut ips = Vec::new();
+        while let Ok(peer) = peers.try_recv() {
+            ips.push(peer.ip());
+        }
+        assert_eq!(ips.len(), 4);
+        assert_eq!(ips.iter().filter(|ip| **ip == local_addresses[1]).count(), 2);
+
+        // 不是本机的地址在校验时报错，运行时的EADDRNOTAVAIL才算端口耗尽
+        let batch_option = BatchOption {
+            connection: ConnectionOption { local_addresses: vec!["192.0.2.1".parse().unwrap()], ..Default::default() },
+            ..Default::default()
+        };
+        let problems = match dry_run(5, 1, &[endpoint], None, Some(&batch_option)) {
+            Err(EngineError::Validation(problems)) => problems,
+            _ => panic!("应该校验失败"),
+        };
+        assert!(matches!(problems[0], EngineError::InvalidLocalAddress(..)));
+    }
+}
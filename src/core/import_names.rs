@@ -0,0 +1,8 @@
+use std::collections::HashMap;
+
+// 导入接口时生成不重复的名称，重复时加序号
+pub(crate) fn unique_name(names: &mut HashMap<String, usize>, base_name: String) -> String {
+    let count = names.entry(base_name.clone()).or_insert(0);
+    *count += 1;
+    if *count == 1 { base_name } else { format!("{} #{}", base_name, count) }
+}
@@ -3,5 +3,25 @@ mod parse_form_data;
 pub mod status_share;
 pub mod sleep_guard;
 pub mod batch;
+pub mod batch_control;
 pub mod check_endpoints_names;
+pub mod validate;
+pub mod request_log;
+pub mod report;
+pub mod compare;
+pub mod threshold;
+pub mod test_plan;
+pub mod har_import;
+pub mod openapi_import;
+pub mod curl_import;
+pub mod postman_import;
 mod concurrency_controller;
+mod dns_resolver;
+mod endpoint_mixer;
+mod fast_rng;
+mod http_client;
+mod import_names;
+mod think_time;
+mod unix_socket;
+#[cfg(test)]
+pub(crate) mod test_server;
@@ -0,0 +1,510 @@
+use std::collections::HashMap;
+use std::path::Path;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde_json::{Map, Value};
+
+use crate::core::import_names::unique_name;
+use crate::models::api_endpoint::ApiEndpoint;
+use crate::models::assert_option::AssertOption;
+use crate::models::engine_error::{localized, EngineError};
+use crate::models::import_result::ImportResult;
+use crate::models::openapi_import_option::OpenApiImportOption;
+
+// openapi中的请求方法
+const METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+// 解析$ref和schema的最大深度，防止循环引用
+const MAX_DEPTH: usize = 8;
+// 路径参数的值只能占一段路径，"/"、"?"、"#"和"%"等字符需要编码
+const PATH_SEGMENT: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'%').add(b'/').add(b'<').add(b'>').add(b'?').add(b'`').add(b'{').add(b'}');
+
+// 从openapi 3文档(json/yaml)导入接口，每个operation生成一个接口
+pub fn load_openapi_endpoints(path: impl AsRef<Path>, option: &OpenApiImportOption) -> Result<ImportResult, EngineError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|e| EngineError::Io(path.display().to_string(), e))?;
+    parse_openapi_endpoints(&source, option)
+}
+
+// json是yaml的子集，统一按yaml解析
+pub fn parse_openapi_endpoints(source: &str, option: &OpenApiImportOption) -> Result<ImportResult, EngineError> {
+    let root: Value = serde_yaml::from_str(source).map_err(|e| EngineError::InvalidDocument("openapi".to_string(), e.to_string()))?;
+    let version = root.get("openapi").and_then(|v| v.as_str()).unwrap_or_default();
+    if !version.starts_with('3') {
+        let version = if version.is_empty() { localized("未知", "unknown") } else { version };
+        return Err(EngineError::UnsupportedDocumentVersion("openapi".to_string(), "3".to_string(), version.to_string()));
+    }
+    let base_url = match &option.base_url {
+        Some(base_url) => base_url.clone(),
+        None => server_url(&root)?,
+    };
+    let mut importer = Importer { root: &root, warnings: Vec::new() };
+    let mut endpoints = Vec::new();
+    let mut names = HashMap::new();
+    let paths = root.get("paths").and_then(|p| p.as_object()).cloned().unwrap_or_default();
+    for (path, path_item) in &paths {
+        let path_item = importer.resolve(path_item);
+        for method in METHODS {
+            let operation = match path_item.get(method) {
+                None => continue,
+                Some(operation) => operation,
+            };
+            if !option.tags.is_empty() {
+                let tags = operation.get("tags").and_then(|t| t.as_array()).cloned().unwrap_or_default();
+                if !tags.iter().any(|t| t.as_str().map(|t| option.tags.iter().any(|o| o == t)).unwrap_or(false)) {
+                    continue;
+                }
+            }
+            let base_name = operation
+                .get("operationId")
+                .and_then(|id| id.as_str())
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| format!("{} {}", method.to_uppercase(), path));
+            let name = unique_name(&mut names, base_name);
+            let endpoint = importer.convert_operation(name, &base_url, path, method, path_item, operation, option);
+            endpoints.push(endpoint);
+        }
+    }
+    if endpoints.is_empty() {
+        return Err(EngineError::NothingToImport("openapi".to_string()));
+    }
+    Ok(ImportResult { endpoints, warnings: importer.warnings })
+}
+
+// 使用第一个server，并用变量的默认值替换模板
+fn server_url(root: &Value) -> Result<String, EngineError> {
+    let server = root
+        .get("servers")
+        .and_then(|s| s.as_array())
+        .and_then(|s| s.first())
+        .ok_or_else(|| EngineError::MissingBaseUrl(String::new()))?;
+    let mut url = server.get("url").and_then(|u| u.as_str()).unwrap_or_default().to_string();
+    if let Some(variables) = server.get("variables").and_then(|v| v.as_object()) {
+        for (name, variable) in variables {
+            let default = variable.get("default").and_then(|d| d.as_str()).unwrap_or_default();
+            url = url.replace(&format!("{{{}}}", name), default);
+        }
+    }
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(EngineError::MissingBaseUrl(url));
+    }
+    Ok(url)
+}
+
+struct Importer<'a> {
+    root: &'a Value,
+    warnings: Vec<String>,
+}
+
+impl<'a> Importer<'a> {
+    // 解析#/开头的本地引用，其他引用原样返回
+    fn resolve(&self, value: &'a Value) -> &'a Value {
+        let mut value = value;
+        for _ in 0..MAX_DEPTH {
+            match value.get("$ref").and_then(|r| r.as_str()).and_then(|r| r.strip_prefix('#')) {
+                Some(pointer) => match self.root.pointer(pointer) {
+                    Some(target) => value = target,
+                    None => return value,
+                },
+                None => return value,
+            }
+        }
+        value
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn convert_operation(
+        &mut self,
+        name: String,
+        base_url: &str,
+        path: &str,
+        method: &str,
+        path_item: &'a Value,
+        operation: &'a Value,
+        option: &OpenApiImportOption,
+    ) -> ApiEndpoint {
+        let mut url_path = path.to_string();
+        let mut query = Vec::new();
+        let mut headers = HashMap::new();
+        let mut cookies = Vec::new();
+        // operation上的参数覆盖path上的同名参数
+        let mut parameters: Vec<&Value> = Vec::new();
+        for list in [path_item.get("parameters"), operation.get("parameters")].into_iter().flatten() {
+            for parameter in list.as_array().into_iter().flatten() {
+                let parameter = self.resolve(parameter);
+                let key = (parameter.get("name"), parameter.get("in"));
+                parameters.retain(|p| (p.get("name"), p.get("in")) != key);
+                parameters.push(parameter);
+            }
+        }
+        for parameter in parameters {
+            let param_name = parameter.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+            let location = parameter.get("in").and_then(|n| n.as_str()).unwrap_or_default();
+            let required = parameter.get("required").and_then(|r| r.as_bool()).unwrap_or(false);
+            if location == "path" {
+                let value = match option.path_params.get(param_name) {
+                    Some(value) => value.clone(),
+                    None => match self.parameter_example(parameter) {
+                        Some(value) => value_to_string(&value),
+                        None => {
+                            self.warnings.push(localized(
+                                format!("{} 的路径参数{}没有示例，请在path_params中设置", name, param_name),
+                                format!("{}: path parameter {} has no example, please set it in path_params", name, param_name),
+                            ));
+                            continue;
+                        }
+                    },
+                };
+                let value = utf8_percent_encode(&value, PATH_SEGMENT).to_string();
+                url_path = url_path.replace(&format!("{{{}}}", param_name), &value);
+                continue;
+            }
+            // 可选参数不导入
+            if !required {
+                continue;
+            }
+            let value = self.parameter_example(parameter).map(|v| value_to_string(&v)).unwrap_or_default();
+            match location {
+                "query" => query.push((param_name.to_string(), value)),
+                "header" => {
+                    headers.insert(param_name.to_string(), value);
+                }
+                "cookie" => cookies.push(format!("{}={}", param_name, value)),
+                _ => {}
+            }
+        }
+        let mut url = format!("{}{}", base_url.trim_end_matches('/'), url_path);
+        if !query.is_empty() {
+            let query: String = url::form_urlencoded::Serializer::new(String::new()).extend_pairs(query).finish();
+            url = format!("{}?{}", url, query);
+        }
+
+        let (json, form_data) = match operation.get("requestBody") {
+            None => (None, None),
+            Some(body) => self.convert_body(&name, self.resolve(body)),
+        };
+        let (expected_status, assert_options) = self.convert_responses(operation, option);
+        ApiEndpoint {
+            name,
+            url,
+            method: method.to_uppercase(),
+            json,
+            form_data,
+            headers: if headers.is_empty() { None } else { Some(headers) },
+            cookies: if cookies.is_empty() { None } else { Some(cookies.join("; ")) },
+            assert_options,
+            expected_status,
+            ..Default::default()
+        }
+    }
+
+    // 请求体只支持json和表单，优先使用json
+    fn convert_body(&mut self, name: &str, body: &'a Value) -> (Option<Value>, Option<HashMap<String, String>>) {
+        let content = match body.get("content").and_then(|c| c.as_object()) {
+            None => return (None, None),
+            Some(content) => content,
+        };
+        if let Some((_, media)) = content.iter().find(|(mime, _)| mime.contains("json")) {
+            return (Some(self.media_example(media)), None);
+        }
+        if let Some((_, media)) = content
+            .iter()
+            .find(|(mime, _)| mime.starts_with("application/x-www-form-urlencoded") || mime.starts_with("multipart/form-data"))
+        {
+            let form_data = match self.media_example(media) {
+                Value::Object(fields) => fields.iter().map(|(k, v)| (k.clone(), value_to_string(v))).collect(),
+                _ => HashMap::new(),
+            };
+            return (None, Some(form_data));
+        }
+        let types: Vec<&String> = content.keys().collect();
+        self.warnings.push(localized(
+            format!("{} 的请求体类型{:?}不支持，已忽略", name, types),
+            format!("{}: unsupported request body types {:?}, ignored", name, types),
+        ));
+        (None, None)
+    }
+
+    // 成功响应的状态码，以及响应schema中固定值的断言
+    fn convert_responses(&mut self, operation: &'a Value, option: &OpenApiImportOption) -> (Option<Vec<u16>>, Option<Vec<AssertOption>>) {
+        let responses = match operation.get("responses").and_then(|r| r.as_object()) {
+            None => return (None, None),
+            Some(responses) => responses,
+        };
+        let mut codes: Vec<u16> = responses
+            .keys()
+            .filter_map(|code| code.parse::<u16>().ok())
+            .filter(|code| (100..400).contains(code))
+            .collect();
+        codes.sort();
+        let expected_status = if option.expect_status && !codes.is_empty() { Some(codes.clone()) } else { None };
+        if !option.generate_asserts {
+            return (expected_status, None);
+        }
+        let schema = codes
+            .first()
+            .and_then(|code| responses.get(&code.to_string()))
+            .map(|response| self.resolve(response))
+            .and_then(|response| response.get("content"))
+            .and_then(|content| content.as_object())
+            .and_then(|content| content.iter().find(|(mime, _)| mime.contains("json")).map(|(_, media)| media))
+            .and_then(|media| media.get("schema"))
+            .map(|schema| self.resolve(schema));
+        let mut assert_options = Vec::new();
+        if let Some(properties) = schema.and_then(|s| s.get("properties")).and_then(|p| p.as_object()) {
+            for (property, property_schema) in properties {
+                let property_schema = self.resolve(property_schema);
+                let fixed = match (property_schema.get("const"), property_schema.get("enum").and_then(|e| e.as_array())) {
+                    (Some(value), _) => Some(value.clone()),
+                    (None, Some(values)) if values.len() == 1 => Some(values[0].clone()),
+                    _ => None,
+                };
+                if let Some(reference_object) = fixed {
+                    // 属性名可能包含"."等字符，使用括号形式
+                    let jsonpath = format!("$['{}']", property.replace('\'', "\\'"));
+                    assert_options.push(AssertOption { jsonpath, reference_object });
+                }
+            }
+        }
+        (expected_status, if assert_options.is_empty() { None } else { Some(assert_options) })
+    }
+
+    // 参数示例: example > examples > schema
+    fn parameter_example(&self, parameter: &'a Value) -> Option<Value> {
+        if let Some(example) = parameter.get("example") {
+            return Some(example.clone());
+        }
+        if let Some(example) = self.first_example(parameter) {
+            return Some(example);
+        }
+        parameter.get("schema").map(|schema| self.schema_example(schema, 0)).filter(|v| !v.is_null())
+    }
+
+    // 请求体示例: example > examples > schema
+    fn media_example(&self, media: &'a Value) -> Value {
+        if let Some(example) = media.get("example") {
+            return example.clone();
+        }
+        if let Some(example) = self.first_example(media) {
+            return example;
+        }
+        media.get("schema").map(|schema| self.schema_example(schema, 0)).unwrap_or(Value::Null)
+    }
+
+    fn first_example(&self, value: &'a Value) -> Option<Value> {
+        value
+            .get("examples")
+            .and_then(|e| e.as_object())
+            .and_then(|e| e.values().next())
+            .map(|example| self.resolve(example))
+            .and_then(|example| example.get("value"))
+            .cloned()
+    }
+
+    // 根据schema生成示例值
+    fn schema_example(&self, schema: &'a Value, depth: usize) -> Value {
+        if depth > MAX_DEPTH {
+            return Value::Null;
+        }
+        let schema = self.resolve(schema);
+        for key in ["example", "default", "const"] {
+            if let Some(value) = schema.get(key) {
+                return value.clone();
+            }
+        }
+        if let Some(value) = schema.get("examples").and_then(|e| e.as_array()).and_then(|e| e.first()) {
+            return value.clone();
+        }
+        if let Some(value) = schema.get("enum").and_then(|e| e.as_array()).and_then(|e| e.first()) {
+            return value.clone();
+        }
+        if let Some(all_of) = schema.get("allOf").and_then(|a| a.as_array()) {
+            let mut merged = Map::new();
+            for item in all_of {
+                if let Value::Object(fields) = self.schema_example(item, depth + 1) {
+                    merged.extend(fields);
+                }
+            }
+            return Value::Object(merged);
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(first) = schema.get(key).and_then(|a| a.as_array()).and_then(|a| a.first()) {
+                return self.schema_example(first, depth + 1);
+            }
+        }
+        // 3.1中type可以是数组
+        let schema_type = match schema.get("type") {
+            Some(Value::String(t)) => t.as_str(),
+            Some(Value::Array(types)) => types.iter().filter_map(|t| t.as_str()).find(|t| *t != "null").unwrap_or("null"),
+            _ if schema.get("properties").is_some() => "object",
+            _ => "",
+        };
+        match schema_type {
+            "object" => {
+                let mut fields = Map::new();
+                for (property, property_schema) in schema.get("properties").and_then(|p| p.as_object()).into_iter().flatten() {
+                    fields.insert(property.clone(), self.schema_example(property_schema, depth + 1));
+                }
+                Value::Object(fields)
+            }
+            "array" => match schema.get("items") {
+                Some(items) => Value::Array(vec![self.schema_example(items, depth + 1)]),
+                None => Value::Array(Vec::new()),
+            },
+            "string" => Value::from(match schema.get("format").and_then(|f| f.as_str()) {
+                Some("date-time") => "2024-01-01T00:00:00Z",
+                Some("date") => "2024-01-01",
+                Some("email") => "user@example.com",
+                Some("uuid") => "00000000-0000-0000-0000-000000000000",
+                Some("uri") => "https://example.com",
+                _ => "string",
+            }),
+            "integer" => Value::from(schema.get("minimum").and_then(|m| m.as_i64()).unwrap_or(1)),
+            "number" => Value::from(schema.get("minimum").and_then(|m| m.as_f64()).unwrap_or(1.0)),
+            "boolean" => Value::Bool(true),
+            _ => Value::Null,
+        }
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r##"
+openapi: 3.0.3
+servers:
+  - url: https://{env}.example.com/v1
+    variables:
+      env:
+        default: api
+paths:
+  /users/{id}:
+    parameters:
+      - name: id
+        in: path
+        required: true
+        schema:
+          type: integer
+          example: 42
+    get:
+      operationId: getUser
+      tags: [user]
+      parameters:
+        - name: verbose
+          in: query
+          required: true
+          schema:
+            type: boolean
+        - name: X-Tenant
+          in: header
+          required: true
+          example: acme
+      responses:
+        "200":
+          description: ok
+          content:
+            application/json:
+              schema:
+                $ref: "#/components/schemas/UserResponse"
+        "404":
+          description: not found
+  /users:
+    post:
+      tags: [user]
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: "#/components/schemas/User"
+      responses:
+        "201":
+          description: created
+  /health:
+    get:
+      tags: [ops]
+      responses:
+        "200":
+          description: ok
+components:
+  schemas:
+    User:
+      type: object
+      properties:
+        name:
+          type: string
+          example: alice
+        email:
+          type: string
+          format: email
+        roles:
+          type: array
+          items:
+            type: string
+            enum: [admin, user]
+    UserResponse:
+      type: object
+      properties:
+        code:
+          type: integer
+          enum: [0]
+        data:
+          $ref: "#/components/schemas/User"
+"##;
+
+    #[test]
+    fn test_parse_openapi_endpoints() {
+        let option = OpenApiImportOption { tags: vec!["user".to_string()], generate_asserts: true, ..Default::default() };
+        let result = parse_openapi_endpoints(SPEC, &option).unwrap();
+        assert_eq!(result.endpoints.len(), 2);
+        let find = |endpoints: &[ApiEndpoint], name: &str| endpoints.iter().find(|e| e.name == name).cloned().unwrap();
+
+        let get_user = &find(&result.endpoints, "getUser");
+        assert_eq!(get_user.name, "getUser");
+        assert_eq!(get_user.url, "https://api.example.com/v1/users/42?verbose=true");
+        assert_eq!(get_user.headers.as_ref().unwrap()["X-Tenant"], "acme");
+        assert_eq!(get_user.expected_status, Some(vec![200]));
+        let assert_options = get_user.assert_options.as_ref().unwrap();
+        assert_eq!(assert_options.len(), 1);
+        assert_eq!(assert_options[0].jsonpath, "$['code']");
+        assert_eq!(assert_options[0].reference_object, Value::from(0));
+
+        let create_user = &find(&result.endpoints, "POST /users");
+        assert_eq!(
+            create_user.json,
+            Some(serde_json::json!({"name": "alice", "email": "user@example.com", "roles": ["admin"]}))
+        );
+        assert_eq!(create_user.expected_status, Some(vec![201]));
+
+        // 覆盖地址和路径参数，参数值按一段路径编码
+        let option = OpenApiImportOption {
+            base_url: Some("http://127.0.0.1:8080".to_string()),
+            path_params: HashMap::from([("id".to_string(), "a/b?c#d e%".to_string())]),
+            ..Default::default()
+        };
+        let result = parse_openapi_endpoints(SPEC, &option).unwrap();
+        assert_eq!(result.endpoints.len(), 3);
+        assert_eq!(find(&result.endpoints, "getUser").url, "http://127.0.0.1:8080/users/a%2Fb%3Fc%23d%20e%25?verbose=true");
+    }
+
+    #[test]
+    fn test_openapi_assert_jsonpath() {
+        let spec = SPEC.replace("        code:\n          type: integer", "        resp.code:\n          type: integer");
+        let option = OpenApiImportOption { tags: vec!["user".to_string()], generate_asserts: true, ..Default::default() };
+        let result = parse_openapi_endpoints(&spec, &option).unwrap();
+        let get_user = result.endpoints.iter().find(|e| e.name == "getUser").unwrap();
+        let assert_option = &get_user.assert_options.as_ref().unwrap()[0];
+        assert_eq!(assert_option.jsonpath, "$['resp.code']");
+        // 括号形式按完整的属性名匹配，不会当成嵌套的路径
+        let body = serde_json::json!({"resp.code": 0, "resp": {"code": 1}});
+        assert_eq!(jsonpath_lib::select(&body, &assert_option.jsonpath).unwrap(), vec![&Value::from(0)]);
+        let body = serde_json::json!({"it's": 0});
+        assert_eq!(jsonpath_lib::select(&body, "$['it\\'s']").unwrap(), vec![&Value::from(0)]);
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+use std::path::Path;
+use serde_json::Value;
+
+use crate::core::import_names::unique_name;
+use crate::core::curl_import::basic_auth;
+use crate::models::api_endpoint::ApiEndpoint;
+use crate::models::engine_error::{localized, EngineError};
+use crate::models::import_result::ImportResult;
+
+// 从postman v2.1集合导入接口，接口名称带上文件夹路径
+pub fn load_postman_collection(path: impl AsRef<Path>) -> Result<ImportResult, EngineError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|e| EngineError::Io(path.display().to_string(), e))?;
+    parse_postman_collection(&source)
+}
+
+pub fn parse_postman_collection(source: &str) -> Result<ImportResult, EngineError> {
+    let collection: Value = serde_json::from_str(source).map_err(|e| EngineError::InvalidDocument("postman".to_string(), e.to_string()))?;
+    let schema = collection.pointer("/info/schema").and_then(|s| s.as_str()).unwrap_or_default();
+    if !schema.contains("v2.1") && !schema.contains("v2.0") {
+        return Err(EngineError::UnsupportedDocumentVersion("postman".to_string(), "v2.1".to_string(), schema.to_string()));
+    }
+    let mut importer = Importer { endpoints: Vec::new(), warnings: Vec::new(), names: HashMap::new() };
+    let scope = Scope {
+        path: Vec::new(),
+        variables: read_variables(&collection, &HashMap::new()),
+        auth: collection.get("auth").cloned(),
+    };
+    if has_scripts(&collection) {
+        importer.warnings.push(localized("集合的脚本不会执行", "collection scripts are not executed").to_string());
+    }
+    importer.import_items(collection.get("item"), &scope);
+    if importer.endpoints.is_empty() {
+        return Err(EngineError::NothingToImport("postman".to_string()));
+    }
+    Ok(ImportResult { endpoints: importer.endpoints, warnings: importer.warnings })
+}
+
+// 转换后的请求体(json, 表单)
+type RequestBody = (Option<Value>, Option<HashMap<String, String>>);
+
+// 文件夹的作用域，子文件夹继承变量和认证
+#[derive(Clone)]
+struct Scope {
+    path: Vec<String>,
+    variables: HashMap<String, String>,
+    auth: Option<Value>,
+}
+
+struct Importer {
+    endpoints: Vec<ApiEndpoint>,
+    warnings: Vec<String>,
+    names: HashMap<String, usize>,
+}
+
+impl Importer {
+    fn import_items(&mut self, items: Option<&Value>, scope: &Scope) {
+        for item in items.and_then(|i| i.as_array()).into_iter().flatten() {
+            let item_name = item.get("name").and_then(|n| n.as_str()).unwrap_or(localized("未命名", "unnamed")).to_string();
+            let mut item_scope = scope.clone();
+            item_scope.path.push(item_name);
+            item_scope.variables = read_variables(item, &scope.variables);
+            if let Some(auth) = item.get("auth") {
+                item_scope.auth = Some(auth.clone());
+            }
+            let full_name = item_scope.path.join("/");
+            if has_scripts(item) {
+                self.warnings.push(localized(
+                    format!("{} 的脚本不会执行", full_name),
+                    format!("{}: scripts are not executed", full_name),
+                ));
+            }
+            // 有item的是文件夹
+            if item.get("item").is_some() {
+                self.import_items(item.get("item"), &item_scope);
+                continue;
+            }
+            match item.get("request") {
+                Some(request) => {
+                    let name = unique_name(&mut self.names, full_name);
+                    match self.convert_request(&name, request, &item_scope) {
+                        Ok(endpoint) => self.endpoints.push(endpoint),
+                        Err(e) => self.warnings.push(localized(format!("{} 导入失败: {}", name, e), format!("{}: import failed: {}", name, e))),
+                    }
+                }
+                None => self.warnings.push(localized(format!("{} 没有请求，已忽略", full_name), format!("{}: no request, ignored", full_name))),
+            }
+        }
+    }
+
+    fn convert_request(&mut self, name: &str, request: &Value, scope: &Scope) -> Result<ApiEndpoint, EngineError> {
+        // request可以直接是url字符串
+        let (method, url) = match request {
+            Value::String(url) => ("GET".to_string(), url.clone()),
+            _ => (
+                request.get("method").and_then(|m| m.as_str()).unwrap_or("GET").to_uppercase(),
+                match request.get("url") {
+                    Some(Value::String(url)) => url.clone(),
+                    Some(url) => url.get("raw").and_then(|r| r.as_str()).unwrap_or_default().to_string(),
+                    None => String::new(),
+                },
+            ),
+        };
+        let url = self.substitute(name, &url, scope);
+        if url.is_empty() {
+            return Err(EngineError::MissingUrl);
+        }
+        // postman允许省略协议
+        let url = if url.starts_with("http://") || url.starts_with("https://") { url } else { format!("http://{}", url) };
+
+        let mut headers = HashMap::new();
+        let mut cookies = None;
+        for header in enabled(request.get("header")) {
+            let key = self.substitute(name, header.get("key").and_then(|k| k.as_str()).unwrap_or_default(), scope);
+            let value = self.substitute(name, header.get("value").and_then(|v| v.as_str()).unwrap_or_default(), scope);
+            if key.eq_ignore_ascii_case("cookie") {
+                cookies = Some(value);
+            } else if !key.is_empty() {
+                headers.insert(key, value);
+            }
+        }
+        // 请求上的认证优先于文件夹和集合上的认证
+        let auth = request.get("auth").cloned().or_else(|| scope.auth.clone());
+        if let Some(auth) = auth {
+            self.apply_auth(name, &auth, scope, &mut headers);
+        }
+
+        let (json, form_data) = match request.get("body") {
+            None => (None, None),
+            Some(body) => self.convert_body(name, body, scope)?,
+        };
+        Ok(ApiEndpoint {
+            name: name.to_string(),
+            url,
+            method,
+            json,
+            form_data,
+            headers: if headers.is_empty() { None } else { Some(headers) },
+            cookies,
+            ..Default::default()
+        })
+    }
+
+    // 只支持basic、bearer和请求头中的apikey
+    fn apply_auth(&mut self, name: &str, auth: &Value, scope: &Scope, headers: &mut HashMap<String, String>) {
+        let auth_type = auth.get("type").and_then(|t| t.as_str()).unwrap_or_default();
+        let param = |key: &str| -> Option<String> {
+            auth.get(auth_type)
+                .and_then(|params| params.as_array())
+                .and_then(|params| params.iter().find(|p| p.get("key").and_then(|k| k.as_str()) == Some(key)))
+                .and_then(|p| p.get("value"))
+                .map(value_to_string)
+        };
+        match auth_type {
+            "noauth" | "" => {}
+            "basic" => {
+                let username = self.substitute(name, &param("username").unwrap_or_default(), scope);
+                let password = self.substitute(name, &param("password").unwrap_or_default(), scope);
+                headers.insert("Authorization".to_string(), basic_auth(&username, &password));
+            }
+            "bearer" => {
+                let token = self.substitute(name, &param("token").unwrap_or_default(), scope);
+                headers.insert("Authorization".to_string(), format!("Bearer {}", token));
+            }
+            "apikey" if param("in").as_deref().unwrap_or("header") == "header" => {
+                let key = self.substitute(name, &param("key").unwrap_or_default(), scope);
+                let value = self.substitute(name, &param("value").unwrap_or_default(), scope);
+                headers.insert(key, value);
+            }
+            other => self.warnings.push(localized(
+                format!("{} 的认证方式{}不支持，已忽略", name, other),
+                format!("{}: unsupported auth type {}, ignored", name, other),
+            )),
+        }
+    }
+
+    fn convert_body(&mut self, name: &str, body: &Value, scope: &Scope) -> Result<RequestBody, EngineError> {
+        match body.get("mode").and_then(|m| m.as_str()).unwrap_or_default() {
+            "raw" => {
+                let raw = self.substitute(name, body.get("raw").and_then(|r| r.as_str()).unwrap_or_default(), scope);
+                if raw.trim().is_empty() {
+                    return Ok((None, None));
+                }
+                let language = body.pointer("/options/raw/language").and_then(|l| l.as_str()).unwrap_or_default();
+                match serde_json::from_str::<Value>(&raw) {
+                    Ok(json) => Ok((Some(json), None)),
+                    Err(e) if language == "json" => Err(EngineError::InvalidJson(e.to_string())),
+                    Err(_) => {
+                        self.warnings.push(localized(format!("{} 的请求体不是json，已忽略", name), format!("{}: body is not json, ignored", name)));
+                        Ok((None, None))
+                    }
+                }
+            }
+            mode @ ("urlencoded" | "formdata") => {
+                let mut form_data = HashMap::new();
+                for field in enabled(body.get(mode)) {
+                    if field.get("type").and_then(|t| t.as_str()) == Some("file") {
+                        self.warnings.push(localized(format!("{} 的文件字段已忽略", name), format!("{}: file fields are ignored", name)));
+                        continue;
+                    }
+                    let key = self.substitute(name, field.get("key").and_then(|k| k.as_str()).unwrap_or_default(), scope);
+                    let value = self.substitute(name, field.get("value").and_then(|v| v.as_str()).unwrap_or_default(), scope);
+                    form_data.insert(key, value);
+                }
+                Ok((None, if form_data.is_empty() { None } else { Some(form_data) }))
+            }
+            "graphql" => {
+                let query = self.substitute(name, body.pointer("/graphql/query").and_then(|q| q.as_str()).unwrap_or_default(), scope);
+                let variables = self.substitute(name, body.pointer("/graphql/variables").and_then(|v| v.as_str()).unwrap_or_default(), scope);
+                let variables: Value = if variables.trim().is_empty() {
+                    Value::Object(Default::default())
+                } else {
+                    serde_json::from_str(&variables).map_err(|e| EngineError::InvalidJson(format!("graphql variables: {}", e)))?
+                };
+                Ok((Some(serde_json::json!({"query": query, "variables": variables})), None))
+            }
+            "" => Ok((None, None)),
+            other => {
+                self.warnings.push(localized(
+                    format!("{} 的请求体类型{}不支持，已忽略", name, other),
+                    format!("{}: unsupported body mode {}, ignored", name, other),
+                ));
+                Ok((None, None))
+            }
+        }
+    }
+
+    // 替换{{变量}}，未定义的变量原样保留并记录警告
+    fn substitute(&mut self, name: &str, text: &str, scope: &Scope) -> String {
+        let mut result = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let end = match rest[start + 2..].find("}}") {
+                None => break,
+                Some(end) => start + 2 + end,
+            };
+            result.push_str(&rest[..start]);
+            let variable = rest[start + 2..end].trim();
+            match scope.variables.get(variable) {
+                Some(value) => result.push_str(value),
+                None => {
+                    let warning = localized(
+                        format!("{} 使用了未定义的变量{}", name, variable),
+                        format!("{}: undefined variable {}", name, variable),
+                    );
+                    if !self.warnings.contains(&warning) {
+                        self.warnings.push(warning);
+                    }
+                    result.push_str(&rest[start..end + 2]);
+                }
+            }
+            rest = &rest[end + 2..];
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+// 读取当前层级的变量，覆盖上层的同名变量
+fn read_variables(item: &Value, parent: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut variables = parent.clone();
+    for variable in enabled(item.get("variable")) {
+        if let Some(key) = variable.get("key").and_then(|k| k.as_str()) {
+            variables.insert(key.to_string(), variable.get("value").map(value_to_string).unwrap_or_default());
+        }
+    }
+    variables
+}
+
+// 过滤掉被禁用的项
+fn enabled(list: Option<&Value>) -> impl Iterator<Item = &Value> {
+    list.and_then(|l| l.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|v| !v.get("disabled").and_then(|d| d.as_bool()).unwrap_or(false))
+}
+
+fn has_scripts(item: &Value) -> bool {
+    item.get("event").and_then(|e| e.as_array()).map(|e| !e.is_empty()).unwrap_or(false)
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::engine_error::{set_language, Language};
+
+    const COLLECTION: &str = r#"{
+        "info": {"name": "demo", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"},
+        "variable": [{"key": "host", "value": "https://api.example.com"}, {"key": "token", "value": "abc"}],
+        "auth": {"type": "bearer", "bearer": [{"key": "token", "value": "{{token}}", "type": "string"}]},
+        "item": [
+            {"name": "user", "variable": [{"key": "id", "value": 7}], "item": [
+                {"name": "get", "request": {"method": "GET", "url": {"raw": "{{host}}/users/{{id}}"},
+                 "header": [{"key": "X-Trace", "value": "1", "disabled": true}, {"key": "Cookie", "value": "sid=1"}]}},
+                {"name": "create", "request": {"method": "POST", "url": "{{host}}/users",
+                 "body": {"mode": "raw", "raw": "{\"name\": \"{{name}}\"}", "options": {"raw": {"language": "json"}}}}}
+            ]},
+            {"name": "login", "request": {"method": "POST", "url": "{{host}}/login", "auth": {"type": "noauth"},
+             "body": {"mode": "urlencoded", "urlencoded": [{"key": "user", "value": "test"}]}}}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_postman_collection() {
+        set_language(Language::Chinese);
+        let result = parse_postman_collection(COLLECTION).unwrap();
+        assert_eq!(result.endpoints.len(), 3);
+
+        let get_user = &result.endpoints[0];
+        assert_eq!(get_user.name, "user/get");
+        assert_eq!(get_user.url, "https://api.example.com/users/7");
+        assert_eq!(get_user.cookies.as_deref(), Some("sid=1"));
+        let headers = get_user.headers.as_ref().unwrap();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["Authorization"], "Bearer abc");
+
+        // 未定义的变量原样保留
+        let create_user = &result.endpoints[1];
+        assert_eq!(create_user.json, Some(serde_json::json!({"name": "{{name}}"})));
+        assert_eq!(result.warnings, vec!["user/create 使用了未定义的变量name".to_string()]);
+
+        let login = &result.endpoints[2];
+        assert_eq!(login.headers, None);
+        assert_eq!(login.form_data.as_ref().unwrap()["user"], "test");
+    }
+}
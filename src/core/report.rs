@@ -0,0 +1,46 @@
+use std::path::Path;
+use crate::models::engine_error::EngineError;
+use crate::models::result::BatchResult;
+
+// 将结果保存为json报告
+pub fn save_json_report(result: &BatchResult, path: impl AsRef<Path>) -> Result<(), EngineError> {
+    let path = path.as_ref();
+    let content = serde_json::to_vec_pretty(result).map_err(|e| EngineError::InvalidDocument(path.display().to_string(), e.to_string()))?;
+    std::fs::write(path, content).map_err(|e| EngineError::Io(path.display().to_string(), e))
+}
+
+// 读取保存的json报告
+pub fn load_json_report(path: impl AsRef<Path>) -> Result<BatchResult, EngineError> {
+    let path = path.as_ref();
+    let content = std::fs::read(path).map_err(|e| EngineError::Io(path.display().to_string(), e))?;
+    serde_json::from_slice(&content).map_err(|e| EngineError::InvalidDocument(path.display().to_string(), e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::batch::batch;
+    use crate::core::test_server::spawn_silent_server;
+    use crate::models::api_endpoint::ApiEndpoint;
+    use crate::models::batch_option::BatchOption;
+
+    #[tokio::test]
+    async fn test_report_round_trip_without_requests() {
+        let addr = spawn_silent_server().await;
+        let endpoint = ApiEndpoint { name: "idle".to_string(), url: format!("http://{}/", addr), ..Default::default() };
+        // 服务一直不响应，请求全部中断，结果和接口都没有完成的请求
+        let batch_option = BatchOption { graceful_stop_secs: Some(1), ..Default::default() };
+        let result = batch(1, 1, false, false, vec![endpoint], None, Some(batch_option)).await.unwrap();
+        assert_eq!(result.total_requests, 0);
+        assert_eq!(result.success_rate, 0.0);
+        assert_eq!(result.api_results[0].total_requests, 0);
+
+        let path = std::env::temp_dir().join(format!("atomic-bomb-report-{}.json", std::process::id()));
+        save_json_report(&result, &path).unwrap();
+        let loaded = load_json_report(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.total_requests, 0);
+        assert_eq!(loaded.error_rate, 0.0);
+        assert_eq!(loaded.api_results[0].success_rate, 0.0);
+    }
+}
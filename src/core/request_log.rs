@@ -0,0 +1,322 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use anyhow::Context;
+use serde_json::{json, Value};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::models::engine_error::EngineError;
+use crate::models::request_log_option::RequestLogOption;
+use crate::models::request_record::RequestRecord;
+
+// 等待写入的记录上限，写入跟不上时丢弃新的记录，不占用越来越多的内存
+const CHANNEL_CAPACITY: usize = 8192;
+
+// 单请求日志，worker把记录发到channel，由后台任务统一写入文件
+pub(crate) struct RequestLogger {
+    option: RequestLogOption,
+    sender: mpsc::Sender<Option<RequestRecord>>,
+    // 参与采样的请求数
+    seen: AtomicU64,
+    // 写入跟不上被丢弃的记录数
+    dropped: AtomicU64,
+    writer: Mutex<Option<JoinHandle<anyhow::Result<()>>>>,
+}
+
+impl RequestLogger {
+    pub(crate) async fn new(option: RequestLogOption) -> Result<Self, EngineError> {
+        if !(option.sample_rate > 0.0 && option.sample_rate <= 1.0) {
+            return Err(EngineError::InvalidSampleRate(option.sample_rate));
+        }
+        let file = File::create(&option.path)
+            .await
+            .map_err(|e| EngineError::Io(option.path.clone(), e))?;
+        // HAR文件边压测边写入，不在内存中保留所有记录
+        let har_file = match &option.har_path {
+            None => None,
+            Some(har_path) => Some(BufWriter::new(File::create(har_path).await.map_err(|e| EngineError::Io(har_path.clone(), e))?)),
+        };
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let writer = tokio::spawn(write_records(BufWriter::new(file), receiver, har_file));
+        Ok(RequestLogger {
+            option,
+            sender,
+            seen: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            writer: Mutex::new(Some(writer)),
+        })
+    }
+
+    // 是否需要采集请求头和请求体等详情
+    pub(crate) fn capture_detail(&self) -> bool {
+        self.option.har_path.is_some()
+    }
+
+    // 只记录失败请求，请求结束知道结果后再采样和创建记录
+    pub(crate) fn errors_only(&self) -> bool {
+        self.option.errors_only
+    }
+
+    // 是否需要创建记录，按固定比例均匀采样，不依赖随机数；
+    // 只记录失败请求时只对失败的请求调用
+    pub(crate) fn sample(&self) -> bool {
+        let n = self.seen.fetch_add(1, Ordering::Relaxed) as f64;
+        ((n + 1.0) * self.option.sample_rate).floor() > (n * self.option.sample_rate).floor()
+    }
+
+    // 写入sample返回true后创建的记录，等待写入的记录已满时丢弃
+    pub(crate) fn record(&self, record: RequestRecord) {
+        if let Err(mpsc::error::TrySendError::Full(_)) = self.sender.try_send(Some(record)) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // 写入跟不上被丢弃的记录数
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    // 写完剩余记录，并导出HAR
+    pub(crate) async fn finish(&self) -> anyhow::Result<()> {
+        let _ = self.sender.send(None).await;
+        match self.writer.lock().await.take() {
+            None => Ok(()),
+            Some(handle) => handle.await.context("请求日志任务异常退出")?,
+        }
+    }
+}
+
+async fn write_records(
+    mut file: BufWriter<File>,
+    mut receiver: mpsc::Receiver<Option<RequestRecord>>,
+    mut har_file: Option<BufWriter<File>>,
+) -> anyhow::Result<()> {
+    // HAR文档的entries逐条追加，结束时补上结尾
+    let (har_head, har_tail) = har_document_parts();
+    if let Some(har_file) = har_file.as_mut() {
+        har_file.write_all(har_head.as_bytes()).await.context("写入HAR文件失败")?;
+    }
+    let mut har_entries = 0usize;
+    while let Some(Some(record)) = receiver.recv().await {
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        file.write_all(line.as_bytes()).await.context("写入请求日志失败")?;
+        if let Some(har_file) = har_file.as_mut() {
+            let mut entry = if har_entries == 0 { String::new() } else { ",".to_string() };
+            entry.push_str(&serde_json::to_string(&har_entry(&record))?);
+            har_file.write_all(entry.as_bytes()).await.context("写入HAR文件失败")?;
+            har_entries += 1;
+        }
+    }
+    file.flush().await.context("写入请求日志失败")?;
+    if let Some(mut har_file) = har_file {
+        har_file.write_all(har_tail.as_bytes()).await.context("写入HAR文件失败")?;
+        har_file.flush().await.context("写入HAR文件失败")?;
+    }
+    Ok(())
+}
+
+// HAR文档中entries之前和之后的部分
+fn har_document_parts() -> (String, String) {
+    let head = format!(r#"{{"log":{{"version":"1.2","creator":{},"entries":["#, har_creator());
+    (head, "]}}".to_string())
+}
+
+// 将请求记录导出为HAR文件
+pub fn export_har(records: &[RequestRecord]) -> Value {
+    har_document(records.iter().map(har_entry).collect())
+}
+
+fn har_document(entries: Vec<Value>) -> Value {
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": har_creator(),
+            "entries": entries,
+        }
+    })
+}
+
+fn har_creator() -> Value {
+    json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+    })
+}
+
+fn har_entry(record: &RequestRecord) -> Value {
+    let detail = record.detail.clone().unwrap_or_default();
+    let started = OffsetDateTime::from_unix_timestamp_nanos(record.timestamp as i128 * 1_000_000)
+        .ok()
+        .and_then(|t| t.format(&Rfc3339).ok())
+        .unwrap_or_default();
+    let http_version = if detail.http_version.is_empty() { "HTTP/1.1".to_string() } else { detail.http_version.clone() };
+    let query_string: Vec<Value> = reqwest::Url::parse(&record.url)
+        .map(|url| url.query_pairs().map(|(k, v)| json!({"name": k, "value": v})).collect())
+        .unwrap_or_default();
+    let mut request = json!({
+        "method": record.method,
+        "url": record.url,
+        "httpVersion": http_version,
+        "cookies": [],
+        "headers": har_headers(&detail.request_headers),
+        "queryString": query_string,
+        "headersSize": -1,
+        "bodySize": detail.request_body.as_ref().map(|b| b.len() as i64).unwrap_or(0),
+    });
+    if let Some(body) = &detail.request_body {
+        request["postData"] = json!({
+            "mimeType": detail.request_content_type.clone().unwrap_or_default(),
+            "text": body,
+        });
+    }
+    let mut content = json!({
+        "size": record.bytes,
+        "mimeType": detail.response_content_type.clone().unwrap_or_default(),
+    });
+    if let Some(body) = &detail.response_body {
+        content["text"] = json!(body);
+    }
+    let mut entry = json!({
+        "startedDateTime": started,
+        "time": record.latency_ms,
+        "request": request,
+        "response": {
+            "status": record.status.unwrap_or(0),
+            "statusText": record.status
+                .and_then(|s| reqwest::StatusCode::from_u16(s).ok())
+                .and_then(|s| s.canonical_reason())
+                .unwrap_or(""),
+            "httpVersion": http_version,
+            "cookies": [],
+            "headers": har_headers(&detail.response_headers),
+            "content": content,
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": record.bytes,
+        },
+        "cache": {},
+        "timings": {
+            "send": 0,
+            "wait": record.latency_ms,
+            "receive": 0,
+        },
+    });
+    let mut comments = Vec::new();
+    if let Some(message) = &record.error_message {
+        comments.push(message.clone());
+    }
+    comments.extend(record.assertion_failures.iter().cloned());
+    if !comments.is_empty() {
+        entry["comment"] = json!(comments.join("; "));
+    }
+    entry
+}
+
+fn har_headers(headers: &[(String, String)]) -> Vec<Value> {
+    headers.iter().map(|(k, v)| json!({"name": k, "value": v})).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::error_kind::ErrorKind;
+    use crate::models::request_record::RequestDetail;
+
+    fn record(index: u128, status: u16) -> RequestRecord {
+        RequestRecord {
+            timestamp: 1_700_000_000_000 + index,
+            name: "login".to_string(),
+            url: "http://127.0.0.1/login?from=app".to_string(),
+            method: "POST".to_string(),
+            status: Some(status),
+            latency_ms: 12,
+            bytes: 2,
+            error_kind: if status >= 400 { Some(ErrorKind::HttpStatus) } else { None },
+            error_message: None,
+            assertion_failures: Vec::new(),
+            http_version: None,
+            detail: Some(RequestDetail {
+                request_headers: vec![("content-type".to_string(), "application/json".to_string())],
+                request_body: Some("{\"user\":\"a\"}".to_string()),
+                request_content_type: Some("application/json".to_string()),
+                http_version: "HTTP/1.1".to_string(),
+                response_headers: Vec::new(),
+                response_body: Some("{}".to_string()),
+                response_content_type: Some("application/json".to_string()),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_log_sampling_and_har() {
+        let dir = std::env::temp_dir().join(format!("atomic-bomb-request-log-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("requests.jsonl");
+        let har_path = dir.join("requests.har");
+        let logger = RequestLogger::new(RequestLogOption {
+            path: path.to_string_lossy().to_string(),
+            sample_rate: 0.5,
+            errors_only: false,
+            har_path: Some(har_path.to_string_lossy().to_string()),
+        }).await.unwrap();
+        for i in 0..10 {
+            if logger.sample() {
+                logger.record(record(i, if i % 3 == 0 { 500 } else { 200 }));
+            }
+        }
+        logger.finish().await.unwrap();
+        let lines = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(lines.lines().count(), 5);
+        let first: Value = serde_json::from_str(lines.lines().next().unwrap()).unwrap();
+        assert_eq!(first["name"], "login");
+        let har: Value = serde_json::from_slice(&std::fs::read(&har_path).unwrap()).unwrap();
+        let entries = har["log"]["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0]["request"]["postData"]["text"], "{\"user\":\"a\"}");
+        assert_eq!(entries[0]["request"]["queryString"][0]["name"], "from");
+        // 请求详情写入了请求日志，读回后导出的HAR与压测时写入的一致
+        let records: Vec<RequestRecord> = lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(export_har(&records)["log"]["entries"], har["log"]["entries"]);
+
+        // 只记录失败请求时在失败请求中采样
+        let logger = RequestLogger::new(RequestLogOption {
+            path: path.to_string_lossy().to_string(),
+            sample_rate: 0.5,
+            errors_only: true,
+            har_path: None,
+        }).await.unwrap();
+        assert!(logger.errors_only());
+        for i in 0..12 {
+            let record = record(i, if i % 3 == 0 { 500 } else { 200 });
+            if record.is_error() && logger.sample() {
+                logger.record(record);
+            }
+        }
+        logger.finish().await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_request_log_dropped() {
+        let path = std::env::temp_dir().join(format!("atomic-bomb-request-log-dropped-{}.jsonl", std::process::id()));
+        let logger = RequestLogger::new(RequestLogOption {
+            path: path.to_string_lossy().to_string(),
+            sample_rate: 1.0,
+            errors_only: false,
+            har_path: None,
+        }).await.unwrap();
+        // 单线程运行时写入任务还没有执行，超出上限的记录被丢弃
+        for i in 0..CHANNEL_CAPACITY as u128 + 5 {
+            logger.record(record(i, 200));
+        }
+        assert_eq!(logger.dropped(), 5);
+        logger.finish().await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), CHANNEL_CAPACITY);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,5 +1,7 @@
 use lazy_static::lazy_static;
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 use crate::models;
 use tokio::sync::Mutex;
 use std::sync::Arc;
@@ -11,3 +13,46 @@ lazy_static! {
     pub static ref RESULTS_QUEUE: Arc<Mutex<VecDeque<models::result::BatchResult>>> = Arc::new(Mutex::new(VecDeque::new()));
     pub static ref RESULTS_SHOULD_STOP: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
 }
+
+// 最近一次压测结束的时间(毫秒时间戳)，压测在产生周期结果之前失败时，迭代周期结果也能知道压测已经结束
+pub static SINGLE_STOPPED_AT: AtomicU64 = AtomicU64::new(0);
+pub static RESULTS_STOPPED_AT: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn now_millis() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(n) => n.as_millis() as u64,
+        Err(_) => 0,
+    }
+}
+
+// 被丢弃时设置停止标志和结束时间，压测出错返回或者被取消时也会生效
+pub(crate) struct StopGuard {
+    should_stop: &'static Mutex<bool>,
+    stopped_at: &'static AtomicU64,
+}
+
+impl StopGuard {
+    pub(crate) fn single() -> Self {
+        StopGuard { should_stop: &SINGLE_SHOULD_STOP, stopped_at: &SINGLE_STOPPED_AT }
+    }
+
+    pub(crate) fn batch() -> Self {
+        StopGuard { should_stop: &RESULTS_SHOULD_STOP, stopped_at: &RESULTS_STOPPED_AT }
+    }
+}
+
+impl Drop for StopGuard {
+    fn drop(&mut self) {
+        match self.should_stop.try_lock() {
+            Ok(mut should_stop) => *should_stop = true,
+            // 锁被占用时交给运行时设置
+            Err(_) => {
+                let should_stop = self.should_stop;
+                if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                    handle.spawn(async move { *should_stop.lock().await = true });
+                }
+            }
+        }
+        self.stopped_at.store(now_millis(), Ordering::Relaxed);
+    }
+}
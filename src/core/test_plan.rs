@@ -0,0 +1,242 @@
+use std::path::Path;
+
+use crate::core::batch::batch;
+use crate::core::threshold::{evaluate_thresholds, THRESHOLD_METRICS};
+use crate::core::validate::{validate_endpoints, validate_options};
+use crate::models::engine_error::EngineError;
+use crate::models::result::BatchResult;
+use crate::models::test_plan::TestPlan;
+use crate::models::threshold::ThresholdFailure;
+
+// 测试计划文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanFormat {
+    Yaml,
+    Toml,
+}
+
+impl PlanFormat {
+    // 根据扩展名判断格式
+    pub fn from_path(path: &Path) -> Result<Self, EngineError> {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+            Some("yaml") | Some("yml") => Ok(PlanFormat::Yaml),
+            Some("toml") => Ok(PlanFormat::Toml),
+            _ => Err(EngineError::UnsupportedPlanFormat(path.display().to_string())),
+        }
+    }
+}
+
+// 从文件加载并校验测试计划
+pub fn load_test_plan(path: impl AsRef<Path>) -> Result<TestPlan, EngineError> {
+    let path = path.as_ref();
+    let format = PlanFormat::from_path(path)?;
+    let source = std::fs::read_to_string(path).map_err(|e| EngineError::Io(path.display().to_string(), e))?;
+    parse_test_plan(&source, format, &path.display().to_string())
+}
+
+// 解析并校验测试计划，错误信息带有行号，file_name只用于错误信息
+pub fn parse_test_plan(source: &str, format: PlanFormat, file_name: &str) -> Result<TestPlan, EngineError> {
+    let plan: TestPlan = match format {
+        PlanFormat::Yaml => serde_yaml::from_str(source).map_err(|e| match e.location() {
+            Some(location) => {
+                // 去掉错误信息末尾重复的位置
+                let message = e.to_string();
+                let message = message.rsplit_once(" at line ").map(|(m, _)| m.to_string()).unwrap_or(message);
+                EngineError::InvalidPlan(format!("{}:{}:{}", file_name, location.line(), location.column()), message)
+            }
+            None => EngineError::InvalidPlan(file_name.to_string(), e.to_string()),
+        })?,
+        PlanFormat::Toml => toml::from_str(source).map_err(|e| match e.span() {
+            Some(span) => {
+                let (line, column) = line_column(source, span.start);
+                EngineError::InvalidPlan(format!("{}:{}:{}", file_name, line, column), e.message().to_string())
+            }
+            None => EngineError::InvalidPlan(file_name.to_string(), e.message().to_string()),
+        })?,
+    };
+    let problems: Vec<EngineError> = validate_test_plan(&plan, source)
+        .into_iter()
+        .map(|(line, problem)| {
+            let location = match line {
+                Some(line) => format!("{}:{}", file_name, line),
+                None => file_name.to_string(),
+            };
+            EngineError::Located(location, Box::new(problem))
+        })
+        .collect();
+    if !problems.is_empty() {
+        return Err(EngineError::Validation(problems));
+    }
+    Ok(plan)
+}
+
+// 按计划执行压测，并检查阈值
+pub async fn run_test_plan(plan: TestPlan) -> Result<(BatchResult, Vec<ThresholdFailure>), EngineError> {
+    let thresholds = plan.thresholds.clone();
+    let result = batch(
+        plan.test_duration_secs,
+        plan.concurrent_requests,
+        plan.verbose,
+        plan.should_prevent,
+        plan.api_endpoints,
+        plan.step_option,
+        plan.batch_option,
+    ).await?;
+    let failures = evaluate_thresholds(&result, &thresholds);
+    Ok((result, failures))
+}
+
+// 按batch的规则校验计划内容，再校验阈值，返回全部问题(行号, 错误)
+fn validate_test_plan(plan: &TestPlan, source: &str) -> Vec<(Option<usize>, EngineError)> {
+    let mut problems: Vec<(Option<usize>, EngineError)> = validate_options(
+        plan.test_duration_secs,
+        plan.concurrent_requests,
+        &plan.api_endpoints,
+        plan.step_option.as_ref(),
+        plan.batch_option.as_ref(),
+    )
+    .into_iter()
+    .map(|problem| {
+        let line = match &problem {
+            EngineError::ZeroValue(field) => find_key_line(source, field, None, 0),
+            EngineError::InvalidProxy(url, _) => find_key_line(source, "url", Some(url), 0),
+            _ => None,
+        };
+        (line, problem)
+    })
+    .collect();
+    for (index, problem) in validate_endpoints(&plan.api_endpoints) {
+        // 同名接口按出现顺序定位
+        let name = &plan.api_endpoints[index].name;
+        let occurrence = plan.api_endpoints[..index].iter().filter(|e| &e.name == name).count();
+        problems.push((find_key_line(source, "name", Some(name), occurrence), problem));
+    }
+    for (index, threshold) in plan.thresholds.iter().enumerate() {
+        let line = find_key_line(source, "metric", None, index);
+        if !THRESHOLD_METRICS.contains(&threshold.metric.as_str()) {
+            problems.push((line, EngineError::UnknownThresholdMetric(threshold.metric.clone())));
+        }
+        if threshold.max.is_none() && threshold.min.is_none() {
+            problems.push((line, EngineError::ThresholdWithoutBound(threshold.metric.clone())));
+        }
+        if let Some(endpoint) = &threshold.endpoint {
+            if !plan.api_endpoints.iter().any(|e| &e.name == endpoint) {
+                problems.push((line, EngineError::UnknownThresholdEndpoint(threshold.metric.clone(), endpoint.clone())));
+            }
+        }
+    }
+    problems
+}
+
+// 查找第occurrence次出现key的行号(从1开始)，value不为空时还要求值相等
+// yaml(key: value)和toml(key = value)都适用
+fn find_key_line(source: &str, key: &str, value: Option<&str>, occurrence: usize) -> Option<usize> {
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim_start().trim_start_matches('-').trim_start();
+            let rest = match line.strip_prefix(key) {
+                None => return false,
+                Some(rest) => rest.trim_start(),
+            };
+            let rest = match rest.strip_prefix(':').or_else(|| rest.strip_prefix('=')) {
+                None => return false,
+                Some(rest) => rest.trim(),
+            };
+            match value {
+                None => true,
+                Some(value) => rest.trim_matches(|c| c == '"' || c == '\'') == value,
+            }
+        })
+        .nth(occurrence)
+        .map(|(index, _)| index + 1)
+}
+
+// 字节偏移转换为行列号(从1开始)
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map(|s| s.chars().count()).unwrap_or(0) + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::engine_error::{set_language, Language};
+
+    const YAML_PLAN: &str = r#"
+test_duration_secs: 10
+concurrent_requests: 20
+step_option:
+  increase_step: 5
+  increase_interval: 2
+endpoints:
+  - name: login
+    url: http://127.0.0.1:8080/login
+    method: POST
+    json:
+      user: test
+    assert_options:
+      - jsonpath: $.code
+        reference_object: 200
+  - name: list
+    url: http://127.0.0.1:8080/list
+    weight: 3
+    headers:
+      x-token: abc
+    cookies: session=1
+thresholds:
+  - metric: response_time_95
+    max: 500
+  - metric: error_rate
+    endpoint: login
+    max: 1
+"#;
+
+    #[test]
+    fn test_parse_test_plan() {
+        set_language(Language::Chinese);
+        let plan = parse_test_plan(YAML_PLAN, PlanFormat::Yaml, "plan.yaml").unwrap();
+        assert_eq!(plan.api_endpoints.len(), 2);
+        assert_eq!(plan.api_endpoints[1].method, "GET");
+        assert_eq!(plan.api_endpoints[1].weight, 3);
+        assert_eq!(plan.thresholds.len(), 2);
+
+        let toml_plan = r#"
+test_duration_secs = 10
+concurrent_requests = 20
+
+[[endpoints]]
+name = "login"
+url = "http://127.0.0.1:8080/login"
+form_data = { user = "test" }
+"#;
+        let plan = parse_test_plan(toml_plan, PlanFormat::Toml, "plan.toml").unwrap();
+        assert_eq!(plan.api_endpoints[0].form_data.as_ref().unwrap()["user"], "test");
+
+        // 语法和类型错误带有行号
+        let err = parse_test_plan("test_duration_secs = \"ten\"\n", PlanFormat::Toml, "plan.toml").err().unwrap();
+        assert!(err.to_string().starts_with("plan.toml:1:"), "{}", err);
+        let err = parse_test_plan(&YAML_PLAN.replace("weight: 3", "weigth: 3"), PlanFormat::Yaml, "plan.yaml").err().unwrap();
+        assert!(err.to_string().starts_with("plan.yaml:18:"), "{}", err);
+
+        // 校验错误一次性全部返回
+        let invalid = YAML_PLAN.replace("name: list", "name: login").replace("metric: error_rate", "metric: p95").replace("method: POST", "method: P OST");
+        let err = parse_test_plan(&invalid, PlanFormat::Yaml, "plan.yaml").err().unwrap().to_string();
+        assert!(err.contains("plan.yaml:16: 重复的name: login"), "{}", err);
+        assert!(err.contains("plan.yaml:8: 接口 login: 无效的请求方法: P OST"), "{}", err);
+        assert!(err.contains("plan.yaml:25: 未知的阈值指标: p95"), "{}", err);
+
+        // batch_option和batch使用同样的规则校验
+        let invalid = YAML_PLAN.replace("thresholds:", "batch_option:\n  pacing_ms: 0\n  proxies:\n    - url: ftp://proxy\n  request_log:\n    path: requests.jsonl\n    sample_rate: 2\nthresholds:");
+        let err = parse_test_plan(&invalid, PlanFormat::Yaml, "plan.yaml").err().unwrap();
+        let EngineError::Validation(problems) = &err else { panic!("{}", err) };
+        assert_eq!(problems.len(), 3, "{}", err);
+        let err = err.message(Language::English);
+        assert!(err.contains("plan.yaml:23: pacing_ms must be greater than 0"), "{}", err);
+        assert!(err.contains("plan.yaml:25: invalid proxy ftp://proxy"), "{}", err);
+        assert!(err.contains("plan.yaml: sample rate must be in (0, 1]: 2"), "{}", err);
+    }
+}
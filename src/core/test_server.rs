@@ -0,0 +1,228 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use hyper::service::service_fn;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+// 单测用的本地http服务，收到的每个请求(原始报文)都会发送到返回的channel里
+pub(crate) async fn spawn_http_server(status: u16, body: &'static str) -> (SocketAddr, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("绑定本地端口失败");
+    let addr = listener.local_addr().expect("获取本地地址失败");
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let _ = serve_connection(stream, status, body, tx).await;
+            });
+        }
+    });
+    (addr, rx)
+}
+
+// 单测用的本地http服务，每个连接的对端地址都会发送到返回的channel里
+pub(crate) async fn spawn_peer_recording_server(body: &'static str) -> (SocketAddr, mpsc::UnboundedReceiver<SocketAddr>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("绑定本地端口失败");
+    let addr = listener.local_addr().expect("获取本地地址失败");
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Ok((stream, peer)) = listener.accept().await {
+            let _ = tx.send(peer);
+            // 不需要请求报文
+            let (request_tx, _) = mpsc::unbounded_channel();
+            tokio::spawn(async move {
+                let _ = serve_connection(stream, 200, body, request_tx).await;
+            });
+        }
+    });
+    (addr, rx)
+}
+
+// 单测用的本地服务，接受连接后一直不响应
+pub(crate) async fn spawn_silent_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("绑定本地端口失败");
+    let addr = listener.local_addr().expect("获取本地地址失败");
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            connections.push(stream);
+        }
+    });
+    addr
+}
+
+// 单测用的本地服务，返回响应头和一部分响应体后一直不再发送
+pub(crate) async fn spawn_stalled_body_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("绑定本地端口失败");
+    let addr = listener.local_addr().expect("获取本地地址失败");
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\npartial").await;
+            connections.push(stream);
+        }
+    });
+    addr
+}
+
+// 单测用的socks5代理，要求用户名密码认证，所有连接都转发到upstream
+// 每个连接的"用户名:密码@目标地址:端口"会发送到返回的channel里
+pub(crate) async fn spawn_socks5_server(upstream: SocketAddr) -> (SocketAddr, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("绑定本地端口失败");
+    let addr = listener.local_addr().expect("获取本地地址失败");
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let _ = serve_socks5(stream, upstream, tx).await;
+            });
+        }
+    });
+    (addr, rx)
+}
+
+async fn serve_socks5(mut stream: tokio::net::TcpStream, upstream: SocketAddr, tx: mpsc::UnboundedSender<String>) -> std::io::Result<()> {
+    // 协商认证方式，只接受用户名密码认证
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head).await?;
+    let mut methods = vec![0u8; head[1] as usize];
+    stream.read_exact(&mut methods).await?;
+    if !methods.contains(&2) {
+        return stream.write_all(&[5, 0xff]).await;
+    }
+    stream.write_all(&[5, 2]).await?;
+    let username = read_socks5_field(&mut stream, 1).await?;
+    let password = read_socks5_field(&mut stream, 0).await?;
+    stream.write_all(&[1, 0]).await?;
+    // 连接请求，目标地址可能是ipv4、域名或ipv6
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await?;
+    let host = match request[3] {
+        1 => {
+            let mut ip = [0u8; 4];
+            stream.read_exact(&mut ip).await?;
+            std::net::Ipv4Addr::from(ip).to_string()
+        }
+        3 => read_socks5_field(&mut stream, 0).await?,
+        _ => {
+            let mut ip = [0u8; 16];
+            stream.read_exact(&mut ip).await?;
+            format!("[{}]", std::net::Ipv6Addr::from(ip))
+        }
+    };
+    let port = stream.read_u16().await?;
+    let _ = tx.send(format!("{}:{}@{}:{}", username, password, host, port));
+    let mut upstream = tokio::net::TcpStream::connect(upstream).await?;
+    stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).await?;
+    tokio::io::copy_bidirectional(&mut stream, &mut upstream).await?;
+    Ok(())
+}
+
+// 读取socks5报文中"长度+内容"形式的字段，skip为长度前需要跳过的字节数
+async fn read_socks5_field(stream: &mut tokio::net::TcpStream, skip: usize) -> std::io::Result<String> {
+    let mut prefix = vec![0u8; skip + 1];
+    stream.read_exact(&mut prefix).await?;
+    let mut field = vec![0u8; prefix[skip] as usize];
+    stream.read_exact(&mut field).await?;
+    Ok(String::from_utf8_lossy(&field).to_string())
+}
+
+// 单测用的本地服务，接受连接后不等待请求直接返回明文http响应，https请求会握手失败
+pub(crate) async fn spawn_plain_reply_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("绑定本地端口失败");
+    let addr = listener.local_addr().expect("获取本地地址失败");
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\n\r\n").await;
+        }
+    });
+    addr
+}
+
+// 单测用的unix socket http服务，收到的每个请求(原始报文)都会发送到返回的channel里
+#[cfg(unix)]
+pub(crate) async fn spawn_unix_http_server(path: &std::path::Path, status: u16, body: &'static str) -> mpsc::UnboundedReceiver<String> {
+    let _ = std::fs::remove_file(path);
+    let listener = tokio::net::UnixListener::bind(path).expect("绑定unix socket失败");
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let _ = serve_connection(stream, status, body, tx).await;
+            });
+        }
+    });
+    rx
+}
+
+// 在一个连接上循环处理请求(keep-alive)
+async fn serve_connection<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, status: u16, body: &str, tx: mpsc::UnboundedSender<String>) -> std::io::Result<()> {
+    let mut buffer: Vec<u8> = Vec::new();
+    loop {
+        // 读取请求头
+        let header_end = loop {
+            if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            let mut chunk = [0u8; 4096];
+            let n = stream.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            buffer.extend_from_slice(&chunk[..n]);
+        };
+        let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+        let content_length = head
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(k, _)| k.trim().eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        // 读取请求体
+        while buffer.len() < header_end + content_length {
+            let mut chunk = [0u8; 4096];
+            let n = stream.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            buffer.extend_from_slice(&chunk[..n]);
+        }
+        let request: Vec<u8> = buffer.drain(..header_end + content_length).collect();
+        let _ = tx.send(String::from_utf8_lossy(&request).to_string());
+        let response = format!(
+            "HTTP/1.1 {} OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await?;
+    }
+}
+
+// 单测用的h2c服务(不协商直接使用HTTP/2)，返回连接数计数
+pub(crate) async fn spawn_h2c_server(body: &'static str) -> (SocketAddr, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("绑定本地端口失败");
+    let addr = listener.local_addr().expect("获取本地地址失败");
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = connections.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            counter.fetch_add(1, Ordering::Relaxed);
+            tokio::spawn(async move {
+                let service = service_fn(|_request| async move { Ok::<_, Infallible>(hyper::Response::new(body.to_string())) });
+                let _ = hyper::server::conn::http2::Builder::new(TokioExecutor::new())
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await;
+            });
+        }
+    });
+    (addr, connections)
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use crate::core::fast_rng::FastRng;
+use crate::models::think_time::ThinkTime;
+
+// 单次等待时间的上限，超过压测时长的等待都会在截止时间结束，上限保证计算截止时刻时不会溢出
+const MAX_THINK_TIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl ThinkTime {
+    // 按分布生成一次等待时间
+    pub(crate) fn sample(&self, rng: &mut FastRng) -> Duration {
+        let ms = match *self {
+            ThinkTime::Fixed { ms } => ms as f64,
+            ThinkTime::Uniform { min_ms, max_ms } => min_ms as f64 + rng.next_f64() * max_ms.saturating_sub(min_ms) as f64,
+            // Box-Muller变换
+            ThinkTime::Normal { mean_ms, std_dev_ms } => {
+                let u1 = 1.0 - rng.next_f64();
+                let u2 = rng.next_f64();
+                mean_ms + std_dev_ms * (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+            }
+            ThinkTime::Exponential { mean_ms } => -mean_ms * (1.0 - rng.next_f64()).ln(),
+        };
+        // 极大的配置值或计算出的NaN不能直接转换
+        Duration::try_from_secs_f64(ms.max(0.0) / 1000.0).map_or(MAX_THINK_TIME, |d| d.min(MAX_THINK_TIME))
+    }
+
+    // 配置错误时返回原因
+    pub(crate) fn check(&self) -> Option<String> {
+        match *self {
+            ThinkTime::Uniform { min_ms, max_ms } if min_ms > max_ms => Some(format!("min_ms({}) > max_ms({})", min_ms, max_ms)),
+            ThinkTime::Normal { mean_ms, std_dev_ms } if !(mean_ms.is_finite() && std_dev_ms.is_finite() && std_dev_ms >= 0.0) => {
+                Some(format!("mean_ms={}, std_dev_ms={}", mean_ms, std_dev_ms))
+            }
+            ThinkTime::Exponential { mean_ms } if !(mean_ms.is_finite() && mean_ms >= 0.0) => Some(format!("mean_ms={}", mean_ms)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::batch::batch;
+    use crate::core::test_server::spawn_http_server;
+    use crate::models::api_endpoint::ApiEndpoint;
+    use crate::models::batch_option::BatchOption;
+
+    #[test]
+    fn test_think_time_sample() {
+        let mut rng = FastRng::new();
+        assert_eq!(ThinkTime::Fixed { ms: 150 }.sample(&mut rng), Duration::from_millis(150));
+        let average = |think_time: ThinkTime, rng: &mut FastRng| -> f64 {
+            (0..20000).map(|_| think_time.sample(rng).as_secs_f64() * 1000.0).sum::<f64>() / 20000.0
+        };
+        for _ in 0..100 {
+            let sample = ThinkTime::Uniform { min_ms: 100, max_ms: 200 }.sample(&mut rng);
+            assert!(sample >= Duration::from_millis(100) && sample <= Duration::from_millis(200));
+        }
+        assert!((average(ThinkTime::Normal { mean_ms: 100.0, std_dev_ms: 10.0 }, &mut rng) - 100.0).abs() < 2.0);
+        assert!((average(ThinkTime::Exponential { mean_ms: 100.0 }, &mut rng) - 100.0).abs() < 5.0);
+        assert!(ThinkTime::Uniform { min_ms: 2, max_ms: 1 }.check().is_some());
+        // 超出范围的值按上限处理，不会panic
+        assert_eq!(ThinkTime::Fixed { ms: u64::MAX }.sample(&mut rng), MAX_THINK_TIME);
+        assert_eq!(ThinkTime::Normal { mean_ms: f64::MAX, std_dev_ms: 0.0 }.sample(&mut rng), MAX_THINK_TIME);
+        assert_eq!(ThinkTime::Exponential { mean_ms: 1e300 }.sample(&mut rng), MAX_THINK_TIME);
+    }
+
+    #[tokio::test]
+    async fn test_batch_think_time() {
+        let (addr, _rx) = spawn_http_server(200, "ok").await;
+        let endpoint = ApiEndpoint {
+            name: "think".to_string(),
+            url: format!("http://{}/", addr),
+            think_time: Some(ThinkTime::Fixed { ms: 100 }),
+            ..Default::default()
+        };
+        // 思考时间100ms，节奏250ms，1秒内每个并发最多4次请求
+        let batch_option = BatchOption { pacing_ms: Some(250), ..Default::default() };
+        let result = batch(1, 2, false, false, vec![endpoint], None, Some(batch_option)).await.unwrap();
+        assert!((6..=8).contains(&result.total_requests), "{}", result.total_requests);
+        assert!(result.max_response_time < 100);
+    }
+}
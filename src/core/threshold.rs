@@ -0,0 +1,86 @@
+use crate::models::engine_error::localized;
+use crate::models::result::BatchResult;
+use crate::models::threshold::{Threshold, ThresholdFailure};
+use crate::sinks::result_sink::{api_metrics, batch_metrics};
+
+// 可以设置阈值的指标
+pub const THRESHOLD_METRICS: [&str; 13] = [
+    "total_requests",
+    "success_rate",
+    "error_rate",
+    "rps",
+    "median_response_time",
+    "response_time_95",
+    "response_time_99",
+    "max_response_time",
+    "min_response_time",
+    "err_count",
+    "total_data_kb",
+    "throughput_per_second_kb",
+    "concurrent_number",
+];
+
+// 检查测试结果是否满足阈值，返回未通过的阈值
+pub fn evaluate_thresholds(result: &BatchResult, thresholds: &[Threshold]) -> Vec<ThresholdFailure> {
+    let mut failures = Vec::new();
+    for threshold in thresholds {
+        let (scope, metrics) = match &threshold.endpoint {
+            None => ("total".to_string(), Some(batch_metrics(result))),
+            Some(name) => (
+                name.clone(),
+                result.api_results.iter().find(|api| &api.name == name).map(api_metrics),
+            ),
+        };
+        let metrics = match metrics {
+            None => {
+                failures.push(ThresholdFailure {
+                    threshold: threshold.clone(),
+                    actual: None,
+                    message: localized(format!("{} 没有测试结果", scope), format!("{} has no result", scope)),
+                });
+                continue;
+            }
+            Some(metrics) => metrics,
+        };
+        let actual = match metrics.iter().find(|(name, _)| *name == threshold.metric) {
+            None => {
+                failures.push(ThresholdFailure {
+                    threshold: threshold.clone(),
+                    actual: None,
+                    message: localized(
+                        format!("{} {} 没有数据", scope, threshold.metric),
+                        format!("{} {} has no data", scope, threshold.metric),
+                    ),
+                });
+                continue;
+            }
+            Some((_, value)) => *value,
+        };
+        if let Some(max) = threshold.max {
+            if actual > max {
+                failures.push(ThresholdFailure {
+                    threshold: threshold.clone(),
+                    actual: Some(actual),
+                    message: localized(
+                        format!("{} {} = {} 超过了上限 {}", scope, threshold.metric, actual, max),
+                        format!("{} {} = {} is above the maximum {}", scope, threshold.metric, actual, max),
+                    ),
+                });
+                continue;
+            }
+        }
+        if let Some(min) = threshold.min {
+            if actual < min {
+                failures.push(ThresholdFailure {
+                    threshold: threshold.clone(),
+                    actual: Some(actual),
+                    message: localized(
+                        format!("{} {} = {} 低于下限 {}", scope, threshold.metric, actual, min),
+                        format!("{} {} = {} is below the minimum {}", scope, threshold.metric, actual, min),
+                    ),
+                });
+            }
+        }
+    }
+    failures
+}
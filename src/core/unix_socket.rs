@@ -0,0 +1,364 @@
+use std::borrow::Cow;
+use std::time::Duration;
+use percent_encoding::percent_decode_str;
+
+use crate::models::error_kind::ErrorKind;
+
+// unix socket地址的前缀，例如 unix:///var/run/app.sock:/api/health?a=1
+// 或者把socket路径编码后作为主机，例如 unix://%2Fvar%2Frun%2Fapp.sock/api/health?a=1
+const UNIX_SCHEME: &str = "unix://";
+
+// 拆分unix socket地址为(socket路径, http路径)，没有http路径时请求"/"
+// 未编码时socket路径到查询参数之前最后一个":/"为止，socket路径本身包含":/"时需要使用编码的形式
+pub(crate) fn parse_unix_url(url: &str) -> Option<(Cow<'_, str>, &str)> {
+    let rest = url.strip_prefix(UNIX_SCHEME)?;
+    let (socket_path, http_path) = if rest.starts_with('/') {
+        let before_query = rest.find(['?', '#']).unwrap_or(rest.len());
+        match rest[..before_query].rfind(":/") {
+            Some(index) => (Cow::Borrowed(&rest[..index]), &rest[index + 1..]),
+            None => (Cow::Borrowed(rest), "/"),
+        }
+    } else {
+        let (host, http_path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+        (percent_decode_str(host).decode_utf8().ok()?, http_path)
+    };
+    if !socket_path.starts_with('/') || socket_path.len() < 2 || !http_path.starts_with('/') {
+        return None;
+    }
+    Some((socket_path, http_path))
+}
+
+// unix socket接口实际请求的url，主机和curl一样使用localhost
+pub(crate) fn unix_request_url(http_path: &str) -> String {
+    format!("http://localhost{}", http_path)
+}
+
+// unix socket请求失败的类型和原因
+pub(crate) struct UnixRequestError {
+    pub(crate) kind: ErrorKind,
+    pub(crate) message: String,
+}
+
+// 连接的编号，放在响应的扩展里，用来区分新建和复用的连接
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct UnixConnectionId(pub(crate) u64);
+
+// 构建unix socket客户端的连接池和协议配置，和tcp客户端一致
+#[derive(Default)]
+pub(crate) struct UnixClientOption {
+    pub(crate) pool_max_idle_per_host: Option<usize>,
+    pub(crate) pool_idle_timeout: Option<Duration>,
+    pub(crate) http2_only: bool,
+    pub(crate) http2_initial_stream_window_size: Option<u32>,
+    pub(crate) http2_initial_connection_window_size: Option<u32>,
+    pub(crate) http2_adaptive_window: bool,
+    pub(crate) http2_keep_alive_interval: Option<Duration>,
+}
+
+pub(crate) use imp::UnixClient;
+
+#[cfg(unix)]
+mod imp {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+    use futures::StreamExt;
+    use http_body_util::BodyStream;
+    use hyper::body::{Bytes, Incoming};
+    use hyper::rt::{Read, ReadBufCursor, Write};
+    use hyper::Uri;
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+    use hyper_util::client::legacy::Client;
+    use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+    use tokio::net::UnixStream;
+    use tokio::sync::mpsc;
+    use tokio::time::Instant;
+
+    use super::{UnixClientOption, UnixConnectionId, UnixRequestError};
+    use crate::models::engine_error::EngineError;
+    use crate::models::error_kind::ErrorKind;
+
+    // reqwest不支持自定义连接器，unix socket目标使用hyper的客户端发送reqwest构建好的请求，
+    // 响应转换成reqwest的响应，统计和断言与tcp目标走同一套流程
+    #[derive(Clone)]
+    pub(crate) struct UnixClient {
+        client: Client<UnixConnector, reqwest::Body>,
+    }
+
+    impl UnixClient {
+        pub(crate) fn new(socket_path: &str, option: UnixClientOption) -> Result<Self, EngineError> {
+            let mut builder = Client::builder(TokioExecutor::new());
+            builder.timer(TokioTimer::new()).pool_timer(TokioTimer::new());
+            if let Some(max_idle) = option.pool_max_idle_per_host {
+                builder.pool_max_idle_per_host(max_idle);
+            }
+            if let Some(idle_timeout) = option.pool_idle_timeout {
+                builder.pool_idle_timeout(idle_timeout);
+            }
+            builder
+                .http2_only(option.http2_only)
+                .http2_initial_stream_window_size(option.http2_initial_stream_window_size)
+                .http2_initial_connection_window_size(option.http2_initial_connection_window_size)
+                .http2_adaptive_window(option.http2_adaptive_window)
+                .http2_keep_alive_interval(option.http2_keep_alive_interval);
+            let connector = UnixConnector { socket_path: Arc::from(socket_path) };
+            Ok(UnixClient { client: builder.build(connector) })
+        }
+
+        // 发送请求，请求上设置的超时时间包括读取响应体的时间
+        pub(crate) async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response, UnixRequestError> {
+            let deadline = request.timeout().map(|timeout| Instant::now() + *timeout);
+            let request = hyper::Request::try_from(request)
+                .map_err(|e| UnixRequestError { kind: ErrorKind::Request, message: e.to_string() })?;
+            let response = match deadline {
+                Some(deadline) => match tokio::time::timeout_at(deadline, self.client.request(request)).await {
+                    Ok(response) => response,
+                    Err(_) => return Err(UnixRequestError { kind: ErrorKind::Timeout, message: "operation timed out".to_string() }),
+                },
+                None => self.client.request(request).await,
+            };
+            let response = response.map_err(|e| UnixRequestError {
+                kind: if e.is_connect() { ErrorKind::Connect } else { ErrorKind::Request },
+                message: error_chain(&e),
+            })?;
+            let (parts, body) = response.into_parts();
+            let body = reqwest::Body::wrap_stream(forward_body(body, deadline));
+            Ok(reqwest::Response::from(hyper::Response::from_parts(parts, body)))
+        }
+    }
+
+    // 错误和底层原因
+    fn error_chain(error: &(dyn std::error::Error + 'static)) -> String {
+        let mut message = error.to_string();
+        let mut source = error.source();
+        while let Some(e) = source {
+            message = format!("{}: {}", message, e);
+            source = e.source();
+        }
+        message
+    }
+
+    // 在单独的任务里读取响应体，超时后返回TimedOut错误，reqwest按超时归类
+    fn forward_body(body: Incoming, deadline: Option<Instant>) -> ReceiverStream {
+        let (tx, rx) = mpsc::channel(8);
+        tokio::spawn(async move {
+            let mut frames = BodyStream::new(body);
+            loop {
+                let next = match deadline {
+                    Some(deadline) => match tokio::time::timeout_at(deadline, frames.next()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            let _ = tx.send(Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "operation timed out"))).await;
+                            break;
+                        }
+                    },
+                    None => frames.next().await,
+                };
+                let item = match next {
+                    None => break,
+                    Some(Ok(frame)) => match frame.into_data() {
+                        Ok(data) => Ok(data),
+                        Err(_) => continue,
+                    },
+                    Some(Err(e)) => Err(std::io::Error::other(e)),
+                };
+                let failed = item.is_err();
+                // 请求被取消时接收端已经丢弃，停止读取并关闭连接
+                if tx.send(item).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        ReceiverStream(rx)
+    }
+
+    struct ReceiverStream(mpsc::Receiver<std::io::Result<Bytes>>);
+
+    impl futures::Stream for ReceiverStream {
+        type Item = std::io::Result<Bytes>;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            self.0.poll_recv(cx)
+        }
+    }
+
+    // 连接编号在所有客户端之间唯一，每个并发使用单独的客户端时也不会重复
+    static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
+    // 连接到固定的unix socket，忽略url中的主机
+    #[derive(Clone)]
+    struct UnixConnector {
+        socket_path: Arc<str>,
+    }
+
+    impl tower_service::Service<Uri> for UnixConnector {
+        type Response = UnixConnection;
+        type Error = std::io::Error;
+        type Future = Pin<Box<dyn Future<Output = Result<UnixConnection, std::io::Error>> + Send>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _uri: Uri) -> Self::Future {
+            let socket_path = self.socket_path.clone();
+            let id = UnixConnectionId(NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed));
+            Box::pin(async move {
+                let stream = UnixStream::connect(&*socket_path)
+                    .await
+                    .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", socket_path, e)))?;
+                Ok(UnixConnection { io: TokioIo::new(stream), id })
+            })
+        }
+    }
+
+    struct UnixConnection {
+        io: TokioIo<UnixStream>,
+        id: UnixConnectionId,
+    }
+
+    impl Connection for UnixConnection {
+        fn connected(&self) -> Connected {
+            Connected::new().extra(self.id)
+        }
+    }
+
+    impl Read for UnixConnection {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: ReadBufCursor<'_>) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.io).poll_read(cx, buf)
+        }
+    }
+
+    impl Write for UnixConnection {
+        fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+            Pin::new(&mut self.io).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.io).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.io).poll_shutdown(cx)
+        }
+    }
+}
+
+// 其他平台在校验时已经拒绝unix socket接口
+#[cfg(not(unix))]
+mod imp {
+    use super::{UnixClientOption, UnixRequestError};
+    use crate::models::engine_error::EngineError;
+
+    #[derive(Clone)]
+    pub(crate) struct UnixClient(Unsupported);
+
+    #[derive(Clone)]
+    enum Unsupported {}
+
+    impl UnixClient {
+        pub(crate) fn new(socket_path: &str, _option: UnixClientOption) -> Result<Self, EngineError> {
+            Err(EngineError::UnixSocketUnsupported(socket_path.to_string()))
+        }
+
+        pub(crate) async fn execute(&self, _request: reqwest::Request) -> Result<reqwest::Response, UnixRequestError> {
+            match self.0 {}
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use crate::core::batch::batch;
+    use crate::core::test_server::spawn_unix_http_server;
+    use crate::models::api_endpoint::ApiEndpoint;
+    use crate::models::assert_option::AssertOption;
+    use crate::models::batch_option::BatchOption;
+    use crate::models::engine_error::{set_language, Language};
+    use crate::models::error_kind::ErrorKind;
+    use crate::models::proxy_option::ProxyOption;
+    use crate::models::request_log_option::RequestLogOption;
+    use crate::models::request_record::RequestRecord;
+
+    #[tokio::test]
+    async fn test_unix_socket() {
+        assert_eq!(parse_unix_url("unix:///tmp/app.sock"), Some(("/tmp/app.sock".into(), "/")));
+        assert_eq!(parse_unix_url("unix:///tmp/app.sock:/api?a=1"), Some(("/tmp/app.sock".into(), "/api?a=1")));
+        assert_eq!(parse_unix_url("unix://"), None);
+
+        let socket_path = std::env::temp_dir().join(format!("atomic-bomb-{}.sock", std::process::id()));
+        let mut rx = spawn_unix_http_server(&socket_path, 200, r#"{"code": 0}"#).await;
+        let endpoint = ApiEndpoint {
+            name: "unix".to_string(),
+            url: format!("unix://{}:/api/health", socket_path.display()),
+            timeout_secs: 5,
+            assert_options: Some(vec![AssertOption { jsonpath: "$.code".to_string(), reference_object: Value::from(0) }]),
+            ..Default::default()
+        };
+        // 代理不可用，unix socket接口不能经过代理
+        let proxy = ProxyOption { url: "http://127.0.0.1:1".to_string(), ..Default::default() };
+        let batch_option = BatchOption { iterations_per_vu: Some(3), proxies: vec![proxy], ..Default::default() };
+        let result = batch(5, 2, false, false, vec![endpoint.clone()], None, Some(batch_option)).await.unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+        assert_eq!(result.total_requests, 6);
+        assert_eq!(result.err_count, 0);
+        assert!(result.assert_errors.is_empty());
+        assert_eq!(result.api_results[0].url, endpoint.url);
+        // 和tcp接口一样统计连接和协议版本，每个并发一个连接
+        assert_eq!(result.api_results[0].connections_opened, 2);
+        assert_eq!(result.api_results[0].connections_reused, 4);
+        assert_eq!(result.api_results[0].http_versions.get("HTTP/1.1"), Some(&6));
+        let request = rx.recv().await.unwrap();
+        assert!(request.starts_with("GET /api/health HTTP/1.1"), "{}", request);
+        assert!(request.to_lowercase().contains("host: localhost\r\n"), "{}", request);
+    }
+
+    #[test]
+    fn test_parse_unix_url() {
+        // socket路径中的":"和查询参数中的":/"不影响拆分
+        assert_eq!(parse_unix_url("unix:///run/app:1.sock:/api"), Some(("/run/app:1.sock".into(), "/api")));
+        assert_eq!(parse_unix_url("unix:///run/app:1.sock"), Some(("/run/app:1.sock".into(), "/")));
+        assert_eq!(parse_unix_url("unix:///tmp/app.sock:/cb?u=http://a/b"), Some(("/tmp/app.sock".into(), "/cb?u=http://a/b")));
+        // 编码的形式，socket路径可以包含任意字符
+        assert_eq!(parse_unix_url("unix://%2Frun%2Fa%3A%2Fb.sock/api?a=1"), Some(("/run/a:/b.sock".into(), "/api?a=1")));
+        assert_eq!(parse_unix_url("unix://%2Ftmp%2Fapp.sock"), Some(("/tmp/app.sock".into(), "/")));
+        let endpoint = ApiEndpoint { name: "unix".to_string(), url: "unix://%2Frun%2Fa%3A%2Fb.sock/api".to_string(), ..Default::default() };
+        assert!(crate::core::validate::validate_endpoint(&endpoint).is_empty());
+        assert_eq!(parse_unix_url("unix://tmp/app.sock"), None);
+        assert_eq!(parse_unix_url("unix:///tmp/app.sock:api"), Some(("/tmp/app.sock:api".into(), "/")));
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket_connect_error() {
+        set_language(Language::Chinese);
+        let socket_path = std::env::temp_dir().join(format!("atomic-bomb-missing-{}.sock", std::process::id()));
+        let log_path = std::env::temp_dir().join(format!("atomic-bomb-unix-{}.jsonl", std::process::id()));
+        let endpoint = ApiEndpoint {
+            name: "missing".to_string(),
+            url: format!("unix://{}:/", socket_path.display()),
+            timeout_secs: 5,
+            ..Default::default()
+        };
+        // socket文件不存在，请求记为连接失败，日志里是原来的url
+        let batch_option = BatchOption {
+            iterations_per_vu: Some(2),
+            request_log: Some(RequestLogOption { path: log_path.display().to_string(), sample_rate: 1.0, errors_only: false, har_path: None }),
+            ..Default::default()
+        };
+        let result = batch(5, 1, false, false, vec![endpoint.clone()], None, Some(batch_option)).await.unwrap();
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        let _ = std::fs::remove_file(&log_path);
+        assert_eq!(result.err_count, 2);
+        assert!(result.http_errors.keys().all(|(_, message, url)| message.starts_with("连接unix socket失败::") && *url == endpoint.url));
+        let records: Vec<RequestRecord> = log.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r.error_kind == Some(ErrorKind::Connect) && r.url == endpoint.url), "{}", log);
+    }
+}
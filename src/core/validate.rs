@@ -0,0 +1,299 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use jsonpath_lib::Compiled;
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::Method;
+use url::Url;
+
+use crate::models::api_endpoint::ApiEndpoint;
+use crate::models::http_version::HttpVersion;
+use crate::models::batch_option::BatchOption;
+use crate::models::engine_error::{localized, EngineError};
+use crate::models::step_option::StepOption;
+use crate::models::tls_option::TlsOption;
+use crate::models::dns_option::DnsOption;
+use crate::models::connection_option::ConnectionStrategy;
+use crate::core::http_client::load_tls;
+use crate::core::unix_socket::parse_unix_url;
+
+// 只校验batch的参数不发送请求，有问题时一次性返回全部问题
+pub fn dry_run(
+    test_duration_secs: u64,
+    concurrent_requests: usize,
+    api_endpoints: &[ApiEndpoint],
+    step_option: Option<&StepOption>,
+    batch_option: Option<&BatchOption>,
+) -> Result<(), EngineError> {
+    let problems = validate_batch(test_duration_secs, concurrent_requests, api_endpoints, step_option, batch_option);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(EngineError::Validation(problems))
+    }
+}
+
+// 校验batch的参数，返回全部问题
+pub fn validate_batch(
+    test_duration_secs: u64,
+    concurrent_requests: usize,
+    api_endpoints: &[ApiEndpoint],
+    step_option: Option<&StepOption>,
+    batch_option: Option<&BatchOption>,
+) -> Vec<EngineError> {
+    let mut problems = validate_options(test_duration_secs, concurrent_requests, api_endpoints, step_option, batch_option);
+    problems.extend(validate_endpoints(api_endpoints).into_iter().map(|(_, problem)| problem));
+    problems
+}
+
+// 校验接口以外的参数
+pub(crate) fn validate_options(
+    test_duration_secs: u64,
+    concurrent_requests: usize,
+    api_endpoints: &[ApiEndpoint],
+    step_option: Option<&StepOption>,
+    batch_option: Option<&BatchOption>,
+) -> Vec<EngineError> {
+    let mut problems = Vec::new();
+    if test_duration_secs == 0 {
+        problems.push(EngineError::ZeroValue("test_duration_secs".to_string()));
+    }
+    if concurrent_requests == 0 {
+        problems.push(EngineError::ZeroValue("concurrent_requests".to_string()));
+    }
+    if api_endpoints.is_empty() {
+        problems.push(EngineError::NoEndpoints);
+    }
+    if let Some(step_option) = step_option {
+        if step_option.increase_step == 0 {
+            problems.push(EngineError::ZeroValue("increase_step".to_string()));
+        }
+    }
+    if batch_option.and_then(|o| o.pacing_ms) == Some(0) {
+        problems.push(EngineError::ZeroValue("pacing_ms".to_string()));
+    }
+    if batch_option.and_then(|o| o.max_requests) == Some(0) {
+        problems.push(EngineError::ZeroValue("max_requests".to_string()));
+    }
+    if batch_option.and_then(|o| o.iterations_per_vu) == Some(0) {
+        problems.push(EngineError::ZeroValue("iterations_per_vu".to_string()));
+    }
+    if batch_option.and_then(|o| o.connection.vus_per_client) == Some(0) {
+        problems.push(EngineError::ZeroValue("vus_per_client".to_string()));
+    }
+    if batch_option.is_some_and(|o| o.connection.vus_per_client.is_some() && o.connection.strategy != ConnectionStrategy::Shared) {
+        problems.push(EngineError::RequiresSharedConnection("vus_per_client".to_string()));
+    }
+    if batch_option.and_then(|o| o.warm_up_secs) == Some(0) {
+        problems.push(EngineError::ZeroValue("warm_up_secs".to_string()));
+    }
+    if batch_option.and_then(|o| o.dns.refresh_secs) == Some(0) {
+        problems.push(EngineError::ZeroValue("refresh_secs".to_string()));
+    }
+    if let Some(dns) = batch_option.map(|o| &o.dns) {
+        let mut hosts = HashSet::new();
+        for (key, ips) in &dns.resolve {
+            match DnsOption::split_key(key) {
+                None => problems.push(EngineError::InvalidResolve(key.clone(), localized("应为 host:port", "expected host:port").to_string())),
+                Some((host, _)) if !hosts.insert(host.to_lowercase()) => {
+                    problems.push(EngineError::InvalidResolve(key.clone(), localized("主机重复", "duplicate host").to_string()))
+                }
+                Some(_) if ips.is_empty() => problems.push(EngineError::InvalidResolve(key.clone(), localized("没有ip地址", "no ip").to_string())),
+                Some(_) => {}
+            }
+        }
+    }
+    // 不是本机的地址绑定时也会返回EADDRNOTAVAIL，提前校验避免误报为端口耗尽
+    for ip in batch_option.map(|o| o.connection.local_addresses.as_slice()).unwrap_or_default() {
+        if let Err(e) = std::net::TcpListener::bind((*ip, 0)) {
+            problems.push(EngineError::InvalidLocalAddress(ip.to_string(), e.to_string()));
+        }
+    }
+    for proxy in batch_option.map(|o| o.proxies.as_slice()).unwrap_or_default() {
+        match Url::parse(&proxy.url) {
+            Ok(url) if matches!(url.scheme(), "socks4" | "socks4a") => {
+                problems.push(EngineError::UnsupportedProxy(proxy.url.clone()));
+            }
+            Ok(url) if matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") && url.has_host() => {}
+            _ => problems.push(EngineError::InvalidProxy(
+                proxy.url.clone(),
+                localized("应为 http(s)://host:port 或 socks5(h)://host:port", "expected http(s)://host:port or socks5(h)://host:port").to_string(),
+            )),
+        }
+        if proxy.password.is_some() && proxy.username.is_none() {
+            problems.push(EngineError::InvalidProxy(proxy.url.clone(), localized("设置了密码但没有用户名", "password without username").to_string()));
+        }
+    }
+    if let Some(tls) = batch_option.and_then(|o| o.tls.as_ref()) {
+        problems.extend(validate_tls(tls));
+    }
+    if let Some(request_log) = batch_option.and_then(|o| o.request_log.as_ref()) {
+        if !(request_log.sample_rate > 0.0 && request_log.sample_rate <= 1.0) {
+            problems.push(EngineError::InvalidSampleRate(request_log.sample_rate));
+        }
+    }
+    problems
+}
+
+// 校验接口的名称和请求配置，问题带有接口的下标
+pub(crate) fn validate_endpoints(api_endpoints: &[ApiEndpoint]) -> Vec<(usize, EngineError)> {
+    let mut problems = Vec::new();
+    let mut names = HashSet::new();
+    for (index, endpoint) in api_endpoints.iter().enumerate() {
+        if endpoint.name.is_empty() {
+            problems.push((index, EngineError::EmptyEndpointName));
+        } else if !names.insert(endpoint.name.as_str()) {
+            problems.push((index, EngineError::DuplicateEndpointName(endpoint.name.clone())));
+        }
+        problems.extend(
+            validate_endpoint(endpoint)
+                .into_iter()
+                .map(|e| (index, EngineError::InvalidEndpoint(endpoint.name.clone(), Box::new(e)))),
+        );
+    }
+    problems
+}
+
+// 校验单个接口的请求配置(不包括名称)
+pub fn validate_endpoint(endpoint: &ApiEndpoint) -> Vec<EngineError> {
+    let mut problems = Vec::new();
+    match Url::parse(&endpoint.url) {
+        // unix socket上只能使用HTTP/1.1或明文HTTP/2
+        Ok(url) if url.scheme() == "unix" => {
+            if parse_unix_url(&endpoint.url).is_none() {
+                problems.push(EngineError::InvalidUrl(endpoint.url.clone()));
+            } else if cfg!(not(unix)) {
+                problems.push(EngineError::UnixSocketUnsupported(endpoint.url.clone()));
+            } else if endpoint.http_version == HttpVersion::Http2 {
+                problems.push(EngineError::Http2RequiresHttps(endpoint.url.clone()));
+            }
+        }
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => {
+            if endpoint.http_version == HttpVersion::Http2 && url.scheme() != "https" {
+                problems.push(EngineError::Http2RequiresHttps(endpoint.url.clone()));
+            }
+        }
+        _ => problems.push(EngineError::InvalidUrl(endpoint.url.clone())),
+    }
+    if let Some(tls) = &endpoint.tls {
+        problems.extend(validate_tls(tls));
+    }
+    if Method::from_str(&endpoint.method.to_uppercase()).is_err() {
+        problems.push(EngineError::InvalidMethod(endpoint.method.clone()));
+    }
+    if endpoint.weight == 0 {
+        problems.push(EngineError::ZeroValue("weight".to_string()));
+    }
+    if endpoint.max_requests == Some(0) {
+        problems.push(EngineError::ZeroValue("max_requests".to_string()));
+    }
+    if let Some(headers) = &endpoint.headers {
+        for (k, v) in headers {
+            if k.parse::<HeaderName>().is_err() {
+                problems.push(EngineError::InvalidHeaderName(k.clone()));
+            } else if v.parse::<HeaderValue>().is_err() {
+                problems.push(EngineError::InvalidHeaderValue(k.clone()));
+            }
+        }
+    }
+    if let Some(cookie) = &endpoint.cookies {
+        if HeaderValue::from_str(cookie).is_err() {
+            problems.push(EngineError::InvalidCookie(cookie.clone()));
+        }
+    }
+    if endpoint.json.is_some() && endpoint.form_data.is_some() {
+        problems.push(EngineError::JsonAndFormTogether(endpoint.name.clone()));
+    }
+    if let Some(reason) = endpoint.think_time.as_ref().and_then(|t| t.check()) {
+        problems.push(EngineError::InvalidThinkTime(reason));
+    }
+    for assert_option in endpoint.assert_options.iter().flatten() {
+        if Compiled::compile(&assert_option.jsonpath).is_err() {
+            problems.push(EngineError::InvalidJsonPath(assert_option.jsonpath.clone()));
+        }
+    }
+    problems
+}
+
+// 校验TLS配置，证书文件需要能正常加载
+fn validate_tls(tls: &TlsOption) -> Vec<EngineError> {
+    let mut problems = Vec::new();
+    if tls.client_cert.is_some() != tls.client_key.is_some() {
+        problems.push(EngineError::InvalidTls(localized("client_cert和client_key需要同时设置", "client_cert and client_key must be set together").to_string()));
+    }
+    if tls.pkcs12.is_some() && tls.client_cert.is_some() {
+        problems.push(EngineError::InvalidTls(localized("pkcs12和client_cert不能同时使用", "pkcs12 and client_cert cannot be used together").to_string()));
+    }
+    if let (Some(min), Some(max)) = (tls.min_version, tls.max_version) {
+        if min > max {
+            problems.push(EngineError::InvalidTls(format!("min_version {:?} > max_version {:?}", min, max)));
+        }
+    }
+    if let Some(server_name) = &tls.server_name {
+        if Url::parse(&format!("https://{}", server_name)).map(|url| url.host_str() != Some(server_name.as_str())).unwrap_or(true) {
+            problems.push(EngineError::InvalidTls(localized(format!("无效的server_name {}", server_name), format!("invalid server_name {}", server_name))));
+        }
+    }
+    if problems.is_empty() {
+        if let Err(e) = load_tls(tls) {
+            problems.push(e);
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use crate::models::assert_option::AssertOption;
+
+    #[test]
+    fn test_dry_run() {
+        let endpoint = ApiEndpoint {
+            name: "登录".to_string(),
+            url: "http://127.0.0.1:8080/login".to_string(),
+            method: "POST".to_string(),
+            assert_options: Some(vec![AssertOption { jsonpath: "$.code".to_string(), reference_object: Value::from(200) }]),
+            ..Default::default()
+        };
+        assert!(dry_run(10, 10, std::slice::from_ref(&endpoint), None, None).is_ok());
+
+        let invalid = ApiEndpoint {
+            url: "ftp//127.0.0.1".to_string(),
+            method: "GE T".to_string(),
+            weight: 0,
+            headers: Some([("x token".to_string(), "1".to_string())].into_iter().collect()),
+            json: Some(Value::Null),
+            form_data: Some(Default::default()),
+            assert_options: Some(vec![AssertOption { jsonpath: "$.[".to_string(), reference_object: Value::Null }]),
+            ..endpoint.clone()
+        };
+        let problems = match dry_run(0, 10, &[endpoint, invalid], None, None) {
+            Err(EngineError::Validation(problems)) => problems,
+            _ => panic!("应该校验失败"),
+        };
+        let messages: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+        assert_eq!(problems.len(), 8, "{:?}", messages);
+        assert!(matches!(problems[0], EngineError::ZeroValue(ref field) if field == "test_duration_secs"));
+        assert!(matches!(problems[1], EngineError::DuplicateEndpointName(_)));
+        assert!(problems[2..].iter().all(|p| matches!(p, EngineError::InvalidEndpoint(..))));
+    }
+
+    #[test]
+    fn test_dry_run_vus_per_client() {
+        let endpoint = ApiEndpoint { name: "h2".to_string(), url: "http://127.0.0.1:8080/".to_string(), ..Default::default() };
+        // 默认每个并发一个连接池，每个共享客户端的并发数不会生效
+        let mut batch_option = BatchOption {
+            connection: crate::models::connection_option::ConnectionOption { vus_per_client: Some(2), ..Default::default() },
+            ..Default::default()
+        };
+        match dry_run(10, 10, std::slice::from_ref(&endpoint), None, Some(&batch_option)) {
+            Err(EngineError::Validation(problems)) => {
+                assert!(matches!(problems.as_slice(), [EngineError::RequiresSharedConnection(field)] if field == "vus_per_client"), "{:?}", problems);
+            }
+            _ => panic!("应该校验失败"),
+        }
+        batch_option.connection.strategy = ConnectionStrategy::Shared;
+        assert!(dry_run(10, 10, &[endpoint], None, Some(&batch_option)).is_ok());
+    }
+}
@@ -1,3 +1,5 @@
 pub mod core;
 pub mod models;
-
+pub mod sinks;
+#[cfg(feature = "python-extension")]
+mod python;
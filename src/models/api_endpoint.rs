@@ -2,18 +2,71 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use crate::models::assert_option::AssertOption;
+use crate::models::http_version::HttpVersion;
+use crate::models::tls_option::TlsOption;
+use crate::models::think_time::ThinkTime;
 
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ApiEndpoint {
     pub name: String,
     pub url: String,
+    #[serde(default = "default_method")]
     pub method: String,
+    #[serde(default)]
     pub timeout_secs: u64,
+    #[serde(default = "default_weight")]
     pub weight: u32,
     pub json: Option<Value>,
     pub form_data: Option<HashMap<String, String>>,
     pub headers: Option<HashMap<String, String>>,
     pub cookies: Option<String>,
     pub assert_options: Option<Vec<AssertOption>>,
+    // 期望的状态码，为空时2xx和3xx都算成功
+    #[serde(default)]
+    pub expected_status: Option<Vec<u16>>,
+    // 请求完成后的思考时间
+    #[serde(default)]
+    pub think_time: Option<ThinkTime>,
+    // 这个接口最多发送的请求数，用完后不再请求这个接口
+    #[serde(default)]
+    pub max_requests: Option<u64>,
+    // 使用的http协议版本
+    #[serde(default)]
+    pub http_version: HttpVersion,
+    // TLS配置，设置后整体覆盖batch中的TLS配置
+    #[serde(default)]
+    pub tls: Option<TlsOption>,
+}
+
+// 除名称和url外都使用默认值，反序列化时省略的字段也是这些值
+impl Default for ApiEndpoint {
+    fn default() -> Self {
+        ApiEndpoint {
+            name: String::new(),
+            url: String::new(),
+            method: default_method(),
+            timeout_secs: 0,
+            weight: default_weight(),
+            json: None,
+            form_data: None,
+            headers: None,
+            cookies: None,
+            assert_options: None,
+            expected_status: None,
+            think_time: None,
+            max_requests: None,
+            http_version: HttpVersion::Auto,
+            tls: None,
+        }
+    }
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_weight() -> u32 {
+    1
 }
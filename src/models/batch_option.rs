@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::core::batch_control::BatchControl;
+use crate::models::connection_option::ConnectionOption;
+use crate::models::dns_option::DnsOption;
+use crate::models::proxy_option::ProxyOption;
+use crate::models::http2_option::Http2Option;
+use crate::models::tls_option::TlsOption;
+use crate::models::mix_mode::MixMode;
+use crate::models::request_log_option::RequestLogOption;
+use crate::sinks::result_sink::ResultSink;
+
+// batch的可选配置
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatchOption {
+    // 结果推送目标，每个统计周期推送快照，压测结束后推送最终结果
+    #[serde(skip)]
+    pub result_sinks: Vec<Arc<dyn ResultSink>>,
+    // 单请求日志
+    pub request_log: Option<RequestLogOption>,
+    // 接口的选择方式，默认按权重拆分并发数
+    pub mix_mode: MixMode,
+    // 每个并发两次迭代开始之间的最小间隔(毫秒)，包括响应时间和思考时间
+    pub pacing_ms: Option<u64>,
+    // 总请求数上限，达到后结束压测
+    pub max_requests: Option<u64>,
+    // 每个并发的迭代次数，完成后这个并发结束，按顺序执行场景时走完一遍场景算一次迭代
+    pub iterations_per_vu: Option<u64>,
+    // 预热时长(秒)，预热期间的请求不计入结果，压测时长从预热结束后开始计算
+    pub warm_up_secs: Option<u64>,
+    // 预热期间逐步启动并发，而不是一开始全部启动
+    pub warm_up_ramp: bool,
+    // 到达压测时长后等待进行中请求的最长时间(秒)，超时的请求记为中断，为空时一直等待
+    pub graceful_stop_secs: Option<u64>,
+    // 连接的使用方式和连接池配置
+    pub connection: ConnectionOption,
+    // HTTP/2配置
+    pub http2: Http2Option,
+    // 所有接口默认的TLS配置
+    pub tls: Option<TlsOption>,
+    // 固定解析和DNS刷新
+    pub dns: DnsOption,
+    // 代理，为空时不使用代理(也不读取HTTP_PROXY等环境变量)
+    pub proxies: Vec<ProxyOption>,
+    // 运行中控制压测(暂停、停止、调整并发数)
+    #[serde(skip)]
+    pub control: Option<Arc<BatchControl>>,
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+// 对比的容忍度，超过容忍度即判定为性能回退
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompareOption {
+    // 响应时间(p50/p95/p99)允许变慢的百分比
+    pub latency_tolerance_percent: f64,
+    // rps允许下降的百分比
+    pub rps_tolerance_percent: f64,
+    // 错误率允许上升的百分点
+    pub error_rate_tolerance: f64,
+}
+
+impl Default for CompareOption {
+    fn default() -> Self {
+        CompareOption {
+            latency_tolerance_percent: 10.0,
+            rps_tolerance_percent: 10.0,
+            error_rate_tolerance: 1.0,
+        }
+    }
+}
+
+// 单个指标的对比
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MetricDiff {
+    pub metric: String,
+    pub baseline: f64,
+    pub current: f64,
+    // 相对基线的变化百分比，基线为0时为空
+    pub change_percent: Option<f64>,
+    pub regression: bool,
+}
+
+// 单个接口(或汇总)的对比
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EndpointDiff {
+    pub name: String,
+    pub metrics: Vec<MetricDiff>,
+    pub regression: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompareResult {
+    // 全部接口的汇总对比
+    pub total: EndpointDiff,
+    pub endpoints: Vec<EndpointDiff>,
+    // 只在基线中出现的接口
+    pub only_in_baseline: Vec<String>,
+    // 只在本次结果中出现的接口
+    pub only_in_current: Vec<String>,
+}
+
+impl CompareResult {
+    pub fn has_regression(&self) -> bool {
+        self.total.regression || self.endpoints.iter().any(|e| e.regression)
+    }
+
+    // 回退描述，例如: checkout response_time_95 变差了 18.0%
+    pub fn regressions(&self) -> Vec<String> {
+        std::iter::once(&self.total)
+            .chain(self.endpoints.iter())
+            .flat_map(|endpoint| {
+                endpoint.metrics.iter().filter(|m| m.regression).map(move |m| {
+                    match m.change_percent {
+                        Some(change) if m.metric != "error_rate" => format!(
+                            "{} {} 变差了 {:.1}% ({} -> {})", endpoint.name, m.metric, change.abs(), m.baseline, m.current
+                        ),
+                        _ => format!(
+                            "{} {} 变差了 ({} -> {})", endpoint.name, m.metric, m.baseline, m.current
+                        ),
+                    }
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,31 @@
+use std::net::IpAddr;
+use serde::{Deserialize, Serialize};
+
+// 连接的使用方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionStrategy {
+    // 所有并发共用一个客户端和连接池
+    Shared,
+    // 每个并发一个客户端，各自维护连接池
+    #[default]
+    PerVu,
+    // 每个请求新建连接，不复用(关闭keep-alive)
+    PerRequest,
+}
+
+// 连接池配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConnectionOption {
+    pub strategy: ConnectionStrategy,
+    // 每个host最多保留的空闲连接数
+    pub pool_max_idle_per_host: Option<usize>,
+    // 空闲连接的保留时间(秒)
+    pub pool_idle_timeout_secs: Option<u64>,
+    // 建立连接时绑定的本地地址，多个地址时按并发轮流分配，避免单个地址的临时端口耗尽
+    pub local_addresses: Vec<IpAddr>,
+    // 共用连接池时每个共享客户端最多分配的并发数，超过后分配新的客户端和连接池，
+    // HTTP/2下相当于每个连接承载的并发流数；为空时所有并发共用客户端(每个本地地址一个)
+    pub vus_per_client: Option<usize>,
+}
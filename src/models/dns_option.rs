@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use serde::{Deserialize, Serialize};
+
+// DNS配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DnsOption {
+    // 固定解析，和curl的--resolve一样，例如 "api.example.com:443": ["10.0.0.1", "10.0.0.2"]
+    // 多个ip时新建连接轮流使用，DNS没有端口的概念，同一个主机名只能配置一次
+    pub resolve: HashMap<String, Vec<IpAddr>>,
+    // 重新解析DNS的间隔(秒)，到期后重新构建客户端，新连接使用新的解析结果
+    pub refresh_secs: Option<u64>,
+}
+
+impl DnsOption {
+    // 拆分"host:port"形式的键，端口可以省略
+    pub fn split_key(key: &str) -> Option<(&str, Option<u16>)> {
+        let (host, port) = match key.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && !host.ends_with(':') => (host, Some(port.parse().ok()?)),
+            Some(_) => return None,
+            None => (key, None),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            None
+        } else {
+            Some((host, port))
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+// 压测结束的原因
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndReason {
+    // 达到压测时长
+    #[default]
+    Duration,
+    // 达到总请求数
+    MaxRequests,
+    // 每个并发都完成了指定的迭代次数
+    Iterations,
+    // 接口的请求预算用完
+    EndpointBudget,
+    // 被手动停止
+    Stopped,
+}
+
+impl EndReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EndReason::Duration => "duration",
+            EndReason::MaxRequests => "max_requests",
+            EndReason::Iterations => "iterations",
+            EndReason::EndpointBudget => "endpoint_budget",
+            EndReason::Stopped => "stopped",
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use thiserror::Error;
+
+// 错误信息的语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Chinese,
+    English,
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "zh" | "zh-cn" | "chinese" => Ok(Language::Chinese),
+            "en" | "en-us" | "english" => Ok(Language::English),
+            _ => Err(format!("不支持的语言: {}，仅支持zh/en", s)),
+        }
+    }
+}
+
+// 全局的错误信息语言，默认中文
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn current_language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::English,
+        _ => Language::Chinese,
+    }
+}
+
+// 按当前语言选择文本，用于导入警告和命令行输出等不是错误的信息
+pub fn localized<T>(chinese: T, english: T) -> T {
+    match current_language() {
+        Language::Chinese => chinese,
+        Language::English => english,
+    }
+}
+
+// 压测引擎的错误，Display按当前语言输出，也可以用message指定语言
+#[derive(Debug, Error)]
+pub enum EngineError {
+    // 接口名称为空
+    #[error("{}", self.message(current_language()))]
+    EmptyEndpointName,
+    // 接口名称重复
+    #[error("{}", self.message(current_language()))]
+    DuplicateEndpointName(String),
+    // json和form同时设置(接口名称或url)
+    #[error("{}", self.message(current_language()))]
+    JsonAndFormTogether(String),
+    // 没有任何接口
+    #[error("{}", self.message(current_language()))]
+    NoEndpoints,
+    // 必须大于0的字段(字段名)
+    #[error("{}", self.message(current_language()))]
+    ZeroValue(String),
+    // 只在共用连接池时生效的字段(字段名)
+    #[error("{}", self.message(current_language()))]
+    RequiresSharedConnection(String),
+    // 无效的url
+    #[error("{}", self.message(current_language()))]
+    InvalidUrl(String),
+    // 无效的请求方法
+    #[error("{}", self.message(current_language()))]
+    InvalidMethod(String),
+    // 无效的请求头名称
+    #[error("{}", self.message(current_language()))]
+    InvalidHeaderName(String),
+    // 无效的请求头值(请求头名称)
+    #[error("{}", self.message(current_language()))]
+    InvalidHeaderValue(String),
+    // 无效的cookie
+    #[error("{}", self.message(current_language()))]
+    InvalidCookie(String),
+    // 无效的json
+    #[error("{}", self.message(current_language()))]
+    InvalidJson(String),
+    // 无效的jsonpath
+    #[error("{}", self.message(current_language()))]
+    InvalidJsonPath(String),
+    // 无效的思考时间
+    #[error("{}", self.message(current_language()))]
+    InvalidThinkTime(String),
+    // TLS配置错误
+    #[error("{}", self.message(current_language()))]
+    InvalidTls(String),
+    // 加载证书失败(路径, 原因)
+    #[error("{}", self.message(current_language()))]
+    InvalidCertificate(String, String),
+    // 无效的固定解析(键, 原因)
+    #[error("{}", self.message(current_language()))]
+    InvalidResolve(String, String),
+    // 无效的代理(url, 原因)
+    #[error("{}", self.message(current_language()))]
+    InvalidProxy(String, String),
+    // 不支持的代理协议，例如socks4(url)
+    #[error("{}", self.message(current_language()))]
+    UnsupportedProxy(String),
+    // 无法绑定的本地地址(地址, 原因)
+    #[error("{}", self.message(current_language()))]
+    InvalidLocalAddress(String, String),
+    // 当前平台不支持unix socket(socket路径)
+    #[error("{}", self.message(current_language()))]
+    UnixSocketUnsupported(String),
+    // 通过ALPN协商HTTP/2只支持https(url)
+    #[error("{}", self.message(current_language()))]
+    Http2RequiresHttps(String),
+    // 请求日志的采样率不在(0, 1]之间
+    #[error("{}", self.message(current_language()))]
+    InvalidSampleRate(f64),
+    // 未知的阈值指标
+    #[error("{}", self.message(current_language()))]
+    UnknownThresholdMetric(String),
+    // 阈值没有设置上下限(指标)
+    #[error("{}", self.message(current_language()))]
+    ThresholdWithoutBound(String),
+    // 阈值引用了不存在的接口(指标, 接口名称)
+    #[error("{}", self.message(current_language()))]
+    UnknownThresholdEndpoint(String, String),
+    // 不支持的测试计划格式(路径)
+    #[error("{}", self.message(current_language()))]
+    UnsupportedPlanFormat(String),
+    // 测试计划的语法或类型错误(位置, 解析器的错误信息)
+    #[error("{}", self.message(current_language()))]
+    InvalidPlan(String, String),
+    // 带有文件位置的错误(文件名:行号, 具体错误)
+    #[error("{}", self.message(current_language()))]
+    Located(String, Box<EngineError>),
+    // 不是curl命令
+    #[error("{}", self.message(current_language()))]
+    NotCurlCommand,
+    // curl选项缺少参数(选项)
+    #[error("{}", self.message(current_language()))]
+    MissingOptionValue(String),
+    // 不支持的curl选项
+    #[error("{}", self.message(current_language()))]
+    UnsupportedCurlOption(String),
+    // 不支持从文件读取的参数(参数)
+    #[error("{}", self.message(current_language()))]
+    UnsupportedFileReference(String),
+    // 无效的表单字段
+    #[error("{}", self.message(current_language()))]
+    InvalidFormField(String),
+    // 不支持的请求体，只支持json和表单(Content-Type，没有时为请求体)
+    #[error("{}", self.message(current_language()))]
+    UnsupportedRequestBody(String),
+    // 无效的超时时间
+    #[error("{}", self.message(current_language()))]
+    InvalidTimeout(String),
+    // 没有url
+    #[error("{}", self.message(current_language()))]
+    MissingUrl,
+    // 引号没有闭合(引号)
+    #[error("{}", self.message(current_language()))]
+    UnclosedQuote(char),
+    // 无法解析的导入文件或报告(文件类型, 原因)
+    #[error("{}", self.message(current_language()))]
+    InvalidDocument(String, String),
+    // 不支持的文档版本(文件类型, 支持的版本, 当前版本)
+    #[error("{}", self.message(current_language()))]
+    UnsupportedDocumentVersion(String, String, String),
+    // 导入文件中没有符合条件的请求(文件类型)
+    #[error("{}", self.message(current_language()))]
+    NothingToImport(String),
+    // openapi文档没有可用的服务地址，需要设置base_url(文档中的地址，没有时为空)
+    #[error("{}", self.message(current_language()))]
+    MissingBaseUrl(String),
+    // 某个接口的配置错误(接口名称, 具体错误)
+    #[error("{}", self.message(current_language()))]
+    InvalidEndpoint(String, Box<EngineError>),
+    // 校验发现的全部问题
+    #[error("{}", self.message(current_language()))]
+    Validation(Vec<EngineError>),
+    // 构建http客户端失败
+    #[error("{}", self.message(current_language()))]
+    ClientBuild(#[source] reqwest::Error),
+    // 读写文件失败(路径)
+    #[error("{}", self.message(current_language()))]
+    Io(String, #[source] std::io::Error),
+}
+
+impl EngineError {
+    pub fn message(&self, language: Language) -> String {
+        match (self, language) {
+            (EngineError::EmptyEndpointName, Language::Chinese) => "api名称不能为空".to_string(),
+            (EngineError::EmptyEndpointName, Language::English) => "endpoint name must not be empty".to_string(),
+            (EngineError::DuplicateEndpointName(name), Language::Chinese) => format!("重复的name: {}", name),
+            (EngineError::DuplicateEndpointName(name), Language::English) => format!("duplicate endpoint name: {}", name),
+            (EngineError::JsonAndFormTogether(name), Language::Chinese) => format!("{}: json和form不允许同时发送", name),
+            (EngineError::JsonAndFormTogether(name), Language::English) => format!("{}: json and form must not be sent together", name),
+            (EngineError::NoEndpoints, Language::Chinese) => "至少需要一个接口".to_string(),
+            (EngineError::NoEndpoints, Language::English) => "at least one endpoint is required".to_string(),
+            (EngineError::ZeroValue(field), Language::Chinese) => format!("{}必须大于0", field),
+            (EngineError::ZeroValue(field), Language::English) => format!("{} must be greater than 0", field),
+            (EngineError::RequiresSharedConnection(field), Language::Chinese) => format!("{}只在connection.strategy为shared时生效", field),
+            (EngineError::RequiresSharedConnection(field), Language::English) => format!("{} only takes effect when connection.strategy is shared", field),
+            (EngineError::InvalidUrl(url), Language::Chinese) => format!("无效的url: {}", url),
+            (EngineError::InvalidUrl(url), Language::English) => format!("invalid url: {}", url),
+            (EngineError::InvalidMethod(method), Language::Chinese) => format!("无效的请求方法: {}", method),
+            (EngineError::InvalidMethod(method), Language::English) => format!("invalid http method: {}", method),
+            (EngineError::InvalidHeaderName(name), Language::Chinese) => format!("无法解析header名称: {}", name),
+            (EngineError::InvalidHeaderName(name), Language::English) => format!("invalid header name: {}", name),
+            (EngineError::InvalidHeaderValue(name), Language::Chinese) => format!("无法解析header的值: {}", name),
+            (EngineError::InvalidHeaderValue(name), Language::English) => format!("invalid value for header: {}", name),
+            (EngineError::InvalidCookie(cookie), Language::Chinese) => format!("设置cookie失败: {}", cookie),
+            (EngineError::InvalidCookie(cookie), Language::English) => format!("invalid cookie: {}", cookie),
+            (EngineError::InvalidJson(e), Language::Chinese) => format!("解析json失败: {}", e),
+            (EngineError::InvalidJson(e), Language::English) => format!("invalid json: {}", e),
+            (EngineError::InvalidJsonPath(path), Language::Chinese) => format!("无效的jsonpath: {}", path),
+            (EngineError::InvalidJsonPath(path), Language::English) => format!("invalid jsonpath: {}", path),
+            (EngineError::InvalidThinkTime(e), Language::Chinese) => format!("无效的思考时间: {}", e),
+            (EngineError::InvalidThinkTime(e), Language::English) => format!("invalid think time: {}", e),
+            (EngineError::InvalidTls(e), Language::Chinese) => format!("TLS配置错误: {}", e),
+            (EngineError::InvalidTls(e), Language::English) => format!("invalid tls option: {}", e),
+            (EngineError::InvalidCertificate(path, e), Language::Chinese) => format!("加载证书失败 {}: {}", path, e),
+            (EngineError::InvalidCertificate(path, e), Language::English) => format!("failed to load certificate {}: {}", path, e),
+            (EngineError::InvalidResolve(key, e), Language::Chinese) => format!("无效的固定解析 {}: {}", key, e),
+            (EngineError::InvalidResolve(key, e), Language::English) => format!("invalid resolve entry {}: {}", key, e),
+            (EngineError::InvalidProxy(url, e), Language::Chinese) => format!("无效的代理 {}: {}", url, e),
+            (EngineError::InvalidProxy(url, e), Language::English) => format!("invalid proxy {}: {}", url, e),
+            (EngineError::UnsupportedProxy(url), Language::Chinese) => format!("不支持的代理协议，只支持http、https和socks5代理: {}", url),
+            (EngineError::UnsupportedProxy(url), Language::English) => format!("unsupported proxy scheme, only http, https and socks5 proxies are supported: {}", url),
+            (EngineError::InvalidLocalAddress(ip, e), Language::Chinese) => format!("无法绑定本地地址 {}: {}", ip, e),
+            (EngineError::InvalidLocalAddress(ip, e), Language::English) => format!("cannot bind local address {}: {}", ip, e),
+            (EngineError::UnixSocketUnsupported(path), Language::Chinese) => format!("当前平台不支持unix socket: {}", path),
+            (EngineError::UnixSocketUnsupported(path), Language::English) => format!("unix sockets are not supported on this platform: {}", path),
+            (EngineError::Http2RequiresHttps(url), Language::Chinese) => {
+                format!("http2需要https通过ALPN协商，明文http请使用http2_prior_knowledge: {}", url)
+            }
+            (EngineError::Http2RequiresHttps(url), Language::English) => {
+                format!("http2 is negotiated via ALPN and requires https, use http2_prior_knowledge for plain http: {}", url)
+            }
+            (EngineError::UnknownThresholdMetric(metric), Language::Chinese) => format!("未知的阈值指标: {}", metric),
+            (EngineError::UnknownThresholdMetric(metric), Language::English) => format!("unknown threshold metric: {}", metric),
+            (EngineError::ThresholdWithoutBound(metric), Language::Chinese) => format!("阈值 {} 需要设置max或min", metric),
+            (EngineError::ThresholdWithoutBound(metric), Language::English) => format!("threshold {} needs max or min", metric),
+            (EngineError::UnknownThresholdEndpoint(metric, name), Language::Chinese) => format!("阈值 {} 引用了不存在的接口: {}", metric, name),
+            (EngineError::UnknownThresholdEndpoint(metric, name), Language::English) => format!("threshold {} refers to unknown endpoint: {}", metric, name),
+            (EngineError::UnsupportedPlanFormat(path), Language::Chinese) => format!("不支持的测试计划格式: {}，仅支持yaml/yml/toml", path),
+            (EngineError::UnsupportedPlanFormat(path), Language::English) => format!("unsupported test plan format: {}, only yaml/yml/toml are supported", path),
+            (EngineError::InvalidPlan(location, e), _) => format!("{}: {}", location, e),
+            (EngineError::Located(location, e), _) => format!("{}: {}", location, e.message(language)),
+            (EngineError::NotCurlCommand, Language::Chinese) => "不是curl命令".to_string(),
+            (EngineError::NotCurlCommand, Language::English) => "not a curl command".to_string(),
+            (EngineError::MissingOptionValue(option), Language::Chinese) => format!("curl选项{}缺少参数", option),
+            (EngineError::MissingOptionValue(option), Language::English) => format!("curl option {} requires a value", option),
+            (EngineError::UnsupportedCurlOption(option), Language::Chinese) => format!("不支持的curl选项: {}", option),
+            (EngineError::UnsupportedCurlOption(option), Language::English) => format!("unsupported curl option: {}", option),
+            (EngineError::UnsupportedFileReference(value), Language::Chinese) => format!("不支持从文件读取: {}", value),
+            (EngineError::UnsupportedFileReference(value), Language::English) => format!("reading from files is not supported: {}", value),
+            (EngineError::InvalidFormField(field), Language::Chinese) => format!("无效的表单字段: {}", field),
+            (EngineError::InvalidFormField(field), Language::English) => format!("invalid form field: {}", field),
+            (EngineError::UnsupportedRequestBody(body), Language::Chinese) => format!("不支持的请求体，只支持json和表单: {}", body),
+            (EngineError::UnsupportedRequestBody(body), Language::English) => format!("unsupported request body, only json and form bodies are supported: {}", body),
+            (EngineError::InvalidTimeout(value), Language::Chinese) => format!("无效的超时时间: {}", value),
+            (EngineError::InvalidTimeout(value), Language::English) => format!("invalid timeout: {}", value),
+            (EngineError::MissingUrl, Language::Chinese) => "没有url".to_string(),
+            (EngineError::MissingUrl, Language::English) => "missing url".to_string(),
+            (EngineError::UnclosedQuote(quote), Language::Chinese) => format!("引号{}没有闭合", quote),
+            (EngineError::UnclosedQuote(quote), Language::English) => format!("unclosed quote {}", quote),
+            (EngineError::InvalidDocument(kind, e), Language::Chinese) => format!("解析{}失败: {}", kind, e),
+            (EngineError::InvalidDocument(kind, e), Language::English) => format!("failed to parse {}: {}", kind, e),
+            (EngineError::UnsupportedDocumentVersion(kind, expected, actual), Language::Chinese) => {
+                format!("只支持{} {}，当前版本: {}", kind, expected, actual)
+            }
+            (EngineError::UnsupportedDocumentVersion(kind, expected, actual), Language::English) => {
+                format!("only {} {} is supported, found: {}", kind, expected, actual)
+            }
+            (EngineError::NothingToImport(kind), Language::Chinese) => format!("{}中没有符合条件的请求", kind),
+            (EngineError::NothingToImport(kind), Language::English) => format!("no matching requests in {}", kind),
+            (EngineError::MissingBaseUrl(url), Language::Chinese) if url.is_empty() => "openapi文档没有servers，请设置base_url".to_string(),
+            (EngineError::MissingBaseUrl(url), Language::English) if url.is_empty() => "openapi document has no servers, please set base_url".to_string(),
+            (EngineError::MissingBaseUrl(url), Language::Chinese) => format!("openapi文档的server不是完整地址({})，请设置base_url", url),
+            (EngineError::MissingBaseUrl(url), Language::English) => format!("openapi server is not an absolute url ({}), please set base_url", url),
+            (EngineError::InvalidEndpoint(name, e), Language::Chinese) => format!("接口 {}: {}", name, e.message(language)),
+            (EngineError::InvalidEndpoint(name, e), Language::English) => format!("endpoint {}: {}", name, e.message(language)),
+            (EngineError::Validation(problems), Language::Chinese) => format!("配置校验失败:\n{}", join_messages(problems, language)),
+            (EngineError::Validation(problems), Language::English) => format!("invalid configuration:\n{}", join_messages(problems, language)),
+            (EngineError::InvalidSampleRate(rate), Language::Chinese) => format!("采样率必须在(0, 1]之间: {}", rate),
+            (EngineError::InvalidSampleRate(rate), Language::English) => format!("sample rate must be in (0, 1]: {}", rate),
+            (EngineError::ClientBuild(e), Language::Chinese) => format!("构建http客户端失败: {}", e),
+            (EngineError::ClientBuild(e), Language::English) => format!("failed to build http client: {}", e),
+            (EngineError::Io(path, e), Language::Chinese) => format!("读写文件失败 {}: {}", path, e),
+            (EngineError::Io(path, e), Language::English) => format!("failed to access file {}: {}", path, e),
+        }
+    }
+}
+
+// 每个问题一行
+fn join_messages(problems: &[EngineError], language: Language) -> String {
+    problems.iter().map(|p| format!("  - {}", p.message(language))).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::batch::batch;
+    use crate::models::api_endpoint::ApiEndpoint;
+
+    #[tokio::test]
+    async fn test_invalid_endpoint_error() {
+        let endpoint = ApiEndpoint {
+            name: "无效请求头".to_string(),
+            url: "http://127.0.0.1:1".to_string(),
+            timeout_secs: 1,
+            headers: Some([("bad header".to_string(), "1".to_string())].into_iter().collect()),
+            ..Default::default()
+        };
+        let err = batch(1, 1, false, false, vec![endpoint], None, None).await.unwrap_err();
+        let problem = match &err {
+            EngineError::Validation(problems) => &problems[0],
+            _ => panic!("应该校验失败"),
+        };
+        assert!(matches!(problem, EngineError::InvalidEndpoint(_, e) if matches!(**e, EngineError::InvalidHeaderName(ref name) if name == "bad header")));
+        assert_eq!(problem.message(Language::English), "endpoint 无效请求头: invalid header name: bad header");
+        assert_eq!(err.message(Language::Chinese), "配置校验失败:\n  - 接口 无效请求头: 无法解析header名称: bad header");
+        assert_eq!("EN".parse::<Language>(), Ok(Language::English));
+    }
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+// 请求失败的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    // 连接失败
+    Connect,
+    // TLS握手失败，包括证书校验失败
+    Tls,
+    // 本地端口耗尽，无法再建立到目标地址的连接
+    PortExhausted,
+    // 请求超时
+    Timeout,
+    // 构建或发送请求失败
+    Request,
+    // 读取响应体失败
+    Body,
+    // 重定向失败
+    Redirect,
+    // 状态码错误
+    HttpStatus,
+    // 断言失败
+    Assertion,
+    // 其他错误
+    Other,
+}
+
+impl ErrorKind {
+    pub fn from_reqwest(error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            ErrorKind::Timeout
+        } else if tls_error_message(error).is_some() {
+            ErrorKind::Tls
+        } else if port_exhausted_message(error).is_some() {
+            ErrorKind::PortExhausted
+        } else if error.is_connect() {
+            ErrorKind::Connect
+        } else if error.is_redirect() {
+            ErrorKind::Redirect
+        } else if error.is_body() || error.is_decode() {
+            ErrorKind::Body
+        } else if error.is_request() || error.is_builder() {
+            ErrorKind::Request
+        } else if error.is_status() {
+            ErrorKind::HttpStatus
+        } else {
+            ErrorKind::Other
+        }
+    }
+}
+
+// 错误链中有native-tls的错误时就是TLS握手失败，返回握手失败的原因
+pub(crate) fn tls_error_message(error: &reqwest::Error) -> Option<String> {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = std::error::Error::source(error);
+    while let Some(e) = source {
+        if let Some(tls_error) = e.downcast_ref::<native_tls::Error>() {
+            return Some(tls_error.to_string());
+        }
+        source = e.source();
+    }
+    None
+}
+
+// 错误链中有EADDRNOTAVAIL或EADDRINUSE时就是本地端口耗尽，本地地址在校验时已经确认可以绑定
+pub(crate) fn port_exhausted_message(error: &reqwest::Error) -> Option<String> {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = std::error::Error::source(error);
+    while let Some(e) = source {
+        if let Some(io_error) = e.downcast_ref::<std::io::Error>() {
+            if matches!(io_error.kind(), std::io::ErrorKind::AddrNotAvailable | std::io::ErrorKind::AddrInUse) {
+                return Some(io_error.to_string());
+            }
+        }
+        source = e.source();
+    }
+    None
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+// har导入的可选配置，过滤条件为空时不过滤
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HarImportOption {
+    // 只导入这些域名(包含子域名)的请求
+    pub domains: Vec<String>,
+    // 只导入这些请求方法
+    pub methods: Vec<String>,
+    // 只导入响应类型以这些值开头的请求，例如application/json
+    pub content_types: Vec<String>,
+    // 额外去掉的请求头，默认已经去掉浏览器自动生成和每次都会变化的请求头
+    pub strip_headers: Vec<String>,
+    // 保留录制时的cookie
+    pub keep_cookies: bool,
+}
+
+impl Default for HarImportOption {
+    fn default() -> Self {
+        HarImportOption {
+            domains: Vec::new(),
+            methods: Vec::new(),
+            content_types: Vec::new(),
+            strip_headers: Vec::new(),
+            keep_cookies: true,
+        }
+    }
+}
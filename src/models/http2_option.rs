@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+// HTTP/2配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Http2Option {
+    // 流级别的初始窗口大小(字节)
+    pub initial_stream_window_size: Option<u32>,
+    // 连接级别的初始窗口大小(字节)
+    pub initial_connection_window_size: Option<u32>,
+    // 根据带宽时延积自动调整窗口大小
+    pub adaptive_window: bool,
+    // 发送ping保持连接的间隔(秒)
+    pub keep_alive_interval_secs: Option<u64>,
+}
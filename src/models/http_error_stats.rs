@@ -2,9 +2,11 @@ use std::sync::{Arc};
 use std::collections::HashMap;
 use tokio::sync::Mutex;
 
+// {(状态码, 错误信息, url): 次数}
+pub(crate) type HttpErrorMap = HashMap<(u16, String, String), u32>;
 
 pub struct HttpErrorStats {
-    pub(crate) errors: Arc<Mutex<HashMap<(u16, String, String), u32>>>,
+    pub(crate) errors: Arc<Mutex<HttpErrorMap>>,
 }
 
 impl HttpErrorStats {
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+// 接口使用的http协议版本
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpVersion {
+    // 按目标支持的协议协商，https通过ALPN协商，http使用HTTP/1.1
+    #[default]
+    Auto,
+    // 只使用HTTP/1.1
+    Http1,
+    // 通过ALPN协商HTTP/2，协商失败时请求报错，只支持https
+    Http2,
+    // 不协商直接使用HTTP/2，明文http时即h2c
+    Http2PriorKnowledge,
+}
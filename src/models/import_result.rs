@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+use crate::models::api_endpoint::ApiEndpoint;
+
+// 从接口文档或者请求集合导入的接口
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ImportResult {
+    pub endpoints: Vec<ApiEndpoint>,
+    // 导入时跳过或者无法完整转换的内容
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+// 接口的选择方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MixMode {
+    // 按权重拆分并发数，每个并发只请求一个接口
+    #[default]
+    Partition,
+    // 每次迭代按权重随机选择接口
+    WeightedRandom,
+    // 每次迭代按权重轮询选择接口，所有并发共用一个轮询序列，请求比例和权重完全一致
+    WeightedRoundRobin,
+    // 每个并发按接口列表的顺序依次请求，请求完最后一个接口后从头开始，忽略权重，用于执行导入的场景
+    // 走完一遍接口列表算一次迭代，iterations_per_vu和pacing_ms按整个场景计算
+    Sequential,
+}
@@ -4,3 +4,24 @@ pub mod assert_option;
 pub mod assert_error_stats;
 pub mod api_endpoint;
 pub mod step_option;
+pub mod batch_option;
+pub mod error_kind;
+pub mod request_log_option;
+pub mod request_record;
+pub mod compare_result;
+pub mod threshold;
+pub mod test_plan;
+pub mod har_import_option;
+pub mod scenario;
+pub mod openapi_import_option;
+pub mod import_result;
+pub mod engine_error;
+pub mod mix_mode;
+pub mod think_time;
+pub mod end_reason;
+pub mod connection_option;
+pub mod http_version;
+pub mod http2_option;
+pub mod tls_option;
+pub mod dns_option;
+pub mod proxy_option;
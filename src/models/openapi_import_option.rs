@@ -0,0 +1,30 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+// openapi导入的可选配置
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenApiImportOption {
+    // 覆盖文档中servers的地址
+    pub base_url: Option<String>,
+    // 路径参数的取值，优先于文档中的示例
+    pub path_params: HashMap<String, String>,
+    // 只导入带有这些tag的接口
+    pub tags: Vec<String>,
+    // 按文档中成功响应的状态码设置expected_status
+    pub expect_status: bool,
+    // 按响应schema中固定的值(const或只有一个值的enum)生成断言
+    pub generate_asserts: bool,
+}
+
+impl Default for OpenApiImportOption {
+    fn default() -> Self {
+        OpenApiImportOption {
+            base_url: None,
+            path_params: HashMap::new(),
+            tags: Vec::new(),
+            expect_status: true,
+            generate_asserts: false,
+        }
+    }
+}
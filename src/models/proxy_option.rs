@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+// 使用代理的请求
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyTarget {
+    // http和https请求都使用代理
+    #[default]
+    All,
+    // 只代理http请求
+    Http,
+    // 只代理https请求
+    Https,
+}
+
+// 代理配置，多个代理时按顺序使用第一个匹配的代理
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProxyOption {
+    // 代理地址，例如 http://127.0.0.1:8888、socks5://127.0.0.1:1080，也可以在地址中带上用户名和密码
+    // socks5在本地解析域名，socks5h由代理解析域名，经过socks5代理的连接不使用固定解析和本地地址
+    pub url: String,
+    pub target: ProxyTarget,
+    // 代理认证，http代理使用basic认证，socks5代理使用用户名密码认证
+    pub username: Option<String>,
+    pub password: Option<String>,
+    // 不使用代理的主机，规则和NO_PROXY环境变量一致，例如 "localhost"、".example.com"、"10.0.0.0/8"
+    pub no_proxy: Vec<String>,
+}
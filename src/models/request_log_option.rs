@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+// 单请求日志配置
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequestLogOption {
+    // jsonl文件路径，每个请求一行
+    pub path: String,
+    // 采样率，取值(0, 1]，1表示记录全部请求
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f64,
+    // 只记录失败的请求
+    #[serde(default)]
+    pub errors_only: bool,
+    // 如果设置了，将采样到的请求(包括请求头、请求体、响应头和响应体)导出为HAR文件
+    pub har_path: Option<String>,
+}
+
+fn default_sample_rate() -> f64 {
+    1.0
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use crate::models::error_kind::ErrorKind;
+
+// 单个请求的记录
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestRecord {
+    // 请求开始时间(毫秒时间戳)
+    pub timestamp: u128,
+    pub name: String,
+    pub url: String,
+    pub method: String,
+    // 没有收到响应时为空
+    pub status: Option<u16>,
+    // 响应的协议版本，没有收到响应时为空
+    #[serde(default)]
+    pub http_version: Option<String>,
+    pub latency_ms: u64,
+    // 响应体大小
+    pub bytes: u64,
+    pub error_kind: Option<ErrorKind>,
+    pub error_message: Option<String>,
+    pub assertion_failures: Vec<String>,
+    // 导出HAR时才会采集的请求详情，写入请求日志后可以用export_har重新导出
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<RequestDetail>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RequestDetail {
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: Option<String>,
+    pub request_content_type: Option<String>,
+    pub http_version: String,
+    pub response_headers: Vec<(String, String)>,
+    pub response_body: Option<String>,
+    pub response_content_type: Option<String>,
+}
+
+impl RequestRecord {
+    pub fn is_error(&self) -> bool {
+        self.error_kind.is_some()
+    }
+}
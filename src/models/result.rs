@@ -1,5 +1,7 @@
 use std::collections::HashMap;
-use serde::{Deserialize, Serialize};
+use std::hash::Hash;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::models::end_reason::EndReason;
 
 #[derive(Debug)]
 #[derive(Clone, Serialize, Deserialize)]
@@ -16,9 +18,20 @@ pub struct TestResult {
     pub err_count: i32,
     pub total_data_kb: f64,
     pub throughput_per_second_kb: f64,
+    #[serde(with = "error_map")]
     pub http_errors: HashMap<(u16, String, String), u32>,
     pub timestamp: u128,
-    pub assert_errors: HashMap<(String, String), u32>
+    #[serde(with = "error_map")]
+    pub assert_errors: HashMap<(String, String), u32>,
+    // 压测结束的原因
+    #[serde(default)]
+    pub end_reason: EndReason,
+    // 是否是预热期间的周期快照
+    #[serde(default)]
+    pub warm_up: bool,
+    // 优雅停止时被中断的请求数，不计入total_requests
+    #[serde(default)]
+    pub interrupted_requests: u64,
 }
 
 #[derive(Debug)]
@@ -37,11 +50,22 @@ pub struct BatchResult {
     pub err_count: i32,
     pub total_data_kb: f64,
     pub throughput_per_second_kb: f64,
+    #[serde(with = "error_map")]
     pub http_errors: HashMap<(u16, String, String), u32>,
     pub timestamp: u128,
+    #[serde(with = "error_map")]
     pub assert_errors: HashMap<(String, String), u32>,
     pub total_concurrent_number: i32,
-    pub api_results: Vec<ApiResult>
+    pub api_results: Vec<ApiResult>,
+    // 压测结束的原因
+    #[serde(default)]
+    pub end_reason: EndReason,
+    // 是否是预热期间的周期快照
+    #[serde(default)]
+    pub warm_up: bool,
+    // 优雅停止时被中断的请求数，不计入total_requests
+    #[serde(default)]
+    pub interrupted_requests: u64,
 }
 
 #[derive(Debug)]
@@ -63,6 +87,18 @@ pub struct ApiResult{
     pub total_data_kb: f64,
     pub throughput_per_second_kb: f64,
     pub concurrent_number: i32,
+    // 优雅停止时被中断的请求数，不计入total_requests
+    #[serde(default)]
+    pub interrupted_requests: u64,
+    // 收到响应时新建的连接数
+    #[serde(default)]
+    pub connections_opened: u64,
+    // 收到响应时复用已有连接的次数
+    #[serde(default)]
+    pub connections_reused: u64,
+    // 每种协议版本的响应数，例如{"HTTP/2.0": 10}
+    #[serde(default)]
+    pub http_versions: HashMap<String, u64>,
 }
 
 impl ApiResult {
@@ -84,6 +120,60 @@ impl ApiResult {
             total_data_kb: 0.0,
             throughput_per_second_kb: 0.0,
             concurrent_number: 0,
+            interrupted_requests: 0,
+            connections_opened: 0,
+            connections_reused: 0,
+            http_versions: HashMap::new(),
         }
     }
 }
+
+// run只有一个接口，汇总结果就是该接口的结果
+impl From<BatchResult> for TestResult {
+    fn from(result: BatchResult) -> Self {
+        TestResult {
+            total_duration: result.total_duration,
+            success_rate: result.success_rate,
+            median_response_time: result.median_response_time,
+            response_time_95: result.response_time_95,
+            response_time_99: result.response_time_99,
+            total_requests: result.total_requests as i32,
+            rps: result.rps,
+            max_response_time: result.max_response_time,
+            min_response_time: result.min_response_time,
+            err_count: result.err_count,
+            total_data_kb: result.total_data_kb,
+            throughput_per_second_kb: result.throughput_per_second_kb,
+            http_errors: result.http_errors,
+            timestamp: result.timestamp,
+            assert_errors: result.assert_errors,
+            end_reason: result.end_reason,
+            warm_up: result.warm_up,
+            interrupted_requests: result.interrupted_requests,
+        }
+    }
+}
+
+impl Default for ApiResult {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 错误统计的key是元组，json不支持非字符串的key，序列化成[[key, 次数], ...]的形式
+mod error_map {
+    use super::*;
+
+    pub fn serialize<K: Serialize, S: Serializer>(map: &HashMap<K, u32>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, K, D>(deserializer: D) -> Result<HashMap<K, u32>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        D: Deserializer<'de>,
+    {
+        let entries: Vec<(K, u32)> = Vec::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use crate::models::api_endpoint::ApiEndpoint;
+use crate::models::think_time::ThinkTime;
+
+// 按顺序执行的场景，从录制文件导入
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub steps: Vec<ScenarioStep>,
+    // 导入时跳过或者无法完整转换的内容
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioStep {
+    pub endpoint: ApiEndpoint,
+    // 这一步完成后到下一步开始前的等待时间(毫秒)
+    #[serde(default)]
+    pub think_time_ms: u64,
+}
+
+impl Scenario {
+    // 步骤的等待时间作为接口的固定思考时间，接口已经设置了思考时间时保持不变；
+    // 配合MixMode::Sequential使用时每个并发按步骤顺序执行
+    pub fn endpoints(&self) -> Vec<ApiEndpoint> {
+        self.steps
+            .iter()
+            .map(|step| {
+                let mut endpoint = step.endpoint.clone();
+                if endpoint.think_time.is_none() && step.think_time_ms > 0 {
+                    endpoint.think_time = Some(ThinkTime::Fixed { ms: step.think_time_ms });
+                }
+                endpoint
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use crate::models::api_endpoint::ApiEndpoint;
+use crate::models::batch_option::BatchOption;
+use crate::models::step_option::StepOption;
+use crate::models::threshold::Threshold;
+
+// 测试计划，字段与batch的参数一一对应，可以从yaml或toml文件加载
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestPlan {
+    pub test_duration_secs: u64,
+    pub concurrent_requests: usize,
+    #[serde(default)]
+    pub verbose: bool,
+    #[serde(default)]
+    pub should_prevent: bool,
+    #[serde(alias = "endpoints")]
+    pub api_endpoints: Vec<ApiEndpoint>,
+    pub step_option: Option<StepOption>,
+    pub batch_option: Option<BatchOption>,
+    // 压测结束后检查的阈值
+    #[serde(default)]
+    pub thresholds: Vec<Threshold>,
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+// 请求完成后到下一次请求前的等待时间(毫秒)，不计入响应时间
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "distribution", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ThinkTime {
+    // 固定时间
+    Fixed { ms: u64 },
+    // [min_ms, max_ms]之间均匀分布
+    Uniform { min_ms: u64, max_ms: u64 },
+    // 正态分布，小于0时按0处理
+    Normal { mean_ms: f64, std_dev_ms: f64 },
+    // 指数分布
+    Exponential { mean_ms: f64 },
+}
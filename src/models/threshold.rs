@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+// 阈值，例如p95不超过500ms、错误率不超过1%
+// metric与结果中的字段同名，例如response_time_95、error_rate、rps
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Threshold {
+    pub metric: String,
+    // 为空时对汇总结果判断，否则对指定名称的接口判断
+    pub endpoint: Option<String>,
+    pub max: Option<f64>,
+    pub min: Option<f64>,
+}
+
+// 未通过的阈值
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThresholdFailure {
+    pub threshold: Threshold,
+    // 找不到接口或指标时为空
+    pub actual: Option<f64>,
+    pub message: String,
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+// TLS协议版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum TlsVersion {
+    #[serde(rename = "1.0")]
+    Tls10,
+    #[serde(rename = "1.1")]
+    Tls11,
+    #[serde(rename = "1.2")]
+    Tls12,
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+// TLS配置，证书和私钥都是文件路径
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsOption {
+    // 额外信任的根证书，PEM文件中可以有多个证书
+    pub ca_cert: Option<String>,
+    // 客户端证书(PEM)，需要和client_key一起使用
+    pub client_cert: Option<String>,
+    // 客户端私钥(PKCS#8 PEM)
+    pub client_key: Option<String>,
+    // 客户端证书和私钥(PKCS#12)，不能和client_cert同时使用
+    pub pkcs12: Option<String>,
+    pub pkcs12_password: Option<String>,
+    // 不校验服务端证书和主机名，只用于测试环境
+    pub danger_accept_invalid_certs: bool,
+    // 覆盖SNI和证书校验使用的主机名，连接仍然发送到url中的地址，Host请求头也会变成这个名称
+    pub server_name: Option<String>,
+    pub min_version: Option<TlsVersion>,
+    pub max_version: Option<TlsVersion>,
+}
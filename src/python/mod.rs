@@ -0,0 +1,199 @@
+mod py_result;
+mod result_iter;
+
+use lazy_static::lazy_static;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use serde::de::DeserializeOwned;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::core::batch::batch as run_batch;
+use crate::core::execute;
+use crate::core::validate::validate_batch;
+use crate::models::api_endpoint::ApiEndpoint;
+use crate::models::assert_option::AssertOption;
+use crate::models::batch_option::BatchOption;
+use crate::models::engine_error::{self, EngineError, Language};
+use crate::models::step_option::StepOption;
+use crate::python::py_result::{PyApiResult, PyBatchResult, PyTestResult};
+use crate::python::result_iter::ResultIter;
+
+lazy_static! {
+    // 所有压测共用一个多线程运行时
+    static ref RUNTIME: Runtime = Builder::new_multi_thread().enable_all().build().expect("创建tokio运行时失败");
+}
+
+// 单接口压测，压测期间释放GIL，batch_option使用dict，字段与测试计划一致
+#[pyfunction]
+#[pyo3(signature = (
+    url,
+    test_duration_secs,
+    concurrent_requests,
+    timeout_secs = 0,
+    verbose = false,
+    method = "GET",
+    json_str = None,
+    form_data_str = None,
+    headers = None,
+    cookie = None,
+    should_prevent = false,
+    assert_options = None,
+    batch_option = None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn run(
+    py: Python<'_>,
+    url: &str,
+    test_duration_secs: u64,
+    concurrent_requests: i32,
+    timeout_secs: u64,
+    verbose: bool,
+    method: &str,
+    json_str: Option<String>,
+    form_data_str: Option<String>,
+    headers: Option<Vec<String>>,
+    cookie: Option<String>,
+    should_prevent: bool,
+    assert_options: Option<Bound<'_, PyAny>>,
+    batch_option: Option<Bound<'_, PyAny>>,
+) -> PyResult<PyTestResult> {
+    let assert_options: Option<Vec<AssertOption>> = from_py(py, assert_options, "assert_options")?;
+    let batch_option: Option<BatchOption> = from_py(py, batch_option, "batch_option")?;
+    let result = py.allow_threads(|| {
+        RUNTIME.block_on(execute::run(
+            url,
+            test_duration_secs,
+            concurrent_requests,
+            timeout_secs,
+            verbose,
+            method,
+            json_str,
+            form_data_str,
+            headers,
+            cookie,
+            should_prevent,
+            assert_options,
+            batch_option,
+        ))
+    });
+    result.map(PyTestResult::from).map_err(engine_error)
+}
+
+// 多接口压测，api_endpoints、step_option和batch_option使用dict，字段与测试计划一致
+#[pyfunction]
+#[pyo3(signature = (
+    test_duration_secs,
+    concurrent_requests,
+    api_endpoints,
+    verbose = false,
+    should_prevent = false,
+    step_option = None,
+    batch_option = None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn batch(
+    py: Python<'_>,
+    test_duration_secs: u64,
+    concurrent_requests: usize,
+    api_endpoints: Bound<'_, PyAny>,
+    verbose: bool,
+    should_prevent: bool,
+    step_option: Option<Bound<'_, PyAny>>,
+    batch_option: Option<Bound<'_, PyAny>>,
+) -> PyResult<PyBatchResult> {
+    let api_endpoints: Vec<ApiEndpoint> = from_py(py, Some(api_endpoints), "api_endpoints")?.unwrap_or_default();
+    let step_option: Option<StepOption> = from_py(py, step_option, "step_option")?;
+    let batch_option: Option<BatchOption> = from_py(py, batch_option, "batch_option")?;
+    let result = py.allow_threads(|| {
+        RUNTIME.block_on(run_batch(
+            test_duration_secs,
+            concurrent_requests,
+            verbose,
+            should_prevent,
+            api_endpoints,
+            step_option,
+            batch_option,
+        ))
+    });
+    result.map(PyBatchResult::from).map_err(engine_error)
+}
+
+// 只校验batch的参数，返回全部问题，没有问题时返回空列表
+#[pyfunction]
+#[pyo3(signature = (
+    test_duration_secs,
+    concurrent_requests,
+    api_endpoints,
+    step_option = None,
+    batch_option = None,
+))]
+fn dry_run(
+    py: Python<'_>,
+    test_duration_secs: u64,
+    concurrent_requests: usize,
+    api_endpoints: Bound<'_, PyAny>,
+    step_option: Option<Bound<'_, PyAny>>,
+    batch_option: Option<Bound<'_, PyAny>>,
+) -> PyResult<Vec<String>> {
+    let api_endpoints: Vec<ApiEndpoint> = from_py(py, Some(api_endpoints), "api_endpoints")?.unwrap_or_default();
+    let step_option: Option<StepOption> = from_py(py, step_option, "step_option")?;
+    let batch_option: Option<BatchOption> = from_py(py, batch_option, "batch_option")?;
+    let problems = validate_batch(test_duration_secs, concurrent_requests, &api_endpoints, step_option.as_ref(), batch_option.as_ref());
+    Ok(problems.iter().map(|p| p.to_string()).collect())
+}
+
+// 迭代run的周期结果，需要在另一个线程里调用run
+#[pyfunction]
+fn run_listen_iter() -> ResultIter {
+    ResultIter::single()
+}
+
+// 迭代batch的周期结果，需要在另一个线程里调用batch
+#[pyfunction]
+fn batch_listen_iter() -> ResultIter {
+    ResultIter::batch()
+}
+
+// 设置错误信息的语言，支持zh/en
+#[pyfunction]
+fn set_language(language: &str) -> PyResult<()> {
+    let language: Language = language.parse().map_err(PyValueError::new_err)?;
+    engine_error::set_language(language);
+    Ok(())
+}
+
+// 配置错误抛ValueError，其他错误抛RuntimeError
+fn engine_error(e: EngineError) -> PyErr {
+    match e {
+        EngineError::ClientBuild(_) | EngineError::Io(..) => PyRuntimeError::new_err(e.to_string()),
+        _ => PyValueError::new_err(e.to_string()),
+    }
+}
+
+// python对象先转成json，再按模型反序列化
+fn from_py<T: DeserializeOwned>(py: Python<'_>, value: Option<Bound<'_, PyAny>>, name: &str) -> PyResult<Option<T>> {
+    let value = match value {
+        None => return Ok(None),
+        Some(value) if value.is_none() => return Ok(None),
+        Some(value) => value,
+    };
+    let json: String = py.import("json")?.call_method1("dumps", (value,))?.extract()?;
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| PyValueError::new_err(format!("{}参数错误: {}", name, e)))
+}
+
+#[pymodule]
+fn atomic_bomb_engine(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(run, m)?)?;
+    m.add_function(wrap_pyfunction!(batch, m)?)?;
+    m.add_function(wrap_pyfunction!(dry_run, m)?)?;
+    m.add_function(wrap_pyfunction!(run_listen_iter, m)?)?;
+    m.add_function(wrap_pyfunction!(batch_listen_iter, m)?)?;
+    m.add_function(wrap_pyfunction!(set_language, m)?)?;
+    m.add_class::<PyTestResult>()?;
+    m.add_class::<PyBatchResult>()?;
+    m.add_class::<PyApiResult>()?;
+    m.add_class::<ResultIter>()?;
+    Ok(())
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use pyo3::prelude::*;
+use serde::Serialize;
+
+use crate::models::result::{ApiResult, BatchResult, TestResult};
+
+// run的结果
+#[pyclass(name = "TestResult", module = "atomic_bomb_engine", frozen)]
+pub struct PyTestResult {
+    #[pyo3(get)]
+    total_duration: f64,
+    #[pyo3(get)]
+    success_rate: f64,
+    #[pyo3(get)]
+    median_response_time: u64,
+    #[pyo3(get)]
+    response_time_95: u64,
+    #[pyo3(get)]
+    response_time_99: u64,
+    #[pyo3(get)]
+    total_requests: i32,
+    #[pyo3(get)]
+    rps: f64,
+    #[pyo3(get)]
+    max_response_time: u64,
+    #[pyo3(get)]
+    min_response_time: u64,
+    #[pyo3(get)]
+    err_count: i32,
+    #[pyo3(get)]
+    total_data_kb: f64,
+    #[pyo3(get)]
+    throughput_per_second_kb: f64,
+    // {(状态码, 错误信息, url): 次数}
+    #[pyo3(get)]
+    http_errors: HashMap<(u16, String, String), u32>,
+    #[pyo3(get)]
+    timestamp: u128,
+    // {(url, 错误信息): 次数}
+    #[pyo3(get)]
+    assert_errors: HashMap<(String, String), u32>,
+    // 结束原因: duration/max_requests/iterations/endpoint_budget/stopped
+    #[pyo3(get)]
+    end_reason: &'static str,
+    // 是否是预热期间的周期快照
+    #[pyo3(get)]
+    warm_up: bool,
+    #[pyo3(get)]
+    interrupted_requests: u64,
+    raw: TestResult,
+}
+
+impl From<TestResult> for PyTestResult {
+    fn from(result: TestResult) -> Self {
+        PyTestResult {
+            total_duration: result.total_duration,
+            success_rate: result.success_rate,
+            median_response_time: result.median_response_time,
+            response_time_95: result.response_time_95,
+            response_time_99: result.response_time_99,
+            total_requests: result.total_requests,
+            rps: result.rps,
+            max_response_time: result.max_response_time,
+            min_response_time: result.min_response_time,
+            err_count: result.err_count,
+            total_data_kb: result.total_data_kb,
+            throughput_per_second_kb: result.throughput_per_second_kb,
+            http_errors: result.http_errors.clone(),
+            timestamp: result.timestamp,
+            assert_errors: result.assert_errors.clone(),
+            end_reason: result.end_reason.as_str(),
+            warm_up: result.warm_up,
+            interrupted_requests: result.interrupted_requests,
+            raw: result,
+        }
+    }
+}
+
+#[pymethods]
+impl PyTestResult {
+    // 转换成dict，错误统计是[[key, 次数], ...]的形式，与json报告一致
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_dict(py, &self.raw)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "TestResult(total_requests={}, rps={:.1}, success_rate={:.2}, response_time_95={})",
+            self.total_requests, self.rps, self.success_rate, self.response_time_95
+        )
+    }
+}
+
+// batch的结果
+#[pyclass(name = "BatchResult", module = "atomic_bomb_engine", frozen)]
+pub struct PyBatchResult {
+    #[pyo3(get)]
+    total_duration: f64,
+    #[pyo3(get)]
+    success_rate: f64,
+    #[pyo3(get)]
+    error_rate: f64,
+    #[pyo3(get)]
+    median_response_time: u64,
+    #[pyo3(get)]
+    response_time_95: u64,
+    #[pyo3(get)]
+    response_time_99: u64,
+    #[pyo3(get)]
+    total_requests: u64,
+    #[pyo3(get)]
+    rps: f64,
+    #[pyo3(get)]
+    max_response_time: u64,
+    #[pyo3(get)]
+    min_response_time: u64,
+    #[pyo3(get)]
+    err_count: i32,
+    #[pyo3(get)]
+    total_data_kb: f64,
+    #[pyo3(get)]
+    throughput_per_second_kb: f64,
+    // {(状态码, 错误信息, url): 次数}
+    #[pyo3(get)]
+    http_errors: HashMap<(u16, String, String), u32>,
+    #[pyo3(get)]
+    timestamp: u128,
+    // {(url, 错误信息): 次数}
+    #[pyo3(get)]
+    assert_errors: HashMap<(String, String), u32>,
+    // 结束原因: duration/max_requests/iterations/endpoint_budget/stopped
+    #[pyo3(get)]
+    end_reason: &'static str,
+    // 是否是预热期间的周期快照
+    #[pyo3(get)]
+    warm_up: bool,
+    #[pyo3(get)]
+    interrupted_requests: u64,
+    #[pyo3(get)]
+    total_concurrent_number: i32,
+    #[pyo3(get)]
+    api_results: Vec<PyApiResult>,
+    raw: BatchResult,
+}
+
+impl From<BatchResult> for PyBatchResult {
+    fn from(result: BatchResult) -> Self {
+        PyBatchResult {
+            total_duration: result.total_duration,
+            success_rate: result.success_rate,
+            error_rate: result.error_rate,
+            median_response_time: result.median_response_time,
+            response_time_95: result.response_time_95,
+            response_time_99: result.response_time_99,
+            total_requests: result.total_requests,
+            rps: result.rps,
+            max_response_time: result.max_response_time,
+            min_response_time: result.min_response_time,
+            err_count: result.err_count,
+            total_data_kb: result.total_data_kb,
+            throughput_per_second_kb: result.throughput_per_second_kb,
+            http_errors: result.http_errors.clone(),
+            timestamp: result.timestamp,
+            assert_errors: result.assert_errors.clone(),
+            end_reason: result.end_reason.as_str(),
+            warm_up: result.warm_up,
+            interrupted_requests: result.interrupted_requests,
+            total_concurrent_number: result.total_concurrent_number,
+            api_results: result.api_results.iter().cloned().map(PyApiResult::from).collect(),
+            raw: result,
+        }
+    }
+}
+
+#[pymethods]
+impl PyBatchResult {
+    // 转换成dict，错误统计是[[key, 次数], ...]的形式，与json报告一致
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_dict(py, &self.raw)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "BatchResult(total_requests={}, rps={:.1}, error_rate={:.2}, response_time_95={}, api_results={})",
+            self.total_requests, self.rps, self.error_rate, self.response_time_95, self.api_results.len()
+        )
+    }
+}
+
+// batch中单个接口的结果
+#[pyclass(name = "ApiResult", module = "atomic_bomb_engine", frozen)]
+#[derive(Clone)]
+pub struct PyApiResult {
+    #[pyo3(get)]
+    name: String,
+    #[pyo3(get)]
+    url: String,
+    #[pyo3(get)]
+    method: String,
+    #[pyo3(get)]
+    success_rate: f64,
+    #[pyo3(get)]
+    error_rate: f64,
+    #[pyo3(get)]
+    median_response_time: u64,
+    #[pyo3(get)]
+    response_time_95: u64,
+    #[pyo3(get)]
+    response_time_99: u64,
+    #[pyo3(get)]
+    total_requests: u64,
+    #[pyo3(get)]
+    rps: f64,
+    #[pyo3(get)]
+    max_response_time: u64,
+    #[pyo3(get)]
+    min_response_time: u64,
+    #[pyo3(get)]
+    err_count: i32,
+    #[pyo3(get)]
+    total_data_kb: f64,
+    #[pyo3(get)]
+    throughput_per_second_kb: f64,
+    #[pyo3(get)]
+    concurrent_number: i32,
+    #[pyo3(get)]
+    interrupted_requests: u64,
+    #[pyo3(get)]
+    connections_opened: u64,
+    #[pyo3(get)]
+    connections_reused: u64,
+    // {协议版本: 响应数}
+    #[pyo3(get)]
+    http_versions: HashMap<String, u64>,
+    raw: ApiResult,
+}
+
+impl From<ApiResult> for PyApiResult {
+    fn from(result: ApiResult) -> Self {
+        PyApiResult {
+            name: result.name.clone(),
+            url: result.url.clone(),
+            method: result.method.clone(),
+            success_rate: result.success_rate,
+            error_rate: result.error_rate,
+            median_response_time: result.median_response_time,
+            response_time_95: result.response_time_95,
+            response_time_99: result.response_time_99,
+            total_requests: result.total_requests,
+            rps: result.rps,
+            max_response_time: result.max_response_time,
+            min_response_time: result.min_response_time,
+            err_count: result.err_count,
+            total_data_kb: result.total_data_kb,
+            throughput_per_second_kb: result.throughput_per_second_kb,
+            concurrent_number: result.concurrent_number,
+            interrupted_requests: result.interrupted_requests,
+            connections_opened: result.connections_opened,
+            connections_reused: result.connections_reused,
+            http_versions: result.http_versions.clone(),
+            raw: result,
+        }
+    }
+}
+
+#[pymethods]
+impl PyApiResult {
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_dict(py, &self.raw)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ApiResult(name={:?}, total_requests={}, rps={:.1}, error_rate={:.2}, response_time_95={})",
+            self.name, self.total_requests, self.rps, self.error_rate, self.response_time_95
+        )
+    }
+}
+
+// 先序列化成json，再用python的json模块解析
+fn to_dict<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
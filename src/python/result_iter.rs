@@ -0,0 +1,87 @@
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use pyo3::prelude::*;
+
+use crate::core::status_share::{now_millis, RESULTS_QUEUE, RESULTS_SHOULD_STOP, RESULTS_STOPPED_AT, SINGLE_RESULT_QUEUE, SINGLE_SHOULD_STOP, SINGLE_STOPPED_AT};
+use crate::python::py_result::{PyBatchResult, PyTestResult};
+
+// 轮询周期结果的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy)]
+enum ResultKind {
+    Single,
+    Batch,
+}
+
+// 周期结果的迭代器，有新结果时返回，压测结束后停止迭代
+// 只返回创建之后产生的结果，避免读到上一次压测残留的数据
+#[pyclass(module = "atomic_bomb_engine")]
+pub struct ResultIter {
+    kind: ResultKind,
+    last_timestamp: u128,
+    // 创建的时间，之后结束的压测即使没有产生周期结果也停止迭代
+    created_at: u64,
+    received: bool,
+}
+
+impl ResultIter {
+    pub(crate) fn single() -> Self {
+        Self::new(ResultKind::Single)
+    }
+
+    pub(crate) fn batch() -> Self {
+        Self::new(ResultKind::Batch)
+    }
+
+    fn new(kind: ResultKind) -> Self {
+        let now = now_millis();
+        ResultIter { kind, last_timestamp: now as u128, created_at: now, received: false }
+    }
+}
+
+#[pymethods]
+impl ResultIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        loop {
+            let last_timestamp = self.last_timestamp;
+            let kind = self.kind;
+            // 读取结果时释放GIL
+            let (latest, stopped, stopped_at) = py.allow_threads(|| match kind {
+                ResultKind::Single => {
+                    let latest = SINGLE_RESULT_QUEUE.blocking_lock().back().filter(|r| r.timestamp > last_timestamp).cloned();
+                    let stopped = *SINGLE_SHOULD_STOP.blocking_lock();
+                    (latest.map(|r| (r.timestamp, Latest::Single(r))), stopped, SINGLE_STOPPED_AT.load(Ordering::Relaxed))
+                }
+                ResultKind::Batch => {
+                    let latest = RESULTS_QUEUE.blocking_lock().back().filter(|r| r.timestamp > last_timestamp).cloned();
+                    let stopped = *RESULTS_SHOULD_STOP.blocking_lock();
+                    (latest.map(|r| (r.timestamp, Latest::Batch(r))), stopped, RESULTS_STOPPED_AT.load(Ordering::Relaxed))
+                }
+            });
+            if let Some((timestamp, latest)) = latest {
+                self.last_timestamp = timestamp;
+                self.received = true;
+                return Ok(Some(match latest {
+                    Latest::Single(r) => PyTestResult::from(r).into_pyobject(py)?.into_any().unbind(),
+                    Latest::Batch(r) => PyBatchResult::from(r).into_pyobject(py)?.into_any().unbind(),
+                }));
+            }
+            if stopped && (self.received || stopped_at >= self.created_at) {
+                return Ok(None);
+            }
+            py.allow_threads(|| std::thread::sleep(POLL_INTERVAL));
+            // 响应ctrl+c
+            py.check_signals()?;
+        }
+    }
+}
+
+enum Latest {
+    Single(crate::models::result::TestResult),
+    Batch(crate::models::result::BatchResult),
+}
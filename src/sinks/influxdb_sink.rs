@@ -0,0 +1,134 @@
+use std::time::Duration;
+use anyhow::Context;
+use futures::future::BoxFuture;
+use reqwest::Client;
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+
+use crate::models::result::BatchResult;
+use crate::sinks::result_sink::{api_metrics, batch_metrics, ResultSink, SinkStage};
+
+// 通过http写入InfluxDB行协议
+// write_url为完整的写入地址，例如:
+// v2: http://127.0.0.1:8086/api/v2/write?org=my-org&bucket=my-bucket
+// v1: http://127.0.0.1:8086/write?db=my-db
+// 时间戳使用纳秒精度，两个版本的默认精度都可以直接写入
+pub struct InfluxDbSink {
+    client: Client,
+    write_url: String,
+    token: Option<String>,
+    measurement: String,
+}
+
+impl InfluxDbSink {
+    pub fn new(write_url: &str, token: Option<String>, measurement: &str) -> anyhow::Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .context("构建InfluxDB http客户端失败")?;
+        Ok(InfluxDbSink {
+            client,
+            write_url: write_url.to_string(),
+            token,
+            measurement: measurement.to_string(),
+        })
+    }
+
+    // 生成行协议文本，汇总数据一行，每个接口一行
+    pub(crate) fn lines(&self, stage: SinkStage, result: &BatchResult) -> String {
+        let timestamp_ns = result.timestamp * 1_000_000;
+        let measurement = escape_measurement(&self.measurement);
+        let mut lines = Vec::new();
+        lines.push(format!(
+            "{},scope=total,stage={} {} {}",
+            measurement,
+            stage.as_str(),
+            fields(batch_metrics(result)),
+            timestamp_ns
+        ));
+        for api_result in &result.api_results {
+            let metrics = api_metrics(api_result);
+            if metrics.is_empty() {
+                continue;
+            }
+            lines.push(format!(
+                "{},scope=api,stage={},name={},method={} {} {}",
+                measurement,
+                stage.as_str(),
+                escape_tag(&api_result.name),
+                escape_tag(&api_result.method),
+                fields(metrics),
+                timestamp_ns
+            ));
+        }
+        lines.join("\n")
+    }
+
+    async fn write(&self, stage: SinkStage, result: &BatchResult) -> anyhow::Result<()> {
+        let mut request = self.client
+            .post(&self.write_url)
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(self.lines(stage, result));
+        if let Some(token) = &self.token {
+            request = request.header(AUTHORIZATION, format!("Token {}", token));
+        }
+        let response = request.send().await.context("写入InfluxDB失败")?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("写入InfluxDB失败，状态码: {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+impl ResultSink for InfluxDbSink {
+    fn on_interval<'a>(&'a self, result: &'a BatchResult) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(self.write(SinkStage::interval(result), result))
+    }
+
+    fn on_finish<'a>(&'a self, result: &'a BatchResult) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(self.write(SinkStage::Final, result))
+    }
+}
+
+fn fields(metrics: Vec<(&'static str, f64)>) -> String {
+    metrics
+        .into_iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// 行协议中measurement需要转义逗号和空格
+fn escape_measurement(value: &str) -> String {
+    value.replace(',', "\\,").replace(' ', "\\ ")
+}
+
+// 行协议中tag需要转义逗号、等号和空格
+fn escape_tag(value: &str) -> String {
+    if value.is_empty() {
+        return "-".to_string();
+    }
+    value.replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_server::spawn_http_server;
+    use crate::sinks::result_sink::sample_batch_result;
+
+    #[tokio::test]
+    async fn test_influxdb_sink() {
+        let (addr, mut requests) = spawn_http_server(204, "").await;
+        let url = format!("http://{}/api/v2/write?org=test&bucket=test", addr);
+        let sink = InfluxDbSink::new(&url, Some("secret".to_string()), "atomic_bomb").unwrap();
+        sink.on_finish(&sample_batch_result()).await.unwrap();
+        let request = requests.recv().await.unwrap();
+        assert!(request.starts_with("POST /api/v2/write?org=test&bucket=test"));
+        assert!(request.contains("authorization: Token secret"));
+        assert!(request.contains("atomic_bomb,scope=total,stage=final total_requests=10,"));
+        assert!(request.contains("atomic_bomb,scope=api,stage=final,name=查询\\ 接口,method=GET "));
+        assert!(request.contains(" 1700000000000000000"));
+        // 没有数据的最小响应时间不写入
+        assert!(!request.contains(&format!("min_response_time={}", u64::MAX)));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod result_sink;
+pub mod influxdb_sink;
+pub mod statsd_sink;
+pub mod otlp_sink;
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use anyhow::Context;
+use futures::future::BoxFuture;
+use reqwest::Client;
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Value};
+
+use crate::models::result::BatchResult;
+use crate::sinks::result_sink::{api_metrics, batch_metrics, ResultSink, SinkStage};
+
+// 通过OTLP/HTTP(json编码)推送gauge指标
+// endpoint为完整的指标地址，例如: http://127.0.0.1:4318/v1/metrics
+pub struct OtlpSink {
+    client: Client,
+    endpoint: String,
+    headers: HashMap<String, String>,
+    service_name: String,
+}
+
+impl OtlpSink {
+    pub fn new(endpoint: &str, headers: Option<HashMap<String, String>>, service_name: &str) -> anyhow::Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .context("构建OTLP http客户端失败")?;
+        Ok(OtlpSink {
+            client,
+            endpoint: endpoint.to_string(),
+            headers: headers.unwrap_or_default(),
+            service_name: service_name.to_string(),
+        })
+    }
+
+    // 生成ExportMetricsServiceRequest的json结构
+    pub(crate) fn payload(&self, stage: SinkStage, result: &BatchResult) -> Value {
+        let time_unix_nano = (result.timestamp * 1_000_000).to_string();
+        // {指标名称: 数据点}
+        let mut data_points: Vec<(&'static str, Vec<Value>)> = Vec::new();
+        let mut push_point = |name: &'static str, value: f64, attributes: Vec<Value>| {
+            let point = json!({
+                "asDouble": value,
+                "timeUnixNano": time_unix_nano,
+                "attributes": attributes,
+            });
+            match data_points.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, points)) => points.push(point),
+                None => data_points.push((name, vec![point])),
+            }
+        };
+        for (name, value) in batch_metrics(result) {
+            push_point(name, value, vec![attribute("scope", "total"), attribute("stage", stage.as_str())]);
+        }
+        for api_result in &result.api_results {
+            for (name, value) in api_metrics(api_result) {
+                push_point(name, value, vec![
+                    attribute("scope", "api"),
+                    attribute("stage", stage.as_str()),
+                    attribute("api.name", &api_result.name),
+                    attribute("http.method", &api_result.method),
+                ]);
+            }
+        }
+        let metrics: Vec<Value> = data_points
+            .into_iter()
+            .map(|(name, points)| json!({
+                "name": format!("atomic_bomb.{}", name),
+                "gauge": { "dataPoints": points },
+            }))
+            .collect();
+        json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [attribute("service.name", &self.service_name)],
+                },
+                "scopeMetrics": [{
+                    "scope": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                    "metrics": metrics,
+                }],
+            }],
+        })
+    }
+
+    async fn write(&self, stage: SinkStage, result: &BatchResult) -> anyhow::Result<()> {
+        let mut request = self.client
+            .post(&self.endpoint)
+            .header(CONTENT_TYPE, "application/json")
+            .body(self.payload(stage, result).to_string());
+        for (k, v) in &self.headers {
+            request = request.header(k, v);
+        }
+        let response = request.send().await.context("推送OTLP指标失败")?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("推送OTLP指标失败，状态码: {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+impl ResultSink for OtlpSink {
+    fn on_interval<'a>(&'a self, result: &'a BatchResult) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(self.write(SinkStage::interval(result), result))
+    }
+
+    fn on_finish<'a>(&'a self, result: &'a BatchResult) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(self.write(SinkStage::Final, result))
+    }
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_server::spawn_http_server;
+    use crate::sinks::result_sink::sample_batch_result;
+
+    #[tokio::test]
+    async fn test_otlp_sink() {
+        let (addr, mut requests) = spawn_http_server(200, "{}").await;
+        let endpoint = format!("http://{}/v1/metrics", addr);
+        let mut headers = HashMap::new();
+        headers.insert("x-api-key".to_string(), "secret".to_string());
+        let sink = OtlpSink::new(&endpoint, Some(headers), "checkout-load").unwrap();
+        sink.on_interval(&sample_batch_result()).await.unwrap();
+        let request = requests.recv().await.unwrap();
+        assert!(request.starts_with("POST /v1/metrics"));
+        assert!(request.contains("x-api-key: secret"));
+        let body: Value = serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        let metrics = &body["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        let rps = metrics.as_array().unwrap().iter().find(|m| m["name"] == "atomic_bomb.rps").unwrap();
+        let points = rps["gauge"]["dataPoints"].as_array().unwrap();
+        // 汇总一个点，接口一个点
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0]["timeUnixNano"], "1700000000000000000");
+        assert_eq!(points[1]["attributes"][2]["value"]["stringValue"], "查询 接口");
+    }
+}
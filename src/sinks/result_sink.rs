@@ -0,0 +1,124 @@
+use futures::future::BoxFuture;
+use crate::models::result::{ApiResult, BatchResult};
+
+// 结果推送的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SinkStage {
+    // 预热期间的周期快照
+    WarmUp,
+    // 压测过程中的周期快照
+    Interval,
+    // 压测结束后的最终结果
+    Final,
+}
+
+impl SinkStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SinkStage::WarmUp => "warm_up",
+            SinkStage::Interval => "interval",
+            SinkStage::Final => "final",
+        }
+    }
+
+    // 周期快照的阶段，区分预热和正式统计
+    pub fn interval(result: &BatchResult) -> Self {
+        if result.warm_up { SinkStage::WarmUp } else { SinkStage::Interval }
+    }
+}
+
+// 结果推送目标，batch会在每个统计周期推送一次快照，压测结束后推送最终结果
+pub trait ResultSink: Send + Sync {
+    // 接收周期快照
+    fn on_interval<'a>(&'a self, result: &'a BatchResult) -> BoxFuture<'a, anyhow::Result<()>>;
+
+    // 接收最终结果，默认和周期快照一样处理
+    fn on_finish<'a>(&'a self, result: &'a BatchResult) -> BoxFuture<'a, anyhow::Result<()>> {
+        self.on_interval(result)
+    }
+}
+
+// 汇总指标，过滤掉还没有数据时的无效值
+pub(crate) fn batch_metrics(result: &BatchResult) -> Vec<(&'static str, f64)> {
+    finite_metrics(vec![
+        ("total_requests", result.total_requests as f64),
+        ("success_rate", result.success_rate),
+        ("error_rate", result.error_rate),
+        ("rps", result.rps),
+        ("median_response_time", result.median_response_time as f64),
+        ("response_time_95", result.response_time_95 as f64),
+        ("response_time_99", result.response_time_99 as f64),
+        ("max_response_time", result.max_response_time as f64),
+        ("min_response_time", min_response_time(result.min_response_time)),
+        ("err_count", result.err_count as f64),
+        ("total_data_kb", result.total_data_kb),
+        ("throughput_per_second_kb", result.throughput_per_second_kb),
+        ("concurrent_number", result.total_concurrent_number as f64),
+        ("interrupted_requests", result.interrupted_requests as f64),
+    ])
+}
+
+// 单个接口的指标
+pub(crate) fn api_metrics(api_result: &ApiResult) -> Vec<(&'static str, f64)> {
+    finite_metrics(vec![
+        ("total_requests", api_result.total_requests as f64),
+        ("success_rate", api_result.success_rate),
+        ("error_rate", api_result.error_rate),
+        ("rps", api_result.rps),
+        ("median_response_time", api_result.median_response_time as f64),
+        ("response_time_95", api_result.response_time_95 as f64),
+        ("response_time_99", api_result.response_time_99 as f64),
+        ("max_response_time", api_result.max_response_time as f64),
+        ("min_response_time", min_response_time(api_result.min_response_time)),
+        ("err_count", api_result.err_count as f64),
+        ("total_data_kb", api_result.total_data_kb),
+        ("throughput_per_second_kb", api_result.throughput_per_second_kb),
+        ("concurrent_number", api_result.concurrent_number as f64),
+        ("interrupted_requests", api_result.interrupted_requests as f64),
+    ])
+}
+
+// 没有请求时最小响应时间是u64::MAX，不推送
+fn min_response_time(value: u64) -> f64 {
+    if value == u64::MAX { f64::NAN } else { value as f64 }
+}
+
+fn finite_metrics(metrics: Vec<(&'static str, f64)>) -> Vec<(&'static str, f64)> {
+    metrics.into_iter().filter(|(_, value)| value.is_finite()).collect()
+}
+
+#[cfg(test)]
+pub(crate) fn sample_batch_result() -> BatchResult {
+    use std::collections::HashMap;
+    let mut api_result = ApiResult::new();
+    api_result.name = "查询 接口".to_string();
+    api_result.url = "http://127.0.0.1/api".to_string();
+    api_result.method = "GET".to_string();
+    api_result.total_requests = 10;
+    api_result.rps = 5.0;
+    api_result.success_rate = 100.0;
+    api_result.min_response_time = 3;
+    BatchResult {
+        total_duration: 2.0,
+        success_rate: 100.0,
+        error_rate: 0.0,
+        median_response_time: 5,
+        response_time_95: 9,
+        response_time_99: 12,
+        total_requests: 10,
+        rps: 5.0,
+        max_response_time: 12,
+        min_response_time: u64::MAX,
+        err_count: 0,
+        total_data_kb: 1.5,
+        throughput_per_second_kb: 0.75,
+        http_errors: HashMap::new(),
+        timestamp: 1_700_000_000_000,
+        assert_errors: HashMap::new(),
+        total_concurrent_number: 2,
+        api_results: vec![api_result],
+        end_reason: Default::default(),
+        warm_up: false,
+        interrupted_requests: 0,
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::Context;
+use futures::future::BoxFuture;
+use tokio::net::{lookup_host, UdpSocket};
+
+use crate::models::result::BatchResult;
+use crate::sinks::result_sink::{api_metrics, batch_metrics, ResultSink};
+
+// 单个udp包的最大长度，避免超过常见MTU被分片
+const MAX_PACKET_SIZE: usize = 1432;
+
+// 通过udp以gauge形式推送到StatsD
+// 汇总指标: {prefix}.{指标}，接口指标: {prefix}.api.{接口名称}.{指标}
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+}
+
+impl StatsdSink {
+    pub async fn new(addr: &str, prefix: &str) -> anyhow::Result<Self> {
+        let target = lookup_host(addr)
+            .await
+            .context("解析StatsD地址失败")?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("无法解析StatsD地址: {}", addr))?;
+        let bind_addr = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = UdpSocket::bind(bind_addr).await.context("绑定udp端口失败")?;
+        socket.connect(target).await.context("连接StatsD失败")?;
+        Ok(StatsdSink {
+            socket,
+            prefix: prefix.trim_end_matches('.').to_string(),
+        })
+    }
+
+    // 生成StatsD报文，每行一个gauge，预热期间的指标放在{prefix}.warm_up下
+    pub(crate) fn lines(&self, result: &BatchResult) -> Vec<String> {
+        let prefix = if result.warm_up { format!("{}.warm_up", self.prefix) } else { self.prefix.clone() };
+        let mut lines = Vec::new();
+        for (name, value) in batch_metrics(result) {
+            lines.push(format!("{}.{}:{}|g", prefix, name, value));
+        }
+        for api_result in &result.api_results {
+            let api_name = sanitize(&api_result.name);
+            for (name, value) in api_metrics(api_result) {
+                lines.push(format!("{}.api.{}.{}:{}|g", prefix, api_name, name, value));
+            }
+        }
+        lines
+    }
+
+    async fn write(&self, result: &BatchResult) -> anyhow::Result<()> {
+        let mut packet = String::new();
+        for line in self.lines(result) {
+            if !packet.is_empty() && packet.len() + line.len() + 1 > MAX_PACKET_SIZE {
+                self.socket.send(packet.as_bytes()).await.context("发送StatsD数据失败")?;
+                packet.clear();
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+        if !packet.is_empty() {
+            self.socket.send(packet.as_bytes()).await.context("发送StatsD数据失败")?;
+        }
+        Ok(())
+    }
+}
+
+impl ResultSink for StatsdSink {
+    fn on_interval<'a>(&'a self, result: &'a BatchResult) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(self.write(result))
+    }
+}
+
+// StatsD的指标名称中不能出现的字符统一替换为下划线
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c == '.' || c == ':' || c == '|' || c == '@' || c.is_whitespace() { '_' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::result_sink::sample_batch_result;
+
+    #[tokio::test]
+    async fn test_statsd_sink() {
+        let listener = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let sink = StatsdSink::new(&addr, "atomic_bomb.").await.unwrap();
+        sink.on_interval(&sample_batch_result()).await.unwrap();
+        let mut buffer = vec![0u8; MAX_PACKET_SIZE];
+        let n = listener.recv(&mut buffer).await.unwrap();
+        let packet = String::from_utf8_lossy(&buffer[..n]).to_string();
+        assert!(packet.starts_with("atomic_bomb.total_requests:10|g\n"));
+        assert!(packet.contains("atomic_bomb.api.查询_接口.rps:5|g"));
+        assert!(!packet.contains("atomic_bomb.min_response_time"));
+    }
+}